@@ -4,8 +4,7 @@ use async_compression::futures::bufread::GzipDecoder;
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
 use futures::{future, io::BufReader, AsyncBufReadExt, FutureExt, Stream, StreamExt, TryStreamExt};
 use http::{Method, StatusCode};
-use recycle::VecExt;
-use rusoto_s3::{GetObjectRequest, Object, S3Client, S3};
+use rusoto_s3::{GetObjectRequest, Object, S3};
 use serde::{Deserialize, Serialize};
 use serde_closure::FnMutNamed;
 use std::{
@@ -17,7 +16,7 @@ use amadeus_core::{
 };
 use amadeus_types::{Data, DateTime, IpAddr, Url};
 
-use super::{list, retry, AwsCredentials, AwsError, AwsRegion, Ref, RUSOTO_DISPATCHER};
+use super::{list, retry, AwsCredentials, AwsError, AwsRegion, S3Options};
 
 #[derive(Clone, Debug)]
 pub struct Cloudfront {
@@ -25,6 +24,7 @@ pub struct Cloudfront {
 	bucket: String,
 	objects: Vec<String>,
 	credentials: AwsCredentials,
+	options: S3Options,
 }
 impl Cloudfront {
 	pub async fn new(region: AwsRegion, bucket: &str, prefix: &str) -> Result<Self, AwsError> {
@@ -32,23 +32,34 @@ impl Cloudfront {
 	}
 	pub async fn new_with(
 		region: AwsRegion, bucket: &str, prefix: &str, credentials: AwsCredentials,
+	) -> Result<Self, AwsError> {
+		Self::new_with_options(region, bucket, prefix, credentials, S3Options::new()).await
+	}
+	/// Connect with `options`, for example to use an S3-compatible store or a
+	/// different [`RetryPolicy`](super::RetryPolicy).
+	pub async fn new_with_options(
+		region: AwsRegion, bucket: &str, prefix: &str, credentials: AwsCredentials,
+		options: S3Options,
 	) -> Result<Self, AwsError> {
 		let (bucket, prefix) = (bucket.to_owned(), prefix.to_owned());
-		let client = S3Client::new_with(
-			Ref(&*RUSOTO_DISPATCHER),
-			credentials.clone(),
-			region.clone(),
-		);
+		let client = options.client(region.clone(), credentials.clone());
 
-		let objects = list(&client, &bucket, &prefix)
+		let objects = list(&client, &bucket, &prefix, options.retry_policy)
 			.await?
-			.map(|object: Object| object.key.unwrap());
+			.into_iter()
+			.map(|object: Object| {
+				object.key.ok_or_else(|| {
+					AwsError::ParseError(String::from("S3 API returned an object without a key"))
+				})
+			})
+			.collect::<Result<_, _>>()?;
 
 		Ok(Self {
 			region,
 			bucket,
 			objects,
 			credentials,
+			options,
 		})
 	}
 }
@@ -59,46 +70,43 @@ type Output = std::pin::Pin<Box<dyn Stream<Item = Result<CloudfrontRow, AwsError
 type Output = impl Stream<Item = Result<CloudfrontRow, AwsError>> + Send;
 
 FnMutNamed! {
-	pub type Closure<> = |self, credentials: AwsCredentials, region: AwsRegion, bucket: String|key=> String| -> Output where {
-		let (credentials, region, bucket) =
-			(self.credentials.clone(), self.region.clone(), self.bucket.clone());
+	pub type Closure<> = |self, credentials: AwsCredentials, region: AwsRegion, bucket: String, options: S3Options|key=> String| -> Output where {
+		let (credentials, region, bucket, options) =
+			(self.credentials.clone(), self.region.clone(), self.bucket.clone(), self.options.clone());
 		#[allow(clippy::let_and_return)]
 		let ret = async move {
-			let client = S3Client::new_with(
-				Ref(once_cell::sync::Lazy::force(&RUSOTO_DISPATCHER)),
-				credentials,
-				region,
-			);
-			let rows = retry(|| {
-				client.get_object(GetObjectRequest {
-					bucket: bucket.clone(),
-					key: key.clone(),
-					..GetObjectRequest::default()
+			let rows = async {
+				let client = options.client(region, credentials);
+				let res = retry(options.retry_policy, || {
+					client.get_object(GetObjectRequest {
+						bucket: bucket.clone(),
+						key: key.clone(),
+						..GetObjectRequest::default()
+					})
 				})
-			})
-			.await
-			.map_err(AwsError::from)
-			.map(|res| {
-				let body = BufReader::new(TryStreamExt::into_async_read(res.body.unwrap()));
+				.await?;
+				let body = res.body.ok_or_else(|| {
+					AwsError::ParseError(format!("S3 API returned no body for {:?}", key))
+				})?;
+				let body = BufReader::new(TryStreamExt::into_async_read(body));
 				let mut body = GzipDecoder::new(body); // Content-Encoding isn't set, so decode manually
 				body.multiple_members(true);
-				BufReader::new(body)
-					.lines()
-					.filter(|x: &Result<String, io::Error>| {
-						future::ready(if let Ok(x) = x {
-							x.chars().find(|x| !x.is_whitespace()) != Some('#')
-						} else {
-							true
+				Ok::<_, AwsError>(
+					BufReader::new(body)
+						.lines()
+						.filter(|x: &Result<String, io::Error>| {
+							future::ready(if let Ok(x) = x {
+								x.chars().find(|x| !x.is_whitespace()) != Some('#')
+							} else {
+								true
+							})
 						})
-					})
-					.then(|x: Result<String, io::Error>| async {
-						if let Ok(x) = x {
-							Ok(CloudfrontRow::from_line(&x))
-						} else {
-							Err(AwsError::from(x.err().unwrap()))
-						}
-					})
-			});
+						.map(|x: Result<String, io::Error>| {
+							x.map(|x| CloudfrontRow::from_line(&x)).map_err(AwsError::from)
+						}),
+				)
+			}
+			.await;
 			ResultExpandIter::new(rows)
 		}
 		.flatten_stream()
@@ -133,10 +141,11 @@ impl Source for Cloudfront {
 			region,
 			objects,
 			credentials,
+			options,
 		} = self;
 		objects
 			.into_dist_stream()
-			.flat_map(Closure::new(credentials, region, bucket))
+			.flat_map(Closure::new(credentials, region, bucket, options))
 	}
 }
 
@@ -1,18 +1,20 @@
 use async_trait::async_trait;
-use futures::{future, future::LocalBoxFuture, FutureExt};
+use futures::{future, future::LocalBoxFuture, lock::Mutex as AsyncMutex, FutureExt, TryStreamExt};
+use http::StatusCode;
 use rusoto_core::RusotoError;
-use rusoto_s3::{GetObjectRequest, HeadObjectRequest, S3Client, S3};
+use rusoto_s3::{
+	AbortMultipartUploadRequest, CompleteMultipartUploadRequest, CompletedMultipartUpload, CompletedPart, CreateMultipartUploadRequest, GetObjectRequest, HeadObjectRequest, PutObjectRequest, S3Client, UploadPartRequest, S3
+};
 use serde::{Deserialize, Serialize};
 use std::{
-	convert::{TryFrom, TryInto}, sync::Arc
+	collections::BTreeMap, convert::{TryFrom, TryInto}, io, mem, sync::{Arc, Mutex}
 };
-use tokio::io::AsyncReadExt;
 
 use amadeus_core::{
-	file::{Directory, File, Page, Partition, PathBuf}, util::IoError
+	file::{get_range, Directory, File, Glob, Page, Partition, PathBuf, SharedBuf}, util::IoError
 };
 
-use super::{retry, AwsCredentials, AwsError, AwsRegion, Ref, RUSOTO_DISPATCHER};
+use super::{retry, AwsCredentials, AwsError, AwsRegion, RetryPolicy, S3Options};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct S3Directory {
@@ -20,6 +22,10 @@ pub struct S3Directory {
 	bucket: String,
 	prefix: String,
 	credentials: AwsCredentials,
+	#[serde(default)]
+	options: S3Options,
+	#[serde(default)]
+	glob: Option<Glob>,
 }
 impl S3Directory {
 	pub fn new(region: AwsRegion, bucket: &str, prefix: &str) -> Self {
@@ -34,7 +40,49 @@ impl S3Directory {
 			bucket,
 			prefix,
 			credentials,
+			options: S3Options::new(),
+			glob: None,
+		}
+	}
+	/// The objects matching a glob pattern like `s3://bucket/logs/2020-0[1-3]-*/*.gz`. See
+	/// [`Glob`] for the syntax.
+	///
+	/// Listing is narrowed server-side to keys starting with the pattern's literal
+	/// [prefix](Glob::prefix), here `logs/2020-0`, and paths are relative to its
+	/// [root](Glob::root), here `logs/`.
+	pub fn glob(region: AwsRegion, pattern: &str) -> Result<Self, AwsError> {
+		Self::glob_with(region, pattern, AwsCredentials::Environment)
+	}
+	pub fn glob_with(
+		region: AwsRegion, pattern: &str, credentials: AwsCredentials,
+	) -> Result<Self, AwsError> {
+		let invalid = || AwsError::Validation(format!("invalid S3 URL: {}", pattern));
+		if !pattern.starts_with("s3://") {
+			return Err(invalid());
 		}
+		let pattern = &pattern["s3://".len()..];
+		let (bucket, pattern) = pattern.split_at(pattern.find('/').ok_or_else(invalid)?);
+		let glob = Glob::new(&pattern[1..]).map_err(|err| AwsError::Validation(err.to_string()))?;
+		let mut self_ = Self::new_with(region, bucket, glob.root(), credentials);
+		self_.glob = Some(glob);
+		Ok(self_)
+	}
+	/// Connect with `options`, for example to use an S3-compatible store.
+	pub fn with_options(mut self, options: S3Options) -> Self {
+		self.options = options;
+		self
+	}
+}
+impl S3Directory {
+	/// The object `key` under this directory's prefix, for example to [create](S3File::create) it.
+	pub fn file(&self, key: &str) -> S3File {
+		S3File::new_with(
+			self.region.clone(),
+			&self.bucket,
+			&format!("{}{}", self.prefix, key),
+			self.credentials.clone(),
+		)
+		.with_options(self.options.clone())
 	}
 }
 #[async_trait(?Send)]
@@ -50,64 +98,85 @@ impl Directory for S3Directory {
 			bucket,
 			prefix,
 			credentials,
+			options,
+			glob,
 		} = self;
-		let client = S3Client::new_with(
-			Ref(&*RUSOTO_DISPATCHER),
-			credentials.clone(),
-			region.clone(),
-		);
-		let objects = super::list(&client, &bucket, &prefix).await?;
+		let client = options.client(region.clone(), credentials.clone());
+		let list_prefix = glob.as_ref().map_or(&*prefix, Glob::prefix);
+		let objects = super::list(&client, &bucket, list_prefix, options.retry_policy).await?;
+		let mut f =
+			|path: &PathBuf| glob.as_ref().map_or(true, |glob| glob.is_match(path)) && f(path);
 
 		let mut current_path = PathBuf::new();
 		let mut skip = false;
 		let mut last_key: Option<String> = None;
-		objects
-			.into_iter()
-			.filter(|object| {
-				let key = object.key.as_ref().unwrap();
-				assert!(key.starts_with(&prefix));
-				let key = &key[prefix.len()..];
-				assert!(last_key.is_none() || **last_key.as_ref().unwrap() < *key, "S3 API not returning objects in \"UTF-8 character encoding in lexicographical order\" as their docs specify");
-				last_key = Some(key.to_owned());
-				let mut path = key.split('/').collect::<Vec<&str>>();
-				let file_name = path.pop().unwrap();
-				skip = skip
-					&& path.len() >= current_path.depth()
-					&& current_path.iter().eq(path
-						.iter()
-						.take(current_path.depth())
-						.copied());
-				if skip {
-					return false;
-				}
-				while current_path.depth() > path.len()
-					|| (current_path.depth() > 0
-						&& current_path.last().unwrap() != path[current_path.depth() - 1])
-				{
-					let _ = current_path.pop().unwrap();
-				}
-				while path.len() > current_path.depth() {
-					current_path.push(path[current_path.depth()]);
-					if !f(&current_path) {
-						skip = true;
-						return false;
-					}
+		let mut partitions = Vec::new();
+		for object in objects {
+			let key = object.key.ok_or_else(|| {
+				AwsError::ParseError(String::from("S3 API returned an object without a key"))
+			})?;
+			if !key.starts_with(&prefix) {
+				return Err(AwsError::ParseError(format!(
+					"S3 API returned key {:?} not under prefix {:?}",
+					key, prefix
+				)));
+			}
+			let relative = &key[prefix.len()..];
+			if last_key
+				.as_ref()
+				.map_or(false, |last_key| **last_key >= *relative)
+			{
+				return Err(AwsError::ParseError(String::from("S3 API not returning objects in \"UTF-8 character encoding in lexicographical order\" as their docs specify")));
+			}
+			last_key = Some(relative.to_owned());
+			let mut path = relative.split('/').collect::<Vec<&str>>();
+			let file_name = path.pop().unwrap();
+			skip = skip
+				&& path.len() >= current_path.depth()
+				&& current_path
+					.iter()
+					.eq(path.iter().take(current_path.depth()).copied());
+			if skip {
+				continue;
+			}
+			while current_path.depth() > path.len()
+				|| (current_path.depth() > 0
+					&& current_path.last().unwrap() != path[current_path.depth() - 1])
+			{
+				let _ = current_path.pop().unwrap();
+			}
+			while path.len() > current_path.depth() {
+				current_path.push(path[current_path.depth()]);
+				if !f(&current_path) {
+					skip = true;
+					break;
 				}
-				current_path.set_file_name(Some(file_name));
-				let ret = f(&current_path);
-				current_path.set_file_name::<Vec<u8>>(None);
-				ret
-			})
-			.map(|object| {
-				Ok(S3Partition {
-					region: region.clone(),
-					bucket: bucket.clone(),
-					key: object.key.unwrap(),
-					len: object.size.unwrap().try_into().unwrap(),
-					credentials: credentials.clone()
-				})
-			})
-			.collect()
+			}
+			if skip {
+				continue;
+			}
+			current_path.set_file_name(Some(file_name));
+			let matched = f(&current_path);
+			current_path.set_file_name::<Vec<u8>>(None);
+			if !matched {
+				continue;
+			}
+			let len = object
+				.size
+				.and_then(|size| size.try_into().ok())
+				.ok_or_else(|| {
+					AwsError::ParseError(format!("S3 API returned no valid size for {:?}", key))
+				})?;
+			partitions.push(S3Partition {
+				region: region.clone(),
+				bucket: bucket.clone(),
+				key,
+				len,
+				credentials: credentials.clone(),
+				options: options.clone(),
+			});
+		}
+		Ok(partitions)
 	}
 }
 
@@ -127,6 +196,8 @@ pub struct S3File {
 	bucket: String,
 	key: String,
 	credentials: AwsCredentials,
+	#[serde(default)]
+	options: S3Options,
 }
 impl S3File {
 	pub fn new(region: AwsRegion, bucket: &str, key: &str) -> Self {
@@ -141,8 +212,35 @@ impl S3File {
 			bucket,
 			key,
 			credentials,
+			options: S3Options::new(),
 		}
 	}
+	/// Connect with `options`, for example to use an S3-compatible store.
+	pub fn with_options(mut self, options: S3Options) -> Self {
+		self.options = options;
+		self
+	}
+}
+impl S3File {
+	/// A [`Page`] that creates this object, or replaces it if it exists, once
+	/// [flushed](Page::flush).
+	///
+	/// Writes must together cover the object contiguously from offset 0, but
+	/// can arrive in any order and concurrently. They're buffered into parts
+	/// that are uploaded in parallel with a multipart upload, which is aborted
+	/// if any part fails.
+	pub fn create(self) -> S3Page {
+		let client = self.options.client(self.region, self.credentials);
+		let retry_policy = self.options.retry_policy;
+		let inner = Arc::new(S3PageInner::new(
+			client,
+			retry_policy,
+			self.bucket,
+			self.key,
+			0,
+		));
+		S3Page { inner }
+	}
 }
 #[async_trait(?Send)]
 impl File for S3File {
@@ -159,9 +257,12 @@ impl Partition for S3File {
 	type Error = IoError;
 
 	async fn pages(self) -> Result<Vec<Self::Page>, Self::Error> {
-		Ok(vec![
-			S3Page::new(self.region, self.bucket, self.key, self.credentials).await,
-		])
+		let client = self.options.client(self.region, self.credentials);
+		let page = S3Page::new(client, self.options.retry_policy, self.bucket, self.key).await?;
+		Ok(vec![page])
+	}
+	fn name(&self) -> Option<String> {
+		Some(self.key.clone())
 	}
 }
 
@@ -172,6 +273,8 @@ pub struct S3Partition {
 	key: String,
 	len: u64,
 	credentials: AwsCredentials,
+	#[serde(default)]
+	options: S3Options,
 }
 #[async_trait(?Send)]
 impl Partition for S3Partition {
@@ -179,33 +282,205 @@ impl Partition for S3Partition {
 	type Error = IoError;
 
 	async fn pages(self) -> Result<Vec<Self::Page>, Self::Error> {
-		let client = S3Client::new_with(Ref(&*RUSOTO_DISPATCHER), self.credentials, self.region);
-		let (bucket, key, len) = (self.bucket, self.key, self.len);
-		let inner = Arc::new(S3PageInner {
-			client,
-			bucket,
-			key,
-			len,
-		});
+		let client = self.options.client(self.region, self.credentials);
+		let (retry_policy, bucket, key, len) =
+			(self.options.retry_policy, self.bucket, self.key, self.len);
+		let inner = Arc::new(S3PageInner::new(client, retry_policy, bucket, key, len));
 		Ok(vec![S3Page { inner }])
 	}
+	fn name(&self) -> Option<String> {
+		Some(self.key.clone())
+	}
 }
 
+/// The size of the parts of multipart uploads. S3 requires all but the last to
+/// be at least 5 MiB, and allows at most 10,000 of them.
+const PART_SIZE: usize = 16 * 1024 * 1024;
+
 struct S3PageInner {
 	client: S3Client,
+	retry_policy: RetryPolicy,
 	bucket: String,
 	key: String,
 	len: u64,
+	upload_id: AsyncMutex<Option<String>>,
+	write: Mutex<WriteState>,
+}
+#[derive(Default)]
+struct WriteState {
+	/// Writes that haven't yet been cut into parts, by offset.
+	pending: BTreeMap<u64, Box<[u8]>>,
+	/// The offset up to which writes have been cut into parts.
+	offset: u64,
+	next_part: i64,
+	parts: Vec<CompletedPart>,
+	/// Set once the upload has been aborted.
+	error: Option<IoError>,
+}
+impl WriteState {
+	/// Buffer a write, returning any parts that can now be uploaded.
+	fn push(&mut self, offset: u64, buf: Box<[u8]>) -> Result<Vec<(i64, Vec<u8>)>, IoError> {
+		if let Some(err) = &self.error {
+			return Err(err.clone());
+		}
+		if offset < self.offset || self.pending.contains_key(&offset) {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"S3 objects must be written contiguously, without overlapping writes",
+			)
+			.into());
+		}
+		let _ = self.pending.insert(offset, buf);
+		let mut parts = Vec::new();
+		while let Some(part) = self.cut(false) {
+			parts.push(part);
+		}
+		Ok(parts)
+	}
+	/// Cut contiguous pending writes into a part, if there are at least
+	/// `PART_SIZE` bytes of them or `all` is set.
+	fn cut(&mut self, all: bool) -> Option<(i64, Vec<u8>)> {
+		let (mut end, mut len, mut count) = (self.offset, 0, 0);
+		for (&offset, buf) in &self.pending {
+			if offset != end || (!all && len >= PART_SIZE) {
+				break;
+			}
+			end += u64::try_from(buf.len()).unwrap();
+			len += buf.len();
+			count += 1;
+		}
+		if !all && len < PART_SIZE {
+			return None;
+		}
+		let mut part = Vec::with_capacity(len);
+		for _ in 0..count {
+			let offset = *self.pending.keys().next().unwrap();
+			part.extend_from_slice(&self.pending.remove(&offset).unwrap());
+		}
+		self.offset = end;
+		self.next_part += 1;
+		Some((self.next_part, part))
+	}
 }
+
 pub struct S3Page {
 	inner: Arc<S3PageInner>,
 }
+impl S3PageInner {
+	fn new(
+		client: S3Client, retry_policy: RetryPolicy, bucket: String, key: String, len: u64,
+	) -> Self {
+		Self {
+			client,
+			retry_policy,
+			bucket,
+			key,
+			len,
+			upload_id: AsyncMutex::new(None),
+			write: Mutex::new(WriteState::default()),
+		}
+	}
+	/// The id of the multipart upload, starting it if necessary.
+	async fn upload_id(&self) -> Result<String, IoError> {
+		let mut upload_id = self.upload_id.lock().await;
+		if let Some(upload_id) = &*upload_id {
+			return Ok(upload_id.clone());
+		}
+		let res = retry(self.retry_policy, || {
+			self.client
+				.create_multipart_upload(CreateMultipartUploadRequest {
+					bucket: self.bucket.clone(),
+					key: self.key.clone(),
+					..CreateMultipartUploadRequest::default()
+				})
+		})
+		.await
+		.map_err(AwsError::from)?;
+		let id = res
+			.upload_id
+			.ok_or_else(|| AwsError::ParseError(String::from("missing upload id")))?;
+		*upload_id = Some(id.clone());
+		Ok(id)
+	}
+	async fn upload_part(&self, part_number: i64, part: Vec<u8>) -> Result<(), IoError> {
+		let upload_id = self.upload_id().await?;
+		let res = retry(self.retry_policy, || {
+			self.client.upload_part(UploadPartRequest {
+				bucket: self.bucket.clone(),
+				key: self.key.clone(),
+				upload_id: upload_id.clone(),
+				part_number,
+				content_length: Some(part.len().try_into().unwrap()),
+				body: Some(part.clone().into()),
+				..UploadPartRequest::default()
+			})
+		})
+		.await
+		.map_err(AwsError::from)?;
+		self.write.lock().unwrap().parts.push(CompletedPart {
+			e_tag: res.e_tag,
+			part_number: Some(part_number),
+		});
+		Ok(())
+	}
+	async fn put_object(&self, body: Vec<u8>) -> Result<(), IoError> {
+		let _ = retry(self.retry_policy, || {
+			self.client.put_object(PutObjectRequest {
+				bucket: self.bucket.clone(),
+				key: self.key.clone(),
+				content_length: Some(body.len().try_into().unwrap()),
+				body: Some(body.clone().into()),
+				..PutObjectRequest::default()
+			})
+		})
+		.await
+		.map_err(AwsError::from)?;
+		Ok(())
+	}
+	async fn complete(&self, mut parts: Vec<CompletedPart>) -> Result<(), IoError> {
+		let upload_id = self.upload_id().await?;
+		parts.sort_by_key(|part| part.part_number);
+		let _ = retry(self.retry_policy, || {
+			self.client
+				.complete_multipart_upload(CompleteMultipartUploadRequest {
+					bucket: self.bucket.clone(),
+					key: self.key.clone(),
+					upload_id: upload_id.clone(),
+					multipart_upload: Some(CompletedMultipartUpload {
+						parts: Some(parts.clone()),
+					}),
+					..CompleteMultipartUploadRequest::default()
+				})
+		})
+		.await
+		.map_err(AwsError::from)?;
+		*self.upload_id.lock().await = None;
+		Ok(())
+	}
+	/// Abort the multipart upload, so the parts uploaded so far aren't stored
+	/// (and billed for) indefinitely, and fail subsequent writes.
+	async fn abort(&self, err: IoError) -> IoError {
+		self.write.lock().unwrap().error = Some(err.clone());
+		if let Some(upload_id) = self.upload_id.lock().await.take() {
+			let _ = retry(self.retry_policy, || {
+				self.client
+					.abort_multipart_upload(AbortMultipartUploadRequest {
+						bucket: self.bucket.clone(),
+						key: self.key.clone(),
+						upload_id: upload_id.clone(),
+						..AbortMultipartUploadRequest::default()
+					})
+			})
+			.await;
+		}
+		err
+	}
+}
 impl S3Page {
 	async fn new(
-		region: AwsRegion, bucket: String, key: String, credentials: AwsCredentials,
-	) -> Self {
-		let client = S3Client::new_with(Ref(&*RUSOTO_DISPATCHER), credentials, region);
-		let object = retry(|| {
+		client: S3Client, retry_policy: RetryPolicy, bucket: String, key: String,
+	) -> Result<Self, AwsError> {
+		let object = retry(retry_policy, || {
 			client.head_object(HeadObjectRequest {
 				bucket: bucket.clone(),
 				key: key.clone(),
@@ -213,15 +488,19 @@ impl S3Page {
 			})
 		})
 		.await
-		.unwrap();
-		let len = object.content_length.unwrap().try_into().unwrap();
-		let inner = Arc::new(S3PageInner {
-			client,
-			bucket,
-			key,
-			len,
-		});
-		Self { inner }
+		.map_err(|err| match err {
+			// HEAD responses have no body to parse, so a missing object is a bare 404
+			RusotoError::Unknown(response) if response.status == StatusCode::NOT_FOUND => {
+				AwsError::NoSuchKey(key.clone())
+			}
+			err => err.into(),
+		})?;
+		let len = object
+			.content_length
+			.and_then(|len| u64::try_from(len).ok())
+			.ok_or_else(|| AwsError::ParseError(String::from("missing Content-Length")))?;
+		let inner = Arc::new(S3PageInner::new(client, retry_policy, bucket, key, len));
+		Ok(Self { inner })
 	}
 }
 impl Page for S3Page {
@@ -232,56 +511,100 @@ impl Page for S3Page {
 	}
 	fn read(
 		&self, offset: u64, len: usize,
-	) -> LocalBoxFuture<'static, Result<Box<[u8]>, Self::Error>> {
+	) -> LocalBoxFuture<'static, Result<SharedBuf, Self::Error>> {
 		let self_ = S3Page {
 			inner: self.inner.clone(),
 		};
 		Box::pin(async move {
-			let len = len.min(usize::try_from(self_.inner.len.saturating_sub(offset)).unwrap());
-			let mut buf_ = vec![0; len].into_boxed_slice();
-			let mut buf = &mut *buf_;
-			let len: u64 = len.try_into().unwrap();
-			let mut pos = 0_u64;
-			let mut errors: usize = 0;
-			let end = offset + len - 1;
-			while !buf.is_empty() {
-				let start = offset + pos;
-				assert_eq!(start, end + 1 - u64::try_from(buf.len()).unwrap()); // TODO
-				let res = self_.inner.client.get_object(GetObjectRequest {
-					bucket: self_.inner.bucket.clone(),
-					key: self_.inner.key.clone(),
-					range: Some(format!("bytes={}-{}", start, end)),
-					..GetObjectRequest::default()
-				});
-				let res = res.await;
+			let inner = &self_.inner;
+			let len = len.min(usize::try_from(inner.len.saturating_sub(offset)).unwrap());
+			if len == 0 {
+				return Ok(SharedBuf::new());
+			}
+			let end = offset + u64::try_from(len).unwrap() - 1;
+			let buf = get_range(inner.retry_policy, offset, len, |start| async move {
+				let res = retry(inner.retry_policy, || {
+					inner.client.get_object(GetObjectRequest {
+						bucket: inner.bucket.clone(),
+						key: inner.key.clone(),
+						range: Some(format!("bytes={}-{}", start, end)),
+						..GetObjectRequest::default()
+					})
+				})
+				.await?;
+				let body = res
+					.body
+					.ok_or_else(|| AwsError::ParseError(String::from("missing response body")))?;
+				Ok::<_, AwsError>((body.map_err(AwsError::from), start))
+			})
+			.await?;
+			Ok(buf.into())
+		})
+	}
+	fn write(
+		&self, offset: u64, buf: Box<[u8]>,
+	) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
+		let inner = self.inner.clone();
+		Box::pin(async move {
+			let res = inner.write.lock().unwrap().push(offset, buf);
+			let res = match res {
+				Ok(parts) => {
+					let uploads = parts
+						.into_iter()
+						.map(|(part_number, part)| inner.upload_part(part_number, part));
+					future::try_join_all(uploads).await.map(drop)
+				}
+				Err(err) => Err(err),
+			};
+			match res {
+				Ok(()) => Ok(()),
+				Err(err) => Err(inner.abort(err).await),
+			}
+		})
+	}
+	fn flush(&self) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
+		let inner = self.inner.clone();
+		Box::pin(async move {
+			let (part_number, part) = {
+				let mut write = inner.write.lock().unwrap();
+				if let Some(err) = &write.error {
+					return Err(err.clone());
+				}
+				let last = write.cut(true).unwrap();
+				if !write.pending.is_empty() {
+					drop(write);
+					let err = io::Error::new(
+						io::ErrorKind::InvalidInput,
+						"S3 objects must be written contiguously from offset 0",
+					);
+					return Err(inner.abort(err.into()).await);
+				}
+				last
+			};
+			let res = if part_number == 1 {
+				// Small enough to not need a multipart upload
+				inner.put_object(part).await
+			} else {
+				let res = if !part.is_empty() {
+					inner.upload_part(part_number, part).await
+				} else {
+					Ok(())
+				};
 				match res {
-					Err(RusotoError::HttpDispatch(_)) if errors < 10 => {
-						errors += 1;
-						continue;
-					}
-					Err(RusotoError::Unknown(response))
-						if response.status.is_server_error() && errors < 10 =>
-					{
-						errors += 1;
-						continue;
+					Ok(()) => {
+						let parts = mem::take(&mut inner.write.lock().unwrap().parts);
+						inner.complete(parts).await
 					}
-					_ => (),
+					Err(err) => Err(err),
 				}
-				let mut read = res.unwrap().body.unwrap().into_async_read();
-				while !buf.is_empty() {
-					let res = read.read_buf(&mut buf).await;
-					match res {
-						Ok(0) | Err(_) => break,
-						Ok(n) => pos += u64::try_from(n).unwrap(),
-					}
+			};
+			match res {
+				Ok(()) => {
+					*inner.write.lock().unwrap() = WriteState::default();
+					Ok(())
 				}
+				Err(err) => Err(inner.abort(err).await),
 			}
-			Ok(buf_)
 		})
 	}
-	fn write(
-		&self, _offset: u64, _buf: Box<[u8]>,
-	) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
-		todo!("Tracking at https://github.com/constellation-rs/amadeus/issues/61")
-	}
 }
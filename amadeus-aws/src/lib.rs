@@ -34,13 +34,18 @@ mod cloudfront;
 mod file;
 
 use async_trait::async_trait;
-use futures::{stream, StreamExt, TryFutureExt, TryStreamExt};
+use futures::{stream, StreamExt, TryStreamExt};
+use http::StatusCode;
+use hyper::client::HttpConnector;
+use hyper_tls::HttpsConnector;
 use once_cell::sync::Lazy;
 use rusoto_core::{
 	credential::StaticProvider, request::{DispatchSignedRequest, DispatchSignedRequestFuture, HttpClient}, signature::SignedRequest, RusotoError
 };
 use rusoto_credential::{CredentialsError, DefaultCredentialsProvider, ProvideAwsCredentials};
-use rusoto_s3::{GetObjectError, ListObjectsV2Error, ListObjectsV2Request, Object, S3Client, S3};
+use rusoto_s3::{
+	CompleteMultipartUploadError, CreateMultipartUploadError, GetObjectError, HeadObjectError, ListObjectsV2Error, ListObjectsV2Request, Object, PutObjectError, S3Client, UploadPartError, S3
+};
 use serde::{Deserialize, Serialize};
 use std::{
 	error, fmt::{self, Display}, future::Future, io, ops::FnMut, time::Duration
@@ -48,10 +53,12 @@ use std::{
 
 use amadeus_core::util::{IoError, ResultExpand};
 
+#[doc(inline)]
+pub use amadeus_core::file::RetryPolicy;
 #[doc(inline)]
 pub use cloudfront::{Cloudfront, CloudfrontRow};
 #[doc(inline)]
-pub use file::{S3Directory, S3File};
+pub use file::{S3Directory, S3File, S3Page};
 #[doc(inline)]
 pub use rusoto_core::Region as AwsRegion;
 
@@ -59,29 +66,35 @@ pub use rusoto_core::Region as AwsRegion;
 
 static RUSOTO_DISPATCHER: Lazy<HttpClient> =
 	Lazy::new(|| HttpClient::new().expect("failed to create request dispatcher"));
+static RUSOTO_DISPATCHER_INSECURE: Lazy<HttpClient> = Lazy::new(|| {
+	let mut http = HttpConnector::new();
+	http.enforce_http(false);
+	let tls = native_tls::TlsConnector::builder()
+		.danger_accept_invalid_certs(true)
+		.danger_accept_invalid_hostnames(true)
+		.build()
+		.expect("failed to create request dispatcher");
+	HttpClient::from_connector(HttpsConnector::from((http, tls.into())))
+});
 static RUSOTO_CREDENTIALS_PROVIDER: Lazy<DefaultCredentialsProvider> =
 	Lazy::new(|| DefaultCredentialsProvider::new().expect("failed to create credentials provider"));
 
-fn retry<F, FU, T, S>(f: F) -> impl Future<Output = Result<T, RusotoError<S>>>
+fn retry<F, FU, T, S>(policy: RetryPolicy, f: F) -> impl Future<Output = Result<T, RusotoError<S>>>
 where
-	F: FnMut() -> FU + Unpin,
+	F: FnMut() -> FU,
 	FU: Future<Output = Result<T, RusotoError<S>>>,
 {
-	futures_retry::FutureRetry::new(f, |err| match err {
-		RusotoError::HttpDispatch(_) => {
-			futures_retry::RetryPolicy::WaitRetry(std::time::Duration::from_millis(10))
+	policy.retry(f, |err| match err {
+		RusotoError::HttpDispatch(_) => true,
+		RusotoError::Unknown(response) => {
+			response.status.is_server_error() || response.status == StatusCode::TOO_MANY_REQUESTS
 		}
-		RusotoError::Unknown(response) if response.status.is_server_error() => {
-			futures_retry::RetryPolicy::WaitRetry(std::time::Duration::from_millis(10))
-		}
-		e => futures_retry::RetryPolicy::ForwardError(e),
+		_ => false,
 	})
-	.map_ok(|(x, _)| x)
-	.map_err(|(x, _)| x)
 }
 
 async fn list(
-	client: &S3Client, bucket: &str, prefix: &str,
+	client: &S3Client, bucket: &str, prefix: &str, policy: RetryPolicy,
 ) -> Result<Vec<Object>, RusotoError<ListObjectsV2Error>> {
 	let (first, continuation_token) = (true, None);
 	let objects: Result<Vec<Object>, _> = stream::unfold(
@@ -93,7 +106,7 @@ async fn list(
 			first = false;
 			Some((
 				stream::iter(ResultExpand(
-					retry(|| {
+					retry(policy, || {
 						client.list_objects_v2(ListObjectsV2Request {
 							bucket: bucket.to_owned(),
 							prefix: Some(prefix.to_owned()),
@@ -135,6 +148,79 @@ where
 	}
 }
 
+/// How to connect to S3 or an S3-compatible store such as MinIO, Ceph or R2.
+///
+/// These are serialized along with the files they're attached to, so they
+/// apply on remote workers too.
+///
+/// Buckets are always addressed path-style, as `endpoint/bucket/key`, including
+/// against AWS itself. Virtual-hosted-style addressing, as `bucket.endpoint/key`,
+/// isn't supported, so stores that only accept it can't be used.
+///
+/// ```
+/// # use amadeus_aws::S3Options;
+/// let options = S3Options::new().endpoint("https://localhost:9000").verify_tls(false);
+/// ```
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct S3Options {
+	endpoint: Option<String>,
+	verify_tls: bool,
+	#[serde(default)]
+	retry_policy: RetryPolicy,
+}
+impl S3Options {
+	/// The default options: the region's AWS endpoint, TLS certificates
+	/// verified, and the default [`RetryPolicy`].
+	pub fn new() -> Self {
+		Self {
+			endpoint: None,
+			verify_tls: true,
+			retry_policy: RetryPolicy::new(),
+		}
+	}
+	/// Send requests to `endpoint`, e.g. `http://localhost:9000`, rather than
+	/// the region's AWS endpoint. The region's name is still used to sign
+	/// requests.
+	pub fn endpoint(mut self, endpoint: &str) -> Self {
+		self.endpoint = Some(endpoint.to_owned());
+		self
+	}
+	/// Whether to verify the endpoint's TLS certificate and hostname. Disabling
+	/// this is useful for stores with self-signed certificates, but leaves
+	/// connections open to interception.
+	pub fn verify_tls(mut self, verify_tls: bool) -> Self {
+		self.verify_tls = verify_tls;
+		self
+	}
+	/// How failed requests are retried. Requests that fail to dispatch, that
+	/// are throttled, or that hit a server error are retried; other errors,
+	/// like a missing object or a denied request, are returned immediately.
+	pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+		self.retry_policy = retry_policy;
+		self
+	}
+	fn client(&self, region: AwsRegion, credentials: AwsCredentials) -> S3Client {
+		let region = match &self.endpoint {
+			Some(endpoint) => AwsRegion::Custom {
+				name: region.name().to_owned(),
+				endpoint: endpoint.clone(),
+			},
+			None => region,
+		};
+		let dispatcher = if self.verify_tls {
+			&RUSOTO_DISPATCHER
+		} else {
+			&RUSOTO_DISPATCHER_INSECURE
+		};
+		S3Client::new_with(Ref(Lazy::force(dispatcher)), credentials, region)
+	}
+}
+impl Default for S3Options {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
 pub enum AwsCredentials {
 	Anonymous,
@@ -240,6 +326,14 @@ impl From<io::Error> for AwsError {
 		Self::Io(err.into())
 	}
 }
+impl From<AwsError> for IoError {
+	fn from(err: AwsError) -> Self {
+		match err {
+			AwsError::Io(err) => err,
+			err => io::Error::new(io::ErrorKind::Other, err).into(),
+		}
+	}
+}
 impl<E> From<RusotoError<E>> for AwsError
 where
 	E: Into<AwsError>,
@@ -270,3 +364,30 @@ impl From<GetObjectError> for AwsError {
 		}
 	}
 }
+impl From<HeadObjectError> for AwsError {
+	fn from(err: HeadObjectError) -> Self {
+		match err {
+			HeadObjectError::NoSuchKey(err) => Self::NoSuchKey(err),
+		}
+	}
+}
+impl From<CreateMultipartUploadError> for AwsError {
+	fn from(err: CreateMultipartUploadError) -> Self {
+		match err {}
+	}
+}
+impl From<UploadPartError> for AwsError {
+	fn from(err: UploadPartError) -> Self {
+		match err {}
+	}
+}
+impl From<CompleteMultipartUploadError> for AwsError {
+	fn from(err: CompleteMultipartUploadError) -> Self {
+		match err {}
+	}
+}
+impl From<PutObjectError> for AwsError {
+	fn from(err: PutObjectError) -> Self {
+		match err {}
+	}
+}
@@ -0,0 +1,515 @@
+use async_trait::async_trait;
+use futures::{future, future::LocalBoxFuture, FutureExt, TryStreamExt};
+use reqwest::{header, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap, convert::TryFrom, io, sync::{Arc, Mutex}
+};
+use url::Url;
+
+use amadeus_core::{
+	file::{get_range, Directory, File, Page, Partition, PathBuf, RetryPolicy, SharedBuf}, util::IoError
+};
+
+use super::{error, Account, AzureCredentials, Element, CLIENT};
+
+/// The blobs in an Azure Blob Storage container whose names start with a prefix.
+///
+/// Paths passed to [`partitions_filter`](Directory::partitions_filter) are
+/// the blob names after the prefix, split on `/`. Virtual directories that
+/// are filtered out aren't listed.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct AzureDirectory {
+	account: Account,
+	container: String,
+	prefix: String,
+}
+impl AzureDirectory {
+	pub fn new(account: &str, container: &str, prefix: &str) -> Self {
+		Self::new_with(account, container, prefix, AzureCredentials::Environment)
+	}
+	pub fn new_with(
+		account: &str, container: &str, prefix: &str, credentials: AzureCredentials,
+	) -> Self {
+		let account = Account {
+			name: account.to_owned(),
+			endpoint: None,
+			credentials,
+		};
+		let (container, prefix) = (container.to_owned(), prefix.to_owned());
+		Self {
+			account,
+			container,
+			prefix,
+		}
+	}
+	/// Connect to `endpoint` rather than `https://<account>.blob.core.windows.net`,
+	/// for example `http://127.0.0.1:10000/devstoreaccount1` for Azurite.
+	pub fn endpoint(mut self, endpoint: Url) -> Self {
+		self.account.endpoint = Some(endpoint);
+		self
+	}
+	/// The blob `name` under this directory's prefix, for example to
+	/// [create](AzureFile::create) it.
+	pub fn file(&self, name: &str) -> AzureFile {
+		AzureFile {
+			account: self.account.clone(),
+			container: self.container.clone(),
+			name: format!("{}{}", self.prefix, name),
+		}
+	}
+}
+#[async_trait(?Send)]
+impl Directory for AzureDirectory {
+	async fn partitions_filter<F>(
+		self, mut f: F,
+	) -> Result<Vec<<Self as File>::Partition>, <Self as File>::Error>
+	where
+		F: FnMut(&PathBuf) -> bool,
+	{
+		let mut partitions = Vec::new();
+		let mut path = PathBuf::new();
+		walk(
+			&self.account,
+			&self.container,
+			&self.prefix,
+			&mut path,
+			&mut f,
+			&mut partitions,
+		)
+		.await?;
+		Ok(partitions)
+	}
+}
+#[async_trait(?Send)]
+impl File for AzureDirectory {
+	type Partition = AzurePartition;
+	type Error = IoError;
+
+	async fn partitions(self) -> Result<Vec<Self::Partition>, Self::Error> {
+		self.partitions_filter(|_| true).await
+	}
+}
+
+/// List the blobs and virtual directories directly under `prefix`, in name order.
+async fn list(
+	account: &Account, container: &str, prefix: &str,
+) -> Result<Vec<(String, Option<u64>)>, IoError> {
+	let mut entries = Vec::new();
+	let mut marker: Option<String> = None;
+	loop {
+		let mut url = account.url(container, None);
+		{
+			let mut query = url.query_pairs_mut();
+			let _ = query
+				.append_pair("restype", "container")
+				.append_pair("comp", "list")
+				.append_pair("prefix", prefix)
+				.append_pair("delimiter", "/");
+			if let Some(marker) = &marker {
+				let _ = query.append_pair("marker", marker);
+			}
+		}
+		let res = account.send(|| CLIENT.get(url.clone())).await?;
+		let body = res.bytes().await.map_err(error)?;
+		let body = Element::parse(&body)?;
+		let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid blob listing");
+		let blobs = body.child("Blobs").ok_or_else(invalid)?;
+		for blob in blobs.children("Blob") {
+			let name = blob.child("Name").and_then(Element::name);
+			let len = blob
+				.child("Properties")
+				.and_then(|properties| properties.child("Content-Length"))
+				.and_then(|len| len.text.parse().ok());
+			match (name, len) {
+				(Some(name), Some(len)) => entries.push((name, Some(len))),
+				_ => return Err(invalid().into()),
+			}
+		}
+		for blob_prefix in blobs.children("BlobPrefix") {
+			let name = blob_prefix
+				.child("Name")
+				.and_then(Element::name)
+				.ok_or_else(invalid)?;
+			entries.push((name, None));
+		}
+		marker = body
+			.child("NextMarker")
+			.map(|marker| marker.text.clone())
+			.filter(|marker| !marker.is_empty());
+		if marker.is_none() {
+			break;
+		}
+	}
+	entries.sort();
+	Ok(entries)
+}
+
+/// List the blobs under `prefix`, at `path` relative to the directory being
+/// listed, descending into the virtual directories that `f` accepts.
+fn walk<'a, F>(
+	account: &'a Account, container: &'a str, prefix: &'a str, path: &'a mut PathBuf, f: &'a mut F,
+	partitions: &'a mut Vec<AzurePartition>,
+) -> LocalBoxFuture<'a, Result<(), IoError>>
+where
+	F: FnMut(&PathBuf) -> bool,
+{
+	Box::pin(async move {
+		for (name, len) in list(account, container, prefix).await? {
+			let component = &name[prefix.len()..];
+			match len {
+				None => {
+					path.push(component.trim_end_matches('/'));
+					if f(&*path) {
+						walk(
+							account,
+							container,
+							&name,
+							&mut *path,
+							&mut *f,
+							&mut *partitions,
+						)
+						.await?;
+					}
+					let _ = path.pop();
+				}
+				Some(len) => {
+					path.set_file_name(Some(component));
+					if f(&*path) {
+						partitions.push(AzurePartition {
+							account: account.clone(),
+							container: container.to_owned(),
+							name: name.clone(),
+							len,
+						});
+					}
+					path.set_file_name::<&str>(None);
+				}
+			}
+		}
+		Ok(())
+	})
+}
+
+/// A blob in Azure Blob Storage.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct AzureFile {
+	account: Account,
+	container: String,
+	name: String,
+}
+impl AzureFile {
+	pub fn new(account: &str, container: &str, name: &str) -> Self {
+		Self::new_with(account, container, name, AzureCredentials::Environment)
+	}
+	pub fn new_with(
+		account: &str, container: &str, name: &str, credentials: AzureCredentials,
+	) -> Self {
+		let account = Account {
+			name: account.to_owned(),
+			endpoint: None,
+			credentials,
+		};
+		let (container, name) = (container.to_owned(), name.to_owned());
+		Self {
+			account,
+			container,
+			name,
+		}
+	}
+	/// Connect to `endpoint` rather than `https://<account>.blob.core.windows.net`,
+	/// for example `http://127.0.0.1:10000/devstoreaccount1` for Azurite.
+	pub fn endpoint(mut self, endpoint: Url) -> Self {
+		self.account.endpoint = Some(endpoint);
+		self
+	}
+	/// A [`Page`] that creates this blob as a block blob, or replaces it if it
+	/// exists, once [flushed](Page::flush).
+	///
+	/// Writes must together cover the blob contiguously from offset 0, but
+	/// can arrive in any order and concurrently. They're buffered into blocks
+	/// that are uploaded in parallel, and committed on flush.
+	pub fn create(self) -> AzurePage {
+		let inner = AzurePageInner::new(self.account, self.container, self.name, 0);
+		AzurePage {
+			inner: Arc::new(inner),
+		}
+	}
+}
+#[async_trait(?Send)]
+impl File for AzureFile {
+	type Partition = AzurePartition;
+	type Error = IoError;
+
+	async fn partitions(self) -> Result<Vec<Self::Partition>, Self::Error> {
+		let url = self.account.url(&self.container, Some(&self.name));
+		let res = self.account.send(|| CLIENT.head(url.clone())).await?;
+		// Not `Response::content_length`, which is that of the (empty) body
+		let len = res
+			.headers()
+			.get(header::CONTENT_LENGTH)
+			.and_then(|len| len.to_str().ok()?.parse().ok())
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+		Ok(vec![AzurePartition {
+			account: self.account,
+			container: self.container,
+			name: self.name,
+			len,
+		}])
+	}
+}
+
+/// Finds the blob's length and then reads it as its one [`AzurePartition`] would.
+#[async_trait(?Send)]
+impl Partition for AzureFile {
+	type Page = AzurePage;
+	type Error = IoError;
+
+	async fn pages(self) -> Result<Vec<Self::Page>, Self::Error> {
+		let partition = self.partitions().await?.pop().unwrap();
+		partition.pages().await
+	}
+	fn name(&self) -> Option<String> {
+		Some(self.name.clone())
+	}
+}
+
+/// A blob in Azure Blob Storage, of known length.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct AzurePartition {
+	account: Account,
+	container: String,
+	name: String,
+	len: u64,
+}
+#[async_trait(?Send)]
+impl Partition for AzurePartition {
+	type Page = AzurePage;
+	type Error = IoError;
+
+	async fn pages(self) -> Result<Vec<Self::Page>, Self::Error> {
+		let inner = AzurePageInner::new(self.account, self.container, self.name, self.len);
+		Ok(vec![AzurePage {
+			inner: Arc::new(inner),
+		}])
+	}
+	fn name(&self) -> Option<String> {
+		Some(self.name.clone())
+	}
+}
+
+/// The size of the blocks of block blobs. Azure allows at most 50,000 of them.
+const BLOCK_SIZE: usize = 16 * 1024 * 1024;
+
+struct AzurePageInner {
+	account: Account,
+	url: Url,
+	len: u64,
+	write: Mutex<WriteState>,
+}
+#[derive(Default)]
+struct WriteState {
+	/// Writes that haven't yet been cut into blocks, by offset.
+	pending: BTreeMap<u64, Box<[u8]>>,
+	/// The offset up to which writes have been cut into blocks.
+	offset: u64,
+	blocks: usize,
+	/// Set once a block has failed to upload.
+	error: Option<IoError>,
+}
+impl WriteState {
+	/// Buffer a write, returning any blocks that can now be uploaded.
+	fn push(&mut self, offset: u64, buf: Box<[u8]>) -> Result<Vec<(usize, Vec<u8>)>, IoError> {
+		if let Some(err) = &self.error {
+			return Err(err.clone());
+		}
+		if offset < self.offset || self.pending.contains_key(&offset) {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"blobs must be written contiguously, without overlapping writes",
+			)
+			.into());
+		}
+		let _ = self.pending.insert(offset, buf);
+		let mut blocks = Vec::new();
+		while let Some(block) = self.cut(false) {
+			blocks.push(block);
+		}
+		Ok(blocks)
+	}
+	/// Cut contiguous pending writes into a block, if there are at least
+	/// `BLOCK_SIZE` bytes of them or `all` is set.
+	fn cut(&mut self, all: bool) -> Option<(usize, Vec<u8>)> {
+		let (mut end, mut len, mut count) = (self.offset, 0, 0);
+		for (&offset, buf) in &self.pending {
+			if offset != end || (!all && len >= BLOCK_SIZE) {
+				break;
+			}
+			end += u64::try_from(buf.len()).unwrap();
+			len += buf.len();
+			count += 1;
+		}
+		if !all && len < BLOCK_SIZE {
+			return None;
+		}
+		let mut block = Vec::with_capacity(len);
+		for _ in 0..count {
+			let offset = *self.pending.keys().next().unwrap();
+			block.extend_from_slice(&self.pending.remove(&offset).unwrap());
+		}
+		self.offset = end;
+		self.blocks += 1;
+		Some((self.blocks - 1, block))
+	}
+}
+/// Block ids must all be the same length within a blob.
+fn block_id(block: usize) -> String {
+	base64::encode(format!("{:010}", block))
+}
+impl AzurePageInner {
+	fn new(account: Account, container: String, name: String, len: u64) -> Self {
+		let url = account.url(&container, Some(&name));
+		Self {
+			account,
+			url,
+			len,
+			write: Mutex::new(WriteState::default()),
+		}
+	}
+	async fn put_block(&self, block: usize, data: Vec<u8>) -> Result<(), IoError> {
+		let mut url = self.url.clone();
+		let _ = url
+			.query_pairs_mut()
+			.append_pair("comp", "block")
+			.append_pair("blockid", &block_id(block));
+		let _ = self
+			.account
+			.send(|| CLIENT.put(url.clone()).body(data.clone()))
+			.await?;
+		Ok(())
+	}
+	async fn put_block_list(&self, blocks: usize) -> Result<(), IoError> {
+		let mut url = self.url.clone();
+		let _ = url.query_pairs_mut().append_pair("comp", "blocklist");
+		let mut body = String::from(r#"<?xml version="1.0" encoding="utf-8"?><BlockList>"#);
+		for block in 0..blocks {
+			body.push_str(&format!("<Latest>{}</Latest>", block_id(block)));
+		}
+		body.push_str("</BlockList>");
+		let _ = self
+			.account
+			.send(|| CLIENT.put(url.clone()).body(body.clone()))
+			.await?;
+		Ok(())
+	}
+	async fn put_blob(&self, data: Vec<u8>) -> Result<(), IoError> {
+		let _ = self
+			.account
+			.send(|| {
+				CLIENT
+					.put(self.url.clone())
+					.header("x-ms-blob-type", "BlockBlob")
+					.body(data.clone())
+			})
+			.await?;
+		Ok(())
+	}
+	/// Fail subsequent writes. Uncommitted blocks are discarded by Azure after
+	/// a week.
+	fn abort(&self, err: IoError) -> IoError {
+		self.write.lock().unwrap().error = Some(err.clone());
+		err
+	}
+}
+
+pub struct AzurePage {
+	inner: Arc<AzurePageInner>,
+}
+impl Page for AzurePage {
+	type Error = IoError;
+
+	fn len(&self) -> LocalBoxFuture<'static, Result<u64, Self::Error>> {
+		future::ready(Ok(self.inner.len)).boxed_local()
+	}
+	fn read(
+		&self, offset: u64, len: usize,
+	) -> LocalBoxFuture<'static, Result<SharedBuf, Self::Error>> {
+		let inner = self.inner.clone();
+		Box::pin(async move {
+			let len = len.min(usize::try_from(inner.len.saturating_sub(offset)).unwrap());
+			if len == 0 {
+				return Ok(SharedBuf::new());
+			}
+			let end = offset + u64::try_from(len).unwrap() - 1;
+			let inner = &inner;
+			let buf = get_range(RetryPolicy::default(), offset, len, |start| async move {
+				let res = inner
+					.account
+					.send(|| {
+						CLIENT
+							.get(inner.url.clone())
+							.header("x-ms-range", format!("bytes={}-{}", start, end))
+					})
+					.await?;
+				if res.status() != StatusCode::PARTIAL_CONTENT {
+					return Err(io::Error::new(
+						io::ErrorKind::InvalidData,
+						"Azure ignored the requested range",
+					)
+					.into());
+				}
+				Ok((res.bytes_stream().map_err(error), start))
+			})
+			.await?;
+			Ok(buf.into())
+		})
+	}
+	fn write(
+		&self, offset: u64, buf: Box<[u8]>,
+	) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
+		let inner = self.inner.clone();
+		Box::pin(async move {
+			let blocks = inner.write.lock().unwrap().push(offset, buf)?;
+			let uploads = blocks
+				.into_iter()
+				.map(|(block, data)| inner.put_block(block, data));
+			future::try_join_all(uploads)
+				.await
+				.map(drop)
+				.map_err(|err| inner.abort(err))
+		})
+	}
+	fn flush(&self) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
+		let inner = self.inner.clone();
+		Box::pin(async move {
+			let (block, data) = {
+				let mut write = inner.write.lock().unwrap();
+				if let Some(err) = &write.error {
+					return Err(err.clone());
+				}
+				let last = write.cut(true).unwrap();
+				if !write.pending.is_empty() {
+					drop(write);
+					let err = io::Error::new(
+						io::ErrorKind::InvalidInput,
+						"blobs must be written contiguously from offset 0",
+					);
+					return Err(inner.abort(err.into()));
+				}
+				last
+			};
+			let res = if block == 0 {
+				// Small enough to not need blocks
+				inner.put_blob(data).await
+			} else if data.is_empty() {
+				inner.put_block_list(block).await
+			} else {
+				match inner.put_block(block, data).await {
+					Ok(()) => inner.put_block_list(block + 1).await,
+					Err(err) => Err(err),
+				}
+			};
+			res.map_err(|err| inner.abort(err))
+		})
+	}
+}
@@ -0,0 +1,336 @@
+//! Harmonious distributed data processing & analysis in Rust.
+//!
+//! <p style="font-family: 'Fira Sans',sans-serif;padding:0.3em 0"><strong>
+//! <a href="https://crates.io/crates/amadeus">📦&nbsp;&nbsp;Crates.io</a>&nbsp;&nbsp;│&nbsp;&nbsp;<a href="https://github.com/constellation-rs/amadeus">📑&nbsp;&nbsp;GitHub</a>&nbsp;&nbsp;│&nbsp;&nbsp;<a href="https://constellation.zulipchat.com/#narrow/stream/213231-amadeus">💬&nbsp;&nbsp;Chat</a>
+//! </strong></p>
+//!
+//! This is a support crate of [Amadeus](https://github.com/constellation-rs/amadeus) and is not intended to be used directly. These types are re-exposed in [`amadeus::source`](https://docs.rs/amadeus/0.3/amadeus/source/index.html).
+
+#![doc(html_root_url = "https://docs.rs/amadeus-azure/0.4.1")]
+#![warn(
+	// missing_copy_implementations,
+	// missing_debug_implementations,
+	// missing_docs,
+	trivial_numeric_casts,
+	unused_import_braces,
+	unused_qualifications,
+	unused_results,
+	unreachable_pub,
+	clippy::pedantic,
+)]
+#![allow(
+	clippy::module_name_repetitions,
+	clippy::must_use_candidate,
+	clippy::missing_errors_doc
+)]
+#![deny(unsafe_code)]
+
+mod file;
+
+use hmac::{Hmac, Mac, NewMac};
+use once_cell::sync::Lazy;
+use percent_encoding::percent_decode_str;
+use reqwest::{Client, Request, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+	env, io, time::{Duration, SystemTime}
+};
+use url::Url;
+use xml::reader::{EventReader, XmlEvent};
+
+use amadeus_core::{file::RetryPolicy, util::IoError};
+
+pub use file::{AzureDirectory, AzureFile, AzurePage, AzurePartition};
+
+/// The version of the Blob service REST API used.
+const VERSION: &str = "2019-12-12";
+
+static CLIENT: Lazy<Client> = Lazy::new(|| {
+	Client::builder()
+		.timeout(Duration::from_secs(120))
+		.build()
+		.unwrap()
+});
+
+/// How requests to Azure Blob Storage are authorized.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum AzureCredentials {
+	/// No authorization, for containers allowing public access.
+	Anonymous,
+	/// An account key, as given in the Azure portal.
+	SharedKey(String),
+	/// A shared access signature token, like `sv=2019-12-12&ss=b&sig=...`.
+	Sas(String),
+	/// The `AZURE_STORAGE_KEY` or else `AZURE_STORAGE_SAS_TOKEN` environment
+	/// variable, or anonymous if neither is set.
+	Environment,
+}
+impl Default for AzureCredentials {
+	fn default() -> Self {
+		AzureCredentials::Environment
+	}
+}
+
+/// A storage account, and the endpoint and credentials to reach it with.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+struct Account {
+	name: String,
+	endpoint: Option<Url>,
+	credentials: AzureCredentials,
+}
+impl Account {
+	/// The URL of `container`, or of blob `blob` within it.
+	fn url(&self, container: &str, blob: Option<&str>) -> Url {
+		let mut url = self.endpoint.clone().unwrap_or_else(|| {
+			Url::parse(&format!("https://{}.blob.core.windows.net", self.name)).unwrap()
+		});
+		{
+			let mut segments = url
+				.path_segments_mut()
+				.expect("Azure endpoint can't be a base");
+			let _ = segments.pop_if_empty().push(container);
+			if let Some(blob) = blob {
+				let _ = segments.extend(blob.split('/'));
+			}
+		}
+		url
+	}
+
+	/// Send the request built by `request`, authorized with the account's
+	/// credentials. Connection errors, timeouts, 5xx and 429 responses are
+	/// retried as per the default [`RetryPolicy`]. Other error statuses are
+	/// returned as errors, with the error code from the body if present.
+	async fn send<F>(&self, request: F) -> Result<Response, IoError>
+	where
+		F: Fn() -> RequestBuilder,
+	{
+		let res = RetryPolicy::default()
+			.retry(
+				|| async {
+					// Signed afresh each attempt, as the signature covers the date
+					let mut req = request()
+						.header("x-ms-version", VERSION)
+						.header("x-ms-date", httpdate::fmt_http_date(SystemTime::now()))
+						.build()
+						.map_err(|err| Failure::Other(error(err)))?;
+					self.authorize(&mut req).map_err(Failure::Other)?;
+					match CLIENT.execute(req).await {
+						Ok(res)
+							if res.status().is_server_error()
+								|| res.status() == StatusCode::TOO_MANY_REQUESTS =>
+						{
+							Err(Failure::Status(res))
+						}
+						Ok(res) => Ok(res),
+						Err(err) => Err(Failure::Send(err)),
+					}
+				},
+				|err| match err {
+					Failure::Status(_) => true,
+					Failure::Send(err) => err.is_connect() || err.is_timeout() || err.is_request(),
+					Failure::Other(_) => false,
+				},
+			)
+			.await;
+		let res = match res {
+			Ok(res) | Err(Failure::Status(res)) => res,
+			Err(Failure::Send(err)) => return Err(error(err)),
+			Err(Failure::Other(err)) => return Err(err),
+		};
+		if res.status().is_client_error() || res.status().is_server_error() {
+			return Err(error_response(res).await);
+		}
+		Ok(res)
+	}
+
+	fn authorize(&self, req: &mut Request) -> Result<(), IoError> {
+		let credentials = match &self.credentials {
+			AzureCredentials::Environment => {
+				if let Ok(key) = env::var("AZURE_STORAGE_KEY") {
+					AzureCredentials::SharedKey(key)
+				} else if let Ok(token) = env::var("AZURE_STORAGE_SAS_TOKEN") {
+					AzureCredentials::Sas(token)
+				} else {
+					AzureCredentials::Anonymous
+				}
+			}
+			credentials => credentials.clone(),
+		};
+		match credentials {
+			AzureCredentials::Anonymous | AzureCredentials::Environment => (),
+			AzureCredentials::Sas(token) => {
+				let token = token.trim_start_matches('?');
+				let query = match req.url().query() {
+					Some(query) => format!("{}&{}", query, token),
+					None => token.to_owned(),
+				};
+				req.url_mut().set_query(Some(&query));
+			}
+			AzureCredentials::SharedKey(key) => {
+				let key = base64::decode(&key).map_err(|err| {
+					io::Error::new(
+						io::ErrorKind::InvalidInput,
+						format!("invalid account key: {}", err),
+					)
+				})?;
+				let mut mac = Hmac::<Sha256>::new_varkey(&key).unwrap();
+				mac.update(self.string_to_sign(req).as_bytes());
+				let signature = base64::encode(mac.finalize().into_bytes());
+				let authorization = format!("SharedKey {}:{}", self.name, signature);
+				let _ = req
+					.headers_mut()
+					.insert("Authorization", authorization.parse().unwrap());
+			}
+		}
+		Ok(())
+	}
+
+	/// See https://docs.microsoft.com/en-us/rest/api/storageservices/authorize-with-shared-key
+	fn string_to_sign(&self, req: &Request) -> String {
+		let header = |name: &str| {
+			req.headers()
+				.get(name)
+				.and_then(|value| value.to_str().ok())
+				.unwrap_or("")
+				.to_owned()
+		};
+		let content_length = req
+			.body()
+			.and_then(reqwest::Body::as_bytes)
+			.map_or(0, <[u8]>::len);
+		let content_length = if content_length == 0 {
+			String::new()
+		} else {
+			content_length.to_string()
+		};
+		let mut headers = req
+			.headers()
+			.iter()
+			.filter(|(name, _)| name.as_str().starts_with("x-ms-"))
+			.map(|(name, value)| format!("{}:{}\n", name, value.to_str().unwrap_or("").trim()))
+			.collect::<Vec<_>>();
+		headers.sort();
+		let mut resource = format!("/{}{}", self.name, req.url().path());
+		let mut query = req
+			.url()
+			.query_pairs()
+			.map(|(name, value)| (name.to_lowercase(), value.into_owned()))
+			.collect::<Vec<_>>();
+		query.sort();
+		for (name, value) in query {
+			resource.push_str(&format!("\n{}:{}", name, value));
+		}
+		format!(
+			"{}\n\n\n{}\n\n{}\n\n\n\n\n\n\n{}{}",
+			req.method(),
+			content_length,
+			header("Content-Type"),
+			headers.concat(),
+			resource
+		)
+	}
+}
+
+/// Why an attempt at sending a request failed.
+enum Failure {
+	/// A response worth retrying.
+	Status(Response),
+	Send(reqwest::Error),
+	Other(IoError),
+}
+
+async fn error_response(res: Response) -> IoError {
+	let status = res.status();
+	let kind = match status {
+		StatusCode::NOT_FOUND => io::ErrorKind::NotFound,
+		StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => io::ErrorKind::PermissionDenied,
+		StatusCode::CONFLICT => io::ErrorKind::AlreadyExists,
+		_ => io::ErrorKind::Other,
+	};
+	let body = res.bytes().await.unwrap_or_default();
+	let body = Element::parse(&body).ok();
+	let child = |name| Some(&*body.as_ref()?.child(name)?.text);
+	let message = match (child("Code"), child("Message")) {
+		(Some(code), Some(message)) => format!("{}: {}", code, message),
+		(Some(code), None) => code.to_owned(),
+		_ => format!("Azure request failed: {}", status),
+	};
+	io::Error::new(kind, message).into()
+}
+
+fn error(err: reqwest::Error) -> IoError {
+	let kind = if err.is_timeout() {
+		io::ErrorKind::TimedOut
+	} else {
+		io::ErrorKind::Other
+	};
+	io::Error::new(kind, err).into()
+}
+
+/// An element of the XML the Blob service responds with, by local name, with
+/// its text unescaped.
+#[derive(Default, Debug)]
+struct Element {
+	name: String,
+	attributes: Vec<(String, String)>,
+	text: String,
+	children: Vec<Element>,
+}
+impl Element {
+	/// Parse the root element of `xml`.
+	fn parse(xml: &[u8]) -> Result<Self, IoError> {
+		let mut stack = vec![Self::default()];
+		for event in EventReader::new(xml) {
+			match event.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))? {
+				XmlEvent::StartElement {
+					name, attributes, ..
+				} => stack.push(Self {
+					name: name.local_name,
+					attributes: attributes
+						.into_iter()
+						.map(|attribute| (attribute.name.local_name, attribute.value))
+						.collect(),
+					..Self::default()
+				}),
+				XmlEvent::EndElement { .. } => {
+					let element = stack.pop().unwrap();
+					stack.last_mut().unwrap().children.push(element);
+				}
+				XmlEvent::Characters(text) | XmlEvent::CData(text) | XmlEvent::Whitespace(text) => {
+					stack.last_mut().unwrap().text.push_str(&text);
+				}
+				_ => (),
+			}
+		}
+		stack.pop().unwrap().children.pop().ok_or_else(|| {
+			io::Error::new(io::ErrorKind::InvalidData, "missing root element").into()
+		})
+	}
+	/// The child elements named `name`.
+	fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Self> + 'a {
+		self.children.iter().filter(move |child| child.name == name)
+	}
+	/// The first child element named `name`.
+	fn child(&self, name: &str) -> Option<&Self> {
+		self.children.iter().find(|child| child.name == name)
+	}
+	fn attribute(&self, name: &str) -> Option<&str> {
+		self.attributes
+			.iter()
+			.find(|(name_, _)| name_ == name)
+			.map(|(_, value)| &**value)
+	}
+	/// The text of a `<Name>` element. Names containing characters that XML
+	/// can't represent are percent-encoded, and marked `Encoded="true"`.
+	fn name(&self) -> Option<String> {
+		if self.attribute("Encoded") == Some("true") {
+			percent_decode_str(&self.text)
+				.decode_utf8()
+				.ok()
+				.map(Into::into)
+		} else {
+			Some(self.text.clone())
+		}
+	}
+}
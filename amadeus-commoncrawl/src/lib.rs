@@ -31,13 +31,13 @@ mod parser;
 
 use async_compression::futures::bufread::GzipDecoder; // TODO: use stream or https://github.com/alexcrichton/flate2-rs/pull/214
 use futures::{io::BufReader, AsyncBufReadExt, FutureExt, Stream, StreamExt, TryStreamExt};
-use reqwest_resume::ClientExt;
 use serde_closure::FnMutNamed;
-use std::{io, time};
+use std::io;
 
 use amadeus_core::{
 	into_par_stream::IntoDistributedStream, par_stream::DistributedStream, util::DistParStream, Source
 };
+use amadeus_http::HttpFile;
 use amadeus_types::Webpage;
 
 use commoncrawl::WarcParser;
@@ -49,33 +49,23 @@ pub struct CommonCrawl {
 }
 impl CommonCrawl {
 	/// CC-MAIN-2020-24
-	pub async fn new(id: &str) -> Result<Self, reqwest::Error> {
+	pub async fn new(id: &str) -> Result<Self, io::Error> {
 		let url = format!(
 			"https://commoncrawl.s3.amazonaws.com/crawl-data/{}/warc.paths.gz",
 			id
 		);
-		let body = reqwest::ClientBuilder::new()
-			.timeout(time::Duration::new(120, 0))
-			.build()
-			.unwrap()
-			.resumable()
-			.get(url.parse().unwrap())
-			.send();
-		let body = body
-			.await?
+		let body = HttpFile::new(url.parse().unwrap())
 			.bytes_stream()
-			.map_err(|e| io::Error::new(io::ErrorKind::Other, e));
+			.map_err(io::Error::from);
 		let body = BufReader::new(body.into_async_read());
 		let mut body = GzipDecoder::new(body); // Content-Encoding isn't set, so decode manually
 		body.multiple_members(true);
 
 		let urls = BufReader::new(body)
 			.lines()
-			.map(|url: Result<String, io::Error>| -> String {
-				format!("http://commoncrawl.s3.amazonaws.com/{}", url.unwrap())
-			})
-			.collect()
-			.await;
+			.map_ok(|url| format!("http://commoncrawl.s3.amazonaws.com/{}", url))
+			.try_collect()
+			.await?;
 		Ok(Self { urls })
 	}
 }
@@ -89,10 +79,9 @@ FnMutNamed! {
 	pub type Closure<> = |self|url=> String| -> Output where {
 		#[allow(clippy::let_and_return)]
 		let ret = async move {
-				let body = reqwest_resume::get(url.parse().unwrap()).await.unwrap();
-				let body = body
+				let body = HttpFile::new(url.parse().unwrap())
 					.bytes_stream()
-					.map_err(|e| io::Error::new(io::ErrorKind::Other, e));
+					.map_err(io::Error::from);
 				let body = BufReader::new(body.into_async_read());
 				let mut body = GzipDecoder::new(body); // Content-Encoding isn't set, so decode manually
 				body.multiple_members(true);
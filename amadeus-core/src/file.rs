@@ -2,19 +2,31 @@
 
 #![allow(clippy::type_complexity)]
 
+mod buf;
+mod compression;
+mod glob;
 mod local;
+mod retry;
 
 use async_trait::async_trait;
-use futures::{future::LocalBoxFuture, ready};
+use futures::{
+	future::{self, LocalBoxFuture}, ready, FutureExt
+};
 use pin_project::pin_project;
 use std::{
-	convert::TryFrom, error::Error, ffi, fmt, future::Future, io, pin::Pin, sync::Arc, task::{Context, Poll}
+	convert::TryFrom, error::Error, ffi, fmt, future::Future, io, mem, pin::Pin, sync::Arc, task::{Context, Poll}
 };
 use widestring::U16String;
 
 use crate::pool::ProcessSend;
 
+pub use buf::SharedBuf;
+pub use compression::{compress, decompress, Compression};
+pub use glob::{Glob, GlobError};
 pub use local::LocalFile;
+#[cfg(not(target_arch = "wasm32"))]
+pub use local::MemoryMapped;
+pub use retry::{get_range, get_resumable, RetryPolicy};
 
 const PAGE_SIZE: usize = 10 * 1024 * 1024; // `Reader` reads this many bytes at a time
 
@@ -206,6 +218,11 @@ pub trait Partition: Clone + fmt::Debug + ProcessSend + 'static {
 	type Error: Error + Clone + PartialEq + ProcessSend + 'static;
 
 	async fn pages(self) -> Result<Vec<Self::Page>, Self::Error>;
+	/// The name of this partition, such as its path or object key, if it has one. This is used
+	/// for example to detect its [`Compression`] from its extension.
+	fn name(&self) -> Option<String> {
+		None
+	}
 }
 #[allow(clippy::len_without_is_empty)]
 pub trait Page {
@@ -214,10 +231,17 @@ pub trait Page {
 	fn len(&self) -> LocalBoxFuture<'static, Result<u64, Self::Error>>;
 	fn read(
 		&self, offset: u64, len: usize,
-	) -> LocalBoxFuture<'static, Result<Box<[u8]>, Self::Error>>;
+	) -> LocalBoxFuture<'static, Result<SharedBuf, Self::Error>>;
 	fn write(
 		&self, offset: u64, buf: Box<[u8]>,
 	) -> LocalBoxFuture<'static, Result<(), Self::Error>>;
+	/// Make previous writes durable and visible to readers. Must be called once all writes have
+	/// completed, as some pages (e.g. objects in S3) can't be read until this has returned.
+	///
+	/// Pages that don't buffer writes needn't override this.
+	fn flush(&self) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
+		future::ok(()).boxed_local()
+	}
 
 	fn reader(self) -> Reader<Self>
 	where
@@ -225,6 +249,12 @@ pub trait Page {
 	{
 		Reader::new(self)
 	}
+	fn writer(self) -> Writer<Self>
+	where
+		Self: Sized,
+	{
+		Writer::new(self)
+	}
 }
 
 impl<T: ?Sized> Page for &T
@@ -238,7 +268,7 @@ where
 	}
 	fn read(
 		&self, offset: u64, len: usize,
-	) -> LocalBoxFuture<'static, Result<Box<[u8]>, Self::Error>> {
+	) -> LocalBoxFuture<'static, Result<SharedBuf, Self::Error>> {
 		(**self).read(offset, len)
 	}
 	fn write(
@@ -246,6 +276,9 @@ where
 	) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
 		(**self).write(offset, buf)
 	}
+	fn flush(&self) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
+		(**self).flush()
+	}
 }
 impl<T: ?Sized> Page for Arc<T>
 where
@@ -258,7 +291,7 @@ where
 	}
 	fn read(
 		&self, offset: u64, len: usize,
-	) -> LocalBoxFuture<'static, Result<Box<[u8]>, Self::Error>> {
+	) -> LocalBoxFuture<'static, Result<SharedBuf, Self::Error>> {
 		(**self).read(offset, len)
 	}
 	fn write(
@@ -266,8 +299,15 @@ where
 	) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
 		(**self).write(offset, buf)
 	}
+	fn flush(&self) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
+		(**self).flush()
+	}
 }
 
+/// An [`AsyncRead`](futures::io::AsyncRead) over a [`Page`], created by [`Page::reader`].
+///
+/// It also implements [`AsyncBufRead`](futures::io::AsyncBufRead), which hands out the buffers
+/// returned by [`Page::read`] directly rather than copying them.
 #[pin_project]
 pub struct Reader<P>
 where
@@ -276,7 +316,8 @@ where
 	#[pin]
 	page: P,
 	#[pin]
-	pending: Option<LocalBoxFuture<'static, Result<Box<[u8]>, P::Error>>>,
+	pending: Option<LocalBoxFuture<'static, Result<SharedBuf, P::Error>>>,
+	buf: SharedBuf,
 	offset: u64,
 }
 #[allow(clippy::len_without_is_empty)]
@@ -288,35 +329,47 @@ where
 		Self {
 			page,
 			pending: None,
+			buf: SharedBuf::new(),
 			offset: 0,
 		}
 	}
 }
+impl<P> futures::io::AsyncBufRead for Reader<P>
+where
+	P: Page,
+{
+	fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<&[u8]>> {
+		let mut self_ = self.project();
+		if self_.buf.is_empty() {
+			if self_.pending.is_none() {
+				let pending = self_.page.read(*self_.offset, PAGE_SIZE);
+				self_.pending.set(Some(pending));
+			}
+			let ret = ready!(self_.pending.as_mut().as_pin_mut().unwrap().poll(cx));
+			self_.pending.set(None);
+			let buf = ret.map_err(Into::into)?;
+			*self_.offset += u64::try_from(buf.len()).unwrap();
+			*self_.buf = buf;
+		}
+		Poll::Ready(Ok(&**self_.buf))
+	}
+	fn consume(self: Pin<&mut Self>, amt: usize) {
+		self.project().buf.advance(amt)
+	}
+}
 impl<P> futures::io::AsyncRead for Reader<P>
 where
 	P: Page,
 {
 	fn poll_read(
-		self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8],
+		mut self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8],
 	) -> Poll<io::Result<usize>> {
-		let mut self_ = self.project();
-		if self_.pending.is_none() {
-			let start = *self_.offset;
-			let len = buf.len();
-			let len = len.min(PAGE_SIZE);
-			let pending = self_.page.read(start, len);
-			*self_.pending = Some(pending);
-		}
-		let ret = ready!(self_.pending.as_mut().as_pin_mut().unwrap().poll(cx));
-		*self_.pending = None;
-		let ret = ret
-			.map(|buf_| {
-				buf[..buf_.len()].copy_from_slice(&buf_);
-				buf_.len()
-			})
-			.map_err(Into::into);
-		*self_.offset += u64::try_from(ret.as_ref().ok().cloned().unwrap_or(0)).unwrap();
-		Poll::Ready(ret)
+		use futures::io::AsyncBufRead;
+		let available = ready!(self.as_mut().poll_fill_buf(cx))?;
+		let len = available.len().min(buf.len());
+		buf[..len].copy_from_slice(&available[..len]);
+		self.consume(len);
+		Poll::Ready(Ok(len))
 	}
 }
 
@@ -350,3 +403,87 @@ where
 // 		Ok(self.offset)
 // 	}
 // }
+
+/// Sequential writes to a [`Page`] from offset 0, buffered into writes of up to 10 MiB.
+/// [Closing](futures::io::AsyncWriteExt::close) it [flushes](Page::flush) the page.
+#[pin_project]
+pub struct Writer<P>
+where
+	P: Page,
+{
+	page: P,
+	buf: Vec<u8>,
+	#[pin]
+	pending: Option<LocalBoxFuture<'static, Result<(), P::Error>>>,
+	offset: u64,
+	closed: bool,
+}
+impl<P> Writer<P>
+where
+	P: Page,
+{
+	fn new(page: P) -> Self {
+		Self {
+			page,
+			buf: Vec::new(),
+			pending: None,
+			offset: 0,
+			closed: false,
+		}
+	}
+	fn start_write(self: Pin<&mut Self>) {
+		let mut self_ = self.project();
+		assert!(self_.pending.is_none());
+		let buf = mem::take(self_.buf).into_boxed_slice();
+		let offset = *self_.offset;
+		*self_.offset += u64::try_from(buf.len()).unwrap();
+		self_.pending.set(Some(self_.page.write(offset, buf)));
+	}
+	fn poll_pending(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+		let mut self_ = self.project();
+		if let Some(pending) = self_.pending.as_mut().as_pin_mut() {
+			let ret = ready!(pending.poll(cx));
+			self_.pending.set(None);
+			ret.map_err(Into::<io::Error>::into)?;
+		}
+		Poll::Ready(Ok(()))
+	}
+}
+impl<P> futures::io::AsyncWrite for Writer<P>
+where
+	P: Page,
+{
+	fn poll_write(
+		mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8],
+	) -> Poll<io::Result<usize>> {
+		// The previous write proceeds while the buffer fills, only blocking once it's full
+		match self.as_mut().poll_pending(cx) {
+			Poll::Ready(ret) => ret?,
+			Poll::Pending if self.buf.len() == PAGE_SIZE => return Poll::Pending,
+			Poll::Pending => (),
+		}
+		if self.buf.len() == PAGE_SIZE {
+			self.as_mut().start_write();
+		}
+		let self_ = self.project();
+		let len = buf.len().min(PAGE_SIZE - self_.buf.len());
+		self_.buf.extend_from_slice(&buf[..len]);
+		Poll::Ready(Ok(len))
+	}
+	fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+		ready!(self.as_mut().poll_pending(cx))?;
+		if !self.buf.is_empty() {
+			self.as_mut().start_write();
+		}
+		self.poll_pending(cx)
+	}
+	fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+		ready!(self.as_mut().poll_flush(cx))?;
+		let mut self_ = self.as_mut().project();
+		if !*self_.closed {
+			*self_.closed = true;
+			self_.pending.set(Some(self_.page.flush()));
+		}
+		self.poll_pending(cx)
+	}
+}
@@ -0,0 +1,104 @@
+use bytes::Bytes;
+use std::{
+	fmt, ops::{Deref, Range}, sync::Arc
+};
+
+/// Bytes read from a [`Page`](super::Page).
+///
+/// This derefs to `[u8]`, and is cheap to clone and [slice](SharedBuf::slice) as it shares the
+/// memory it was made from rather than owning a copy: for example a response body, or a memory
+/// map, which is kept alive for as long as any buffer referencing it is.
+#[derive(Clone)]
+pub struct SharedBuf {
+	owner: Arc<dyn AsRef<[u8]> + Send + Sync>,
+	start: usize,
+	end: usize,
+}
+impl SharedBuf {
+	/// An empty buffer.
+	pub fn new() -> Self {
+		Self::from_owner(Bytes::new())
+	}
+	/// A buffer referencing all of the bytes of `owner`, without copying them.
+	pub fn from_owner<T>(owner: T) -> Self
+	where
+		T: AsRef<[u8]> + Send + Sync + 'static,
+	{
+		let end = owner.as_ref().len();
+		Self {
+			owner: Arc::new(owner),
+			start: 0,
+			end,
+		}
+	}
+	/// A buffer referencing `range` of this one, without copying it.
+	///
+	/// Panics if `range` is out of bounds.
+	pub fn slice(&self, range: Range<usize>) -> Self {
+		assert!(
+			range.start <= range.end && range.end <= self.len(),
+			"range {:?} out of bounds of buffer of length {}",
+			range,
+			self.len()
+		);
+		Self {
+			owner: self.owner.clone(),
+			start: self.start + range.start,
+			end: self.start + range.end,
+		}
+	}
+	/// Drop the first `n` bytes of this buffer.
+	///
+	/// Panics if `n` is greater than its length.
+	pub fn advance(&mut self, n: usize) {
+		assert!(n <= self.len(), "advance past end of buffer");
+		self.start += n;
+	}
+	/// Copy the bytes into a `Vec`.
+	pub fn into_vec(self) -> Vec<u8> {
+		self.to_vec()
+	}
+}
+impl Deref for SharedBuf {
+	type Target = [u8];
+
+	fn deref(&self) -> &[u8] {
+		&(*self.owner).as_ref()[self.start..self.end]
+	}
+}
+impl AsRef<[u8]> for SharedBuf {
+	fn as_ref(&self) -> &[u8] {
+		self
+	}
+}
+impl Default for SharedBuf {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+impl From<Bytes> for SharedBuf {
+	fn from(bytes: Bytes) -> Self {
+		Self::from_owner(bytes)
+	}
+}
+impl From<Vec<u8>> for SharedBuf {
+	fn from(vec: Vec<u8>) -> Self {
+		Self::from_owner(vec)
+	}
+}
+impl From<Box<[u8]>> for SharedBuf {
+	fn from(buf: Box<[u8]>) -> Self {
+		Self::from_owner(buf)
+	}
+}
+impl PartialEq for SharedBuf {
+	fn eq(&self, other: &Self) -> bool {
+		**self == **other
+	}
+}
+impl Eq for SharedBuf {}
+impl fmt::Debug for SharedBuf {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Debug::fmt(&**self, f)
+	}
+}
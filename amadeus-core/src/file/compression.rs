@@ -0,0 +1,218 @@
+#[cfg(not(target_arch = "wasm32"))]
+use async_compression::futures::{
+	bufread::{BzDecoder, XzDecoder, ZstdDecoder}, write::{BzEncoder, XzEncoder, ZstdEncoder}
+};
+use async_compression::{
+	futures::{bufread::GzipDecoder, write::GzipEncoder}, Level
+};
+use futures::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::{
+	io, pin::Pin, task::{Context, Poll}
+};
+
+use super::Page;
+
+/// A compression format that input files can be transparently decompressed from, and output
+/// files compressed to.
+///
+/// [`decompress`] detects which a file is in from its extension, or failing that its magic
+/// bytes, unless one is given explicitly. [`compress`] picks one from the extension.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+pub enum Compression {
+	Uncompressed,
+	Gzip,
+	Zstd,
+	Bzip2,
+	Xz,
+	/// The lz4 frame format.
+	///
+	/// **Decompressing lz4 buffers the whole compressed and decompressed file in memory**, as
+	/// there's no streaming lz4 decoder for futures. Prefer another format for large files.
+	Lz4,
+}
+impl Compression {
+	/// The compression implied by a file name or path's extension, like `.gz` or `.zst`, or
+	/// `None` if it doesn't imply one. Extensions of uncompressed formats, like `.csv` or
+	/// `.json`, imply [`Uncompressed`](Self::Uncompressed).
+	pub fn from_extension(name: &str) -> Option<Self> {
+		let extension = name.rsplit('/').next().unwrap().rsplit('.').next().unwrap();
+		Some(match &*extension.to_ascii_lowercase() {
+			"csv" | "tsv" | "json" | "jsonl" | "ndjson" | "txt" | "log" | "xml" | "html" => {
+				Self::Uncompressed
+			}
+			"gz" | "gzip" => Self::Gzip,
+			"zst" | "zstd" => Self::Zstd,
+			"bz2" => Self::Bzip2,
+			"xz" => Self::Xz,
+			"lz4" => Self::Lz4,
+			_ => return None,
+		})
+	}
+
+	/// The compression of a file beginning with `bytes`, detected from its magic bytes.
+	pub fn from_magic(bytes: &[u8]) -> Self {
+		if bytes.starts_with(&[0x1f, 0x8b]) {
+			Self::Gzip
+		} else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+			Self::Zstd
+		} else if bytes.starts_with(b"BZh") {
+			Self::Bzip2
+		} else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+			Self::Xz
+		} else if bytes.starts_with(&[0x04, 0x22, 0x4d, 0x18]) {
+			Self::Lz4
+		} else {
+			Self::Uncompressed
+		}
+	}
+
+	/// Wrap `reader` to decompress it. Concatenated streams, as produced by appending to a
+	/// compressed log file, are decompressed as one.
+	///
+	/// Every format but [`Lz4`](Self::Lz4) is decompressed as it's read. **Lz4 is read to the
+	/// end and decompressed in memory before the first byte is returned.**
+	pub fn decoder<'a, R>(self, reader: R) -> Pin<Box<dyn AsyncRead + 'a>>
+	where
+		R: AsyncBufRead + Unpin + 'a,
+	{
+		match self {
+			Self::Uncompressed => Box::pin(reader),
+			Self::Gzip => {
+				let mut decoder = GzipDecoder::new(reader);
+				decoder.multiple_members(true);
+				Box::pin(decoder)
+			}
+			#[cfg(not(target_arch = "wasm32"))]
+			Self::Zstd => {
+				let mut decoder = ZstdDecoder::new(reader);
+				decoder.multiple_members(true);
+				Box::pin(decoder)
+			}
+			#[cfg(not(target_arch = "wasm32"))]
+			Self::Bzip2 => {
+				let mut decoder = BzDecoder::new(reader);
+				decoder.multiple_members(true);
+				Box::pin(decoder)
+			}
+			#[cfg(not(target_arch = "wasm32"))]
+			Self::Xz => {
+				let mut decoder = XzDecoder::new(reader);
+				decoder.multiple_members(true);
+				Box::pin(decoder)
+			}
+			// There's no streaming lz4 decoder for futures, so buffer the whole file. This is
+			// documented on the variant and on `decoder`; keep those in sync if this changes.
+			#[cfg(not(target_arch = "wasm32"))]
+			Self::Lz4 => {
+				use futures::AsyncReadExt;
+				let mut reader = reader;
+				let decoded = futures::stream::once(async move {
+					let mut compressed = Vec::new();
+					let _ = reader.read_to_end(&mut compressed).await?;
+					let mut decoded = Vec::new();
+					let _ =
+						io::Read::read_to_end(&mut lz4::Decoder::new(&*compressed)?, &mut decoded)?;
+					Ok::<_, io::Error>(decoded)
+				});
+				Box::pin(decoded.boxed_local().into_async_read())
+			}
+			#[cfg(target_arch = "wasm32")]
+			compression => {
+				let err = futures::stream::once(async move {
+					Err::<Vec<u8>, _>(io::Error::new(
+						io::ErrorKind::Other,
+						format!("{:?} decompression isn't supported on wasm", compression),
+					))
+				});
+				Box::pin(err.boxed_local().into_async_read())
+			}
+		}
+	}
+
+	/// Wrap `writer` to compress what's written to it, at `level` if given or else the format's
+	/// default. Closing the returned writer finishes the compressed stream and closes `writer`.
+	///
+	/// Levels are clamped to those the format supports, e.g. 1-9 for gzip and 1-21 for zstd.
+	/// Writing lz4 isn't supported.
+	pub fn encoder<'a, W>(self, writer: W, level: Option<u32>) -> Pin<Box<dyn AsyncWrite + 'a>>
+	where
+		W: AsyncWrite + Unpin + 'a,
+	{
+		let level = level.map_or(Level::Default, Level::Precise);
+		match self {
+			Self::Uncompressed => Box::pin(writer),
+			Self::Gzip => Box::pin(GzipEncoder::with_quality(writer, level)),
+			#[cfg(not(target_arch = "wasm32"))]
+			Self::Zstd => Box::pin(ZstdEncoder::with_quality(writer, level)),
+			#[cfg(not(target_arch = "wasm32"))]
+			Self::Bzip2 => Box::pin(BzEncoder::with_quality(writer, level)),
+			#[cfg(not(target_arch = "wasm32"))]
+			Self::Xz => Box::pin(XzEncoder::with_quality(writer, level)),
+			compression => Box::pin(Unsupported(compression)),
+		}
+	}
+}
+
+/// A writer that fails, for formats that can't be written.
+struct Unsupported(Compression);
+impl Unsupported {
+	fn err(&self) -> io::Error {
+		io::Error::new(
+			io::ErrorKind::Other,
+			format!("{:?} compression isn't supported", self.0),
+		)
+	}
+}
+impl AsyncWrite for Unsupported {
+	fn poll_write(self: Pin<&mut Self>, _cx: &mut Context, _buf: &[u8]) -> Poll<io::Result<usize>> {
+		Poll::Ready(Err(self.err()))
+	}
+	fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+		Poll::Ready(Err(self.err()))
+	}
+	fn poll_close(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+		Poll::Ready(Err(self.err()))
+	}
+}
+
+/// Write `page`, compressing it.
+///
+/// If `compression` is `None` it's picked from the extension of `name`, which is typically the
+/// name of the file being written, with no extension meaning uncompressed. Output is streamed
+/// through the encoder to the page in chunks, so the whole file is never held in memory. It must
+/// be [closed](futures::io::AsyncWriteExt::close) to finish the stream and
+/// [flush](Page::flush) the page.
+pub fn compress<'a, P>(
+	page: P, name: Option<&str>, compression: Option<Compression>, level: Option<u32>,
+) -> Pin<Box<dyn AsyncWrite + 'a>>
+where
+	P: Page + 'a,
+{
+	let compression = compression
+		.or_else(|| name.and_then(Compression::from_extension))
+		.unwrap_or(Compression::Uncompressed);
+	compression.encoder(Box::pin(page.writer()), level)
+}
+
+/// Read `page`, decompressing it.
+///
+/// If `compression` is `None` it's detected from the extension of `name`, which is typically
+/// the [name](super::Partition::name) of the partition the page is from. Only if that's missing
+/// or unrecognised is it detected from the page's magic bytes, so that for example a `.csv`
+/// that happens to begin with `BZh` is read as is.
+///
+/// Note that lz4 pages are [buffered in memory](Compression::Lz4) to be decompressed.
+pub async fn decompress<'a, P>(
+	page: P, name: Option<&str>, compression: Option<Compression>,
+) -> io::Result<Pin<Box<dyn AsyncRead + 'a>>>
+where
+	P: Page + 'a,
+{
+	let mut reader = Box::pin(page.reader());
+	let compression = match compression.or_else(|| name.and_then(Compression::from_extension)) {
+		Some(compression) => compression,
+		None => Compression::from_magic(reader.fill_buf().await?),
+	};
+	Ok(compression.decoder(reader))
+}
@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, error::Error, fmt};
+
+use super::PathBuf;
+
+/// A glob pattern like `/data/**/*.parquet` or `logs/2020-0[1-3]-*/*.gz`, for selecting the
+/// files of a [`Directory`](super::Directory).
+///
+/// Within a path component, `*` matches any sequence of characters, `?` matches any single
+/// character, `[abc]` and `[a-z]` match one of a set of characters, `[!abc]` (or `[^abc]`) one
+/// not in it, and `\` escapes the following character. A component of exactly `**` matches any
+/// number of directories, including none.
+///
+/// The components before the first containing a wildcard are the pattern's
+/// [`root`](Self::root), which is where listing starts. [`is_match`](Self::is_match) is then
+/// given paths relative to it, and rejects directories that can't contain a match so they
+/// aren't descended into.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Glob {
+	pattern: String,
+	prefix: String,
+	root_len: usize,
+	segments: Vec<Segment>,
+}
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Segment {
+	AnyDepth,
+	Tokens(Vec<Token>),
+}
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Token {
+	Char(char),
+	AnyChar,
+	AnyChars,
+	Class {
+		negated: bool,
+		ranges: Vec<(char, char)>,
+	},
+}
+
+impl Glob {
+	pub fn new(pattern: &str) -> Result<Self, GlobError> {
+		// Find the literal prefix, and where the root directory ends in the pattern
+		let mut prefix = String::new();
+		let (mut root_len, mut pattern_root_len) = (0, 0);
+		let mut chars = pattern.char_indices();
+		while let Some((i, c)) = chars.next() {
+			match c {
+				'*' | '?' | '[' => break,
+				'\\' => prefix.push(
+					chars
+						.next()
+						.ok_or(GlobError("trailing escape character"))?
+						.1,
+				),
+				'/' => {
+					prefix.push(c);
+					root_len = prefix.len();
+					pattern_root_len = i + 1;
+				}
+				c => prefix.push(c),
+			}
+		}
+		let segments = pattern[pattern_root_len..]
+			.split('/')
+			.filter(|segment| !segment.is_empty())
+			.map(Segment::parse)
+			.collect::<Result<_, _>>()?;
+		Ok(Self {
+			pattern: pattern.to_owned(),
+			prefix,
+			root_len,
+			segments,
+		})
+	}
+
+	/// The pattern this was created from.
+	pub fn as_str(&self) -> &str {
+		&self.pattern
+	}
+
+	/// The directory all matches are under, i.e. the components of the pattern before the
+	/// first containing a wildcard, unescaped. It is empty or ends in `/`.
+	pub fn root(&self) -> &str {
+		&self.prefix[..self.root_len]
+	}
+
+	/// The longest literal string all matches start with, unescaped. For example
+	/// `logs/2020-0[1-3]-*/*.gz` has root `logs/` and prefix `logs/2020-0`. Object stores can
+	/// use this to narrow listing server-side.
+	pub fn prefix(&self) -> &str {
+		&self.prefix
+	}
+
+	/// Whether `path`, relative to the [`root`](Self::root), matches the pattern. Directories
+	/// match if they could contain a file that matches, so this can be passed directly to
+	/// [`Directory::partitions_filter`](super::Directory::partitions_filter).
+	pub fn is_match(&self, path: &PathBuf) -> bool {
+		let components = path
+			.iter()
+			.chain(path.file_name())
+			.map(|component| component.to_string_lossy().chars().collect())
+			.collect::<Vec<Vec<char>>>();
+		Segment::matches(&self.segments, &components, !path.is_file())
+	}
+}
+impl Segment {
+	fn parse(segment: &str) -> Result<Self, GlobError> {
+		if segment == "**" {
+			return Ok(Self::AnyDepth);
+		}
+		let mut tokens = Vec::new();
+		let mut chars = segment.chars().peekable();
+		while let Some(c) = chars.next() {
+			tokens.push(match c {
+				'*' => Token::AnyChars,
+				'?' => Token::AnyChar,
+				'\\' => Token::Char(chars.next().ok_or(GlobError("trailing escape character"))?),
+				'[' => {
+					let negated = chars.peek().map_or(false, |&c| c == '!' || c == '^');
+					if negated {
+						let _ = chars.next();
+					}
+					let mut ranges = Vec::new();
+					loop {
+						let start = match chars.next() {
+							Some(']') if !ranges.is_empty() => break,
+							Some('\\') => chars.next(),
+							c => c,
+						}
+						.ok_or(GlobError("unclosed character class"))?;
+						let mut end = start;
+						if chars.peek() == Some(&'-') {
+							let _ = chars.next();
+							end = match chars.next() {
+								Some(']') => {
+									ranges.push((start, start));
+									ranges.push(('-', '-'));
+									break;
+								}
+								Some('\\') => chars.next(),
+								c => c,
+							}
+							.ok_or(GlobError("unclosed character class"))?;
+						}
+						ranges.push((start, end));
+					}
+					Token::Class { negated, ranges }
+				}
+				c => Token::Char(c),
+			});
+		}
+		Ok(Self::Tokens(tokens))
+	}
+
+	/// Whether `components` match `segments`. If `partial`, `components` are a directory that
+	/// matches if some path within it could.
+	fn matches(segments: &[Self], components: &[Vec<char>], partial: bool) -> bool {
+		if partial && components.is_empty() {
+			return !segments.is_empty();
+		}
+		match segments.split_first() {
+			None => components.is_empty(),
+			Some((Self::AnyDepth, rest)) => {
+				Self::matches(rest, components, partial)
+					|| (!components.is_empty()
+						&& Self::matches(segments, &components[1..], partial))
+			}
+			Some((Self::Tokens(tokens), rest)) => {
+				components
+					.split_first()
+					.map_or(false, |(component, components)| {
+						Token::matches(tokens, component)
+							&& Self::matches(rest, components, partial)
+					})
+			}
+		}
+	}
+}
+impl Token {
+	/// Whether `tokens` match the whole of `chars`, backtracking to the last `*` on a mismatch.
+	fn matches(tokens: &[Self], chars: &[char]) -> bool {
+		let (mut t, mut c) = (0, 0);
+		let mut backtrack = None;
+		while c < chars.len() {
+			match tokens.get(t) {
+				Some(Self::AnyChars) => {
+					backtrack = Some((t, c));
+					t += 1;
+					continue;
+				}
+				Some(token) if token.matches_char(chars[c]) => {
+					t += 1;
+					c += 1;
+					continue;
+				}
+				_ => (),
+			}
+			match backtrack {
+				Some((backtrack_t, backtrack_c)) => {
+					t = backtrack_t + 1;
+					c = backtrack_c + 1;
+					backtrack = Some((backtrack_t, c));
+				}
+				None => return false,
+			}
+		}
+		tokens[t..].iter().all(|token| *token == Self::AnyChars)
+	}
+	fn matches_char(&self, c: char) -> bool {
+		match self {
+			Self::Char(c_) => *c_ == c,
+			Self::AnyChar => true,
+			Self::AnyChars => unreachable!(),
+			Self::Class { negated, ranges } => {
+				ranges.iter().any(|&(start, end)| start <= c && c <= end) != *negated
+			}
+		}
+	}
+}
+impl fmt::Debug for Glob {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("Glob").field(&self.pattern).finish()
+	}
+}
+impl fmt::Display for Glob {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.pattern)
+	}
+}
+impl TryFrom<String> for Glob {
+	type Error = GlobError;
+
+	fn try_from(pattern: String) -> Result<Self, Self::Error> {
+		Self::new(&pattern)
+	}
+}
+impl From<Glob> for String {
+	fn from(glob: Glob) -> Self {
+		glob.pattern
+	}
+}
+
+/// The error returned by [`Glob::new`] for an invalid pattern.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GlobError(&'static str);
+impl Error for GlobError {}
+impl fmt::Display for GlobError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "invalid glob pattern: {}", self.0)
+	}
+}
@@ -3,7 +3,7 @@
 use async_trait::async_trait;
 use futures::{future, future::LocalBoxFuture, stream, FutureExt, StreamExt, TryStreamExt};
 use std::{
-	ffi::{OsStr, OsString}, fs, future::Future, io, path::{Path, PathBuf}, sync::Arc
+	convert::TryFrom, ffi::{OsStr, OsString}, fs, future::Future, io, path::{Path, PathBuf}, sync::Arc
 };
 use walkdir::WalkDir;
 
@@ -13,14 +13,14 @@ use std::os::unix::fs::FileExt;
 use std::os::windows::fs::FileExt;
 #[cfg(target_arch = "wasm32")]
 use {
-	futures::lock::Mutex, js_sys::{ArrayBuffer, Uint8Array}, std::convert::TryFrom, wasm_bindgen::{JsCast, JsValue}, wasm_bindgen_futures::JsFuture, web_sys::{Blob, Response}
+	futures::lock::Mutex, js_sys::{ArrayBuffer, Uint8Array}, wasm_bindgen::{JsCast, JsValue}, wasm_bindgen_futures::JsFuture, web_sys::{Blob, Response}
 };
 #[cfg(not(target_arch = "wasm32"))]
 use {
-	std::io::{Seek, SeekFrom}, tokio::task::spawn_blocking
+	memmap::Mmap, serde::{Deserialize, Serialize}, std::io::{Seek, SeekFrom}, tokio::task::spawn_blocking
 };
 
-use super::{Directory, File, Page, Partition};
+use super::{Directory, File, Glob, Page, Partition, SharedBuf};
 #[cfg(target_arch = "wasm32")]
 use crate::util::{f64_to_u64, u64_to_f64};
 use crate::util::{IoError, ResultExpand};
@@ -87,6 +87,9 @@ impl Partition for PathBuf {
 	async fn pages(self) -> Result<Vec<Self::Page>, Self::Error> {
 		Ok(vec![LocalFile::open(self)?])
 	}
+	fn name(&self) -> Option<String> {
+		Some(self.to_string_lossy().into_owned())
+	}
 }
 #[async_trait(?Send)]
 impl Directory for &Path {
@@ -141,6 +144,32 @@ impl File for &Path {
 		PathBuf::partitions(self.into()).await
 	}
 }
+/// A glob pattern is the local files that match it.
+#[async_trait(?Send)]
+impl Directory for Glob {
+	async fn partitions_filter<F>(
+		self, mut f: F,
+	) -> Result<Vec<<Self as File>::Partition>, <Self as File>::Error>
+	where
+		F: FnMut(&super::PathBuf) -> bool,
+	{
+		let root = match self.root() {
+			"" => PathBuf::from("."),
+			root => PathBuf::from(root),
+		};
+		root.partitions_filter(|path| self.is_match(path) && f(path))
+			.await
+	}
+}
+#[async_trait(?Send)]
+impl File for Glob {
+	type Partition = PathBuf;
+	type Error = IoError;
+
+	async fn partitions(self) -> Result<Vec<Self::Partition>, Self::Error> {
+		self.partitions_filter(|_| true).await
+	}
+}
 #[async_trait(?Send)]
 impl File for String {
 	type Partition = PathBuf;
@@ -177,6 +206,78 @@ impl File for &OsStr {
 		PathBuf::partitions(self.into()).await
 	}
 }
+
+/// Local files that are read through a memory map rather than with `read` calls, for example
+/// `Parquet::new(unsafe { MemoryMapped::new(PathBuf::from("data.parquet")) })`.
+///
+/// Pages then [read](Page::read) without copying, returning buffers that reference the map
+/// directly, and with no round trip through the blocking thread pool.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct MemoryMapped<F>(F);
+#[cfg(not(target_arch = "wasm32"))]
+impl<F> MemoryMapped<F> {
+	/// Read the files of `file` through memory maps.
+	///
+	/// # Safety
+	///
+	/// The files must not be truncated or modified, by this or any other process, while they're
+	/// mapped, which is until the last buffer read from them is dropped. Truncation can crash the
+	/// process with `SIGBUS`, and modification changes the contents of buffers already read,
+	/// which is undefined behaviour. This holds too for a `MemoryMapped` that's deserialized, for
+	/// example on another process of a distributed pool.
+	#[allow(unsafe_code)]
+	pub unsafe fn new(file: F) -> Self {
+		Self(file)
+	}
+}
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait(?Send)]
+impl<F> File for MemoryMapped<F>
+where
+	F: File<Partition = PathBuf>,
+{
+	type Partition = MemoryMapped<PathBuf>;
+	type Error = F::Error;
+
+	async fn partitions(self) -> Result<Vec<Self::Partition>, Self::Error> {
+		let partitions = self.0.partitions().await?;
+		Ok(partitions.into_iter().map(MemoryMapped).collect())
+	}
+}
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait(?Send)]
+impl<F> Directory for MemoryMapped<F>
+where
+	F: Directory + File<Partition = PathBuf>,
+{
+	async fn partitions_filter<F1>(
+		self, f: F1,
+	) -> Result<Vec<<Self as File>::Partition>, <Self as File>::Error>
+	where
+		F1: FnMut(&super::PathBuf) -> bool,
+	{
+		let partitions = self.0.partitions_filter(f).await?;
+		Ok(partitions.into_iter().map(MemoryMapped).collect())
+	}
+}
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait(?Send)]
+impl Partition for MemoryMapped<PathBuf> {
+	type Page = LocalFile;
+	type Error = IoError;
+
+	async fn pages(self) -> Result<Vec<Self::Page>, Self::Error> {
+		// Safety: upheld by the caller of `MemoryMapped::new`
+		#[allow(unsafe_code)]
+		let page = unsafe { LocalFile::open_mmap(self.0)? };
+		Ok(vec![page])
+	}
+	fn name(&self) -> Option<String> {
+		self.0.name()
+	}
+}
+
 // impl File for fs::File {
 // 	type Partition = Self;
 // 	type Error = IoError;
@@ -216,6 +317,7 @@ impl<Fut: Future> FutureOrOutput<Fut> {
 #[cfg(not(target_arch = "wasm32"))]
 struct LocalFileInner {
 	file: fs::File,
+	map: Option<SharedBuf>,
 }
 #[cfg(target_arch = "wasm32")]
 struct LocalFileInner {
@@ -251,6 +353,28 @@ impl LocalFile {
 		}
 	}
 
+	/// Opens a file for random access through a memory map, so that reads return buffers
+	/// referencing the map rather than copies.
+	///
+	/// # Safety
+	///
+	/// The file must not be truncated or modified while it's mapped, which is until this and
+	/// the last buffer read from it are dropped. See [`MemoryMapped::new`].
+	#[cfg(not(target_arch = "wasm32"))]
+	#[allow(unsafe_code)]
+	pub unsafe fn open_mmap<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+		let file = fs::File::open(path)?;
+		// Empty maps aren't allowed, and reads of an empty file needn't touch it anyway
+		let map = if file.metadata()?.len() == 0 {
+			None
+		} else {
+			// Safety: upheld by our caller
+			Some(SharedBuf::from_owner(Mmap::map(&file)?))
+		};
+		let inner = Arc::new(LocalFileInner { file, map });
+		Ok(Self { inner })
+	}
+
 	fn clone(&self) -> Self {
 		Self {
 			inner: self.inner.clone(),
@@ -280,7 +404,7 @@ impl LocalFile {
 
 	#[cfg(not(target_arch = "wasm32"))]
 	fn from_file(file: fs::File) -> io::Result<Self> {
-		let inner = Arc::new(LocalFileInner { file });
+		let inner = Arc::new(LocalFileInner { file, map: None });
 		Ok(Self { inner })
 	}
 	#[cfg(target_arch = "wasm32")]
@@ -416,9 +540,26 @@ impl Page for LocalFile {
 	}
 	fn read(
 		&self, mut offset: u64, len: usize,
-	) -> LocalBoxFuture<'static, Result<Box<[u8]>, Self::Error>> {
+	) -> LocalBoxFuture<'static, Result<SharedBuf, Self::Error>> {
+		#[cfg(not(target_arch = "wasm32"))]
+		{
+			if let Some(map) = &self.inner.map {
+				let start =
+					usize::try_from(offset).map_or(map.len(), |offset| offset.min(map.len()));
+				let end = start + len.min(map.len() - start);
+				return future::ok(map.slice(start..end)).boxed_local();
+			}
+		}
 		let self_ = self.clone();
 		Box::pin(async move {
+			// Don't allocate more than remains, as `Reader` asks for large reads. Small reads
+			// are allocated as asked, to save finding the length on every one.
+			let len = if len > 64 * 1024 {
+				let remaining = self_.len().await?.saturating_sub(offset);
+				usize::try_from(remaining).map_or(len, |remaining| remaining.min(len))
+			} else {
+				len
+			};
 			let mut buf_ = vec![0; len];
 			let mut buf = &mut *buf_;
 			while !buf.is_empty() {
@@ -435,7 +576,7 @@ impl Page for LocalFile {
 			}
 			let len = len - buf.len();
 			buf_.truncate(len);
-			Ok(buf_.into_boxed_slice())
+			Ok(buf_.into())
 		})
 	}
 	fn write(
@@ -0,0 +1,196 @@
+use bytes::{Buf, Bytes, BytesMut};
+use futures::{stream, Stream, StreamExt};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, future::Future, io, pin::Pin, time::Duration};
+
+/// How failed requests to a remote store are retried.
+///
+/// Retries back off exponentially with full jitter, i.e. the `n`th retry waits a random delay of
+/// up to `initial_backoff * 2^(n-1)`, capped at `max_backoff`. Which errors are worth retrying is
+/// up to the store: typically connection failures, timeouts, throttling and server errors.
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct RetryPolicy {
+	max_attempts: u32,
+	initial_backoff: Duration,
+	max_backoff: Duration,
+}
+impl RetryPolicy {
+	/// The default policy: up to 10 attempts, backing off from 10ms up to 10s.
+	pub fn new() -> Self {
+		Self {
+			max_attempts: 10,
+			initial_backoff: Duration::from_millis(10),
+			max_backoff: Duration::from_secs(10),
+		}
+	}
+	/// Give up after `max_attempts` attempts, including the first. Must be at least 1.
+	pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+		assert_ne!(max_attempts, 0, "at least one attempt must be made");
+		self.max_attempts = max_attempts;
+		self
+	}
+	/// The upper bound of the delay before the first retry.
+	pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+		self.initial_backoff = initial_backoff;
+		self
+	}
+	/// The upper bound of the delay before any retry.
+	pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+		self.max_backoff = max_backoff;
+		self
+	}
+	/// The delay before retrying after `attempts` failed attempts, or `None` if no attempts
+	/// remain. `0` is treated as `1`.
+	pub fn backoff(&self, attempts: u32) -> Option<Duration> {
+		if attempts >= self.max_attempts {
+			return None;
+		}
+		let backoff = 2_u32
+			.checked_pow(attempts.saturating_sub(1))
+			.and_then(|factor| self.initial_backoff.checked_mul(factor))
+			.map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff));
+		Some(backoff.mul_f64(rand::thread_rng().gen_range(0.0, 1.0)))
+	}
+	/// Await `f()`, calling it again after a backoff while it fails with an error for which
+	/// `retryable` returns `true`.
+	pub async fn retry<F, Fut, T, E, R>(self, mut f: F, mut retryable: R) -> Result<T, E>
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = Result<T, E>>,
+		R: FnMut(&E) -> bool,
+	{
+		let mut attempts = 0;
+		loop {
+			attempts += 1;
+			match f().await {
+				Err(err) if retryable(&err) => match self.backoff(attempts) {
+					Some(backoff) => tokio::time::delay_for(backoff).await,
+					None => break Err(err),
+				},
+				res => break res,
+			}
+		}
+	}
+}
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Download the bytes from `offset` up to `end` (or the end of the object if `None`), resuming
+/// from where it left off should the response body be cut short or fail partway.
+///
+/// `request(start)` should request the bytes from `start` onwards, with for example an HTTP
+/// `Range: bytes=start-` header, retrying as appropriate. It resolves to the response body
+/// together with the offset that body begins at, which is `start` if the range was honoured or `0`
+/// if the whole object was sent instead; any bytes before `start` are discarded.
+///
+/// Without an `end`, a body that ends cleanly is taken to be complete. Resuming gives up, yielding
+/// the last error, once `policy` runs out of attempts without making progress.
+pub fn get_resumable<F, Fut, S, E>(
+	policy: RetryPolicy, offset: u64, end: Option<u64>, request: F,
+) -> impl Stream<Item = Result<Bytes, E>>
+where
+	F: FnMut(u64) -> Fut,
+	Fut: Future<Output = Result<(S, u64), E>>,
+	S: Stream<Item = Result<Bytes, E>>,
+	E: From<io::Error>,
+{
+	let state = Resume {
+		request,
+		body: None,
+		position: offset,
+		skip: 0,
+		attempts: 0,
+	};
+	stream::unfold(Some(state), move |state| async move {
+		let mut state = state?;
+		loop {
+			if end.map_or(false, |end| state.position >= end) {
+				return None;
+			}
+			if state.body.is_none() {
+				match (state.request)(state.position).await {
+					Ok((body, start)) => {
+						state.skip = state.position.saturating_sub(start);
+						state.body = Some(Box::pin(body));
+					}
+					Err(err) => return Some((Err(err), None)),
+				}
+			}
+			let err = match state.body.as_mut().unwrap().next().await {
+				Some(Ok(mut chunk)) => {
+					let skip = usize::try_from(state.skip.min(u64::try_from(chunk.len()).unwrap()))
+						.unwrap();
+					chunk.advance(skip);
+					state.skip -= u64::try_from(skip).unwrap();
+					if let Some(end) = end {
+						let remaining = end - state.position;
+						chunk.truncate(usize::try_from(remaining).unwrap_or(usize::max_value()));
+					}
+					if chunk.is_empty() {
+						continue;
+					}
+					state.position += u64::try_from(chunk.len()).unwrap();
+					state.attempts = 0;
+					return Some((Ok(chunk), Some(state)));
+				}
+				Some(Err(err)) => err,
+				None if end.is_none() && state.skip == 0 => return None,
+				None => {
+					io::Error::new(io::ErrorKind::UnexpectedEof, "response body truncated").into()
+				}
+			};
+			// The body was cut short, so request the rest
+			state.body = None;
+			state.attempts += 1;
+			match policy.backoff(state.attempts) {
+				Some(backoff) => tokio::time::delay_for(backoff).await,
+				None => return Some((Err(err), None)),
+			}
+		}
+	})
+}
+
+struct Resume<F, S> {
+	request: F,
+	body: Option<Pin<Box<S>>>,
+	position: u64,
+	skip: u64,
+	attempts: u32,
+}
+
+/// Download the `len` bytes at `offset`, as per [`get_resumable`].
+///
+/// `len` must not reach past the end of the object. A body that arrives in one piece is returned
+/// without copying.
+pub async fn get_range<F, Fut, S, E>(
+	policy: RetryPolicy, offset: u64, len: usize, request: F,
+) -> Result<Bytes, E>
+where
+	F: FnMut(u64) -> Fut,
+	Fut: Future<Output = Result<(S, u64), E>>,
+	S: Stream<Item = Result<Bytes, E>>,
+	E: From<io::Error>,
+{
+	if len == 0 {
+		return Ok(Bytes::new());
+	}
+	let end = offset + u64::try_from(len).unwrap();
+	let mut body = Box::pin(get_resumable(policy, offset, Some(end), request));
+	let first = match body.next().await {
+		Some(chunk) => chunk?,
+		None => return Ok(Bytes::new()),
+	};
+	if first.len() == len {
+		return Ok(first);
+	}
+	let mut buf = BytesMut::with_capacity(len);
+	buf.extend_from_slice(&first);
+	while let Some(chunk) = body.next().await {
+		buf.extend_from_slice(&chunk?);
+	}
+	Ok(buf.freeze())
+}
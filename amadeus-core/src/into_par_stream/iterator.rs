@@ -24,6 +24,7 @@ impl<I: Iterator + Sized> IteratorExt for I {}
 
 impl_par_dist_rename! {
 	#[pin_project]
+	#[derive(Clone)]
 	pub struct IterParStream<I>(pub(crate) I);
 
 	impl<I: Iterator> ParallelStream for IterParStream<I>
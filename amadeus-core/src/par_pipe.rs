@@ -1,9 +1,9 @@
 #![allow(unused_qualifications)]
 
 use either::Either;
-use futures::Stream;
+use futures::{Future, Stream};
 use serde_closure::traits;
-use std::{cmp::Ordering, hash::Hash, iter, ops};
+use std::{cmp::Ordering, hash::Hash, iter, ops, time::Duration};
 
 use super::{par_sink::*, par_stream::*};
 use crate::{pipe::Pipe, pool::ProcessSend};
@@ -72,6 +72,63 @@ macro_rules! pipe {
 				$assert_pipe(Filter::new(self, f))
 			}
 
+			#[inline]
+			fn sample_fraction(self, fraction: f64, seed: u64) -> SampleFraction<Self>
+			where
+				Self::Output: Hash,
+				Self: Sized,
+			{
+				$assert_pipe(SampleFraction::new(
+					self,
+					::amadeus_streaming::SampleFraction::new(fraction, seed),
+				))
+			}
+
+			#[inline]
+			fn map_async<Fut, F>(self, concurrency: usize, f: F) -> MapAsync<Self, F>
+			where
+				F: $fns::FnMut(Self::Output) -> Fut + Clone + $send + 'static,
+				Fut: Future,
+				Self: Sized,
+			{
+				$assert_pipe(MapAsync::new(self, concurrency, f))
+			}
+
+			#[inline]
+			fn filter_async<Fut, F>(self, concurrency: usize, f: F) -> FilterAsync<Self, F>
+			where
+				F: $fns::FnMut(&Self::Output) -> Fut + Clone + $send + 'static,
+				Fut: Future<Output = bool>,
+				Self: Sized,
+			{
+				$assert_pipe(FilterAsync::new(self, concurrency, f))
+			}
+
+			#[inline]
+			fn chunks(self, n: usize) -> Chunks<Self>
+			where
+				Self: Sized,
+			{
+				$assert_pipe(Chunks::new(self, n))
+			}
+
+			#[inline]
+			fn chunks_timeout(self, n: usize, timeout: Duration) -> ChunksTimeout<Self>
+			where
+				Self: Sized,
+			{
+				$assert_pipe(ChunksTimeout::new(self, n, timeout))
+			}
+
+			#[inline]
+			fn flatten(self) -> Flatten<Self>
+			where
+				Self::Output: IntoIterator,
+				Self: Sized,
+			{
+				$assert_pipe(Flatten::new(self))
+			}
+
 			#[inline]
 			fn cloned<'a, T>(self) -> Cloned<Self, T, Input>
 			where
@@ -177,6 +234,33 @@ macro_rules! pipe {
 				$assert_sink(Histogram::new(self))
 			}
 
+			#[inline]
+			fn histogram_buckets(self, edges: &[f64]) -> HistogramBuckets<Self>
+			where
+				Self::Output: ToF64,
+				Self: Sized,
+			{
+				$assert_sink(HistogramBuckets::new(self, edges.to_vec()))
+			}
+
+			#[inline]
+			fn histogram_auto(self, buckets: usize) -> HistogramAuto<Self>
+			where
+				Self::Output: ToF64,
+				Self: Sized,
+			{
+				$assert_sink(HistogramAuto::new(self, buckets))
+			}
+
+			#[inline]
+			fn histogram_log(self, min: f64, base: f64) -> HistogramLog<Self>
+			where
+				Self::Output: ToF64,
+				Self: Sized,
+			{
+				$assert_sink(HistogramLog::new(self, min, base))
+			}
+
 			#[inline]
 			fn sort_n_by<F>(self, n: usize, cmp: F) -> Sort<Self, F>
 			where
@@ -220,6 +304,51 @@ macro_rules! pipe {
 				$assert_sink(StdDev::new(self))
 			}
 
+			#[inline]
+			fn moments(self) -> Moments<Self>
+			where
+				Self::Output: ToF64,
+				Self: Sized,
+			{
+				$assert_sink(Moments::new(self))
+			}
+
+			#[inline]
+			fn covariance(self) -> Covariance<Self>
+			where
+				Self: $pipe<Input, Output = (f64, f64)> + Sized,
+			{
+				$assert_sink(Covariance::new(self))
+			}
+
+			#[inline]
+			fn pearson_correlation(self) -> PearsonCorrelation<Self>
+			where
+				Self: $pipe<Input, Output = (f64, f64)> + Sized,
+			{
+				$assert_sink(PearsonCorrelation::new(self))
+			}
+
+			#[inline]
+			fn linear_regression(self) -> LinearRegression<Self>
+			where
+				Self: $pipe<Input, Output = (Vec<f64>, f64)> + Sized,
+			{
+				$assert_sink(LinearRegression::new(self))
+			}
+
+			#[inline]
+			fn quantiles(self, quantiles: &[f64]) -> Quantiles<Self>
+			where
+				Self: $pipe<Input, Output = f64> + Sized,
+			{
+				assert!(
+					quantiles.iter().all(|q| (0.0..=1.0).contains(q)),
+					"quantiles must be between 0 and 1"
+				);
+				$assert_sink(Quantiles::new(self, quantiles.to_vec()))
+			}
+
 			#[inline]
 			fn combine<F>(self, f: F) -> Combine<Self, F>
 			where
@@ -299,6 +428,19 @@ macro_rules! pipe {
 				$assert_sink(MostFrequent::new(self, n, probability, tolerance))
 			}
 
+			#[inline]
+			fn top_k_by<F, G, K>(
+				self, key: F, weight: G, k: usize, probability: f64, tolerance: f64,
+			) -> TopKBy<Self, F, G>
+			where
+				F: $fns::FnMut(&Self::Output) -> K + Clone + $send + 'static,
+				G: $fns::FnMut(&Self::Output) -> u64 + Clone + $send + 'static,
+				K: Hash + Eq + Clone + $send + 'static,
+				Self: Sized,
+			{
+				$assert_sink(TopKBy::new(self, key, weight, k, probability, tolerance))
+			}
+
 			#[inline]
 			fn most_distinct<A, B>(
 				self, n: usize, probability: f64, tolerance: f64, error_rate: f64,
@@ -317,6 +459,15 @@ macro_rules! pipe {
 				))
 			}
 
+			#[inline]
+			fn bloom_filter(self, capacity: usize, false_positive_rate: f64) -> BloomFilter<Self>
+			where
+				Self::Output: Hash + 'static,
+				Self: Sized,
+			{
+				$assert_sink(BloomFilter::new(self, capacity, false_positive_rate))
+			}
+
 			#[inline]
 			fn sample_unstable(self, samples: usize) -> SampleUnstable<Self>
 			where
@@ -326,6 +477,35 @@ macro_rules! pipe {
 				$assert_sink(SampleUnstable::new(self, samples))
 			}
 
+			#[inline]
+			fn sample<T>(self, samples: usize, seed: u64) -> Sample<Self>
+			where
+				T: $send + 'static,
+				Self: $pipe<Input, Output = (Position, T)> + Sized,
+			{
+				$assert_sink(Sample::new(self, samples, seed))
+			}
+
+			#[inline]
+			fn sample_weighted<T, F>(self, samples: usize, seed: u64, f: F) -> SampleWeighted<Self, F>
+			where
+				F: $fns::FnMut(&T) -> f64 + Clone + $send + 'static,
+				T: $send + 'static,
+				Self: $pipe<Input, Output = (Position, T)> + Sized,
+			{
+				$assert_sink(SampleWeighted::new(self, samples, seed, f))
+			}
+
+			#[inline]
+			fn sample_stratified<A, B>(self, samples: usize, seed: u64) -> SampleStratified<Self>
+			where
+				Self: $pipe<Input, Output = (Position, (A, B))> + Sized,
+				A: Hash + Eq + $send + 'static,
+				B: $send + 'static,
+			{
+				$assert_sink(SampleStratified::new(self, samples, seed))
+			}
+
 			#[inline]
 			fn all<F>(self, f: F) -> All<Self, F>
 			where
@@ -352,6 +532,34 @@ macro_rules! pipe {
 			{
 				$assert_sink(Collect::new(self))
 			}
+
+			#[inline]
+			fn collect_ordered<T, B>(self) -> CollectOrdered<Self, B>
+			where
+				T: $send + 'static,
+				B: iter::FromIterator<T> + 'static,
+				Self: $pipe<Input, Output = (Position, T)> + Sized,
+			{
+				$assert_sink(CollectOrdered::new(self))
+			}
+
+			#[inline]
+			fn take<T>(self, n: usize) -> Limit<Self>
+			where
+				T: $send + 'static,
+				Self: $pipe<Input, Output = (Position, T)> + Sized,
+			{
+				$assert_sink(Limit::new(self, 0, n))
+			}
+
+			#[inline]
+			fn limit<T>(self, offset: usize, n: usize) -> Limit<Self>
+			where
+				T: $send + 'static,
+				Self: $pipe<Input, Output = (Position, T)> + Sized,
+			{
+				$assert_sink(Limit::new(self, offset, n))
+			}
 		}
 
 		#[inline(always)]
@@ -1,18 +1,26 @@
 mod all;
 mod any;
+mod buckets;
 mod collect;
+mod collect_ordered;
 mod combine;
 mod combiner;
 mod count;
+mod covariance;
 mod fold;
 mod folder;
 mod for_each;
 mod fork;
 mod group_by;
 mod histogram;
+mod limit;
+mod linear_regression;
 mod max;
 mod mean;
+mod moments;
+mod partition_lengths;
 mod pipe;
+mod quantiles;
 mod sample;
 mod stddev;
 mod sum;
@@ -22,7 +30,7 @@ use super::par_pipe::*;
 use crate::{pipe::Sink, pool::ProcessSend};
 
 pub use self::{
-	all::*, any::*, collect::*, combine::*, combiner::*, count::*, fold::*, folder::*, for_each::*, fork::*, group_by::*, histogram::*, max::*, mean::*, pipe::*, sample::*, stddev::*, sum::*, tuple::*
+	all::*, any::*, buckets::*, collect::*, collect_ordered::*, combine::*, combiner::*, count::*, covariance::*, fold::*, folder::*, for_each::*, fork::*, group_by::*, histogram::*, limit::*, linear_regression::*, max::*, mean::*, moments::*, partition_lengths::*, pipe::*, quantiles::*, sample::*, stddev::*, sum::*, tuple::*
 };
 
 #[must_use]
@@ -0,0 +1,363 @@
+use amadeus_streaming::{TDigest, UnionAssign};
+use derive_new::new;
+use educe::Educe;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::BTreeMap, marker::PhantomData};
+
+use super::{folder_par_sink, FolderSync, FolderSyncReducer, ParallelPipe, ParallelSink, ToF64};
+use crate::util::{f64_to_u64, u64_to_f64};
+
+/// The compression of the t-digest used to estimate bucket counts.
+const COMPRESSION: f64 = 100.0;
+
+/// A bucket of a histogram, counting the values `lower <= value < upper`.
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Bucket {
+	/// The inclusive lower edge.
+	pub lower: f64,
+	/// The exclusive upper edge, or inclusive for the last bucket of
+	/// [`histogram_buckets()`](crate::par_stream::ParallelStream::histogram_buckets)
+	/// and [`histogram_auto()`](crate::par_stream::ParallelStream::histogram_auto).
+	pub upper: f64,
+	/// The number of values in the bucket.
+	pub count: u64,
+}
+
+#[must_use]
+pub struct HistogramBuckets<P> {
+	pipe: P,
+	edges: Vec<f64>,
+}
+impl<P> HistogramBuckets<P> {
+	pub fn new(pipe: P, edges: Vec<f64>) -> Self {
+		assert!(edges.len() >= 2, "at least two edges are required");
+		assert!(
+			edges.windows(2).all(|edges| edges[0] < edges[1]),
+			"edges must be strictly increasing"
+		);
+		Self { pipe, edges }
+	}
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item>, Item> ParallelSink<Item> for HistogramBuckets<P>
+	where
+		P::Output: ToF64,
+	{
+		folder_par_sink!(
+			BucketsFolder<StepA>,
+			BucketsFolder<StepB>,
+			self,
+			BucketsFolder::new(self.edges.clone()),
+			BucketsFolder::new(self.edges)
+		);
+	}
+}
+
+#[derive(Educe, Serialize, Deserialize, new)]
+#[educe(Clone)]
+#[serde(bound = "")]
+pub struct BucketsFolder<Step> {
+	edges: Vec<f64>,
+	marker: PhantomData<fn() -> Step>,
+}
+
+pub struct StepA;
+pub struct StepB;
+
+impl<Item: ToF64> FolderSync<Item> for BucketsFolder<StepA> {
+	type State = Vec<u64>;
+	type Done = Vec<u64>;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		vec![0; self.edges.len() - 1]
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: Item) {
+		let item = item.to_f64();
+		let (first, last) = (self.edges[0], self.edges[self.edges.len() - 1]);
+		if !(first..=last).contains(&item) {
+			return;
+		}
+		// The number of edges <= item
+		let below = self
+			.edges
+			.binary_search_by(|&edge| {
+				if edge <= item {
+					Ordering::Less
+				} else {
+					Ordering::Greater
+				}
+			})
+			.unwrap_err();
+		// The last bucket includes its upper edge
+		let i = (below - 1).min(state.len() - 1);
+		state[i] += 1;
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+impl FolderSync<Vec<u64>> for BucketsFolder<StepB> {
+	type State = Vec<u64>;
+	type Done = Vec<Bucket>;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		vec![0; self.edges.len() - 1]
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: Vec<u64>) {
+		for (count, item) in state.iter_mut().zip(item) {
+			*count += item;
+		}
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		self.edges
+			.windows(2)
+			.zip(state)
+			.map(|(edges, count)| Bucket {
+				lower: edges[0],
+				upper: edges[1],
+				count,
+			})
+			.collect()
+	}
+}
+
+#[must_use]
+pub struct HistogramAuto<P> {
+	pipe: P,
+	buckets: usize,
+}
+impl<P> HistogramAuto<P> {
+	pub fn new(pipe: P, buckets: usize) -> Self {
+		assert_ne!(buckets, 0, "buckets must be greater than 0");
+		Self { pipe, buckets }
+	}
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item>, Item> ParallelSink<Item> for HistogramAuto<P>
+	where
+		P::Output: ToF64,
+	{
+		folder_par_sink!(
+			AutoFolder<StepA>,
+			AutoFolder<StepB>,
+			self,
+			AutoFolder::new(self.buckets),
+			AutoFolder::new(self.buckets)
+		);
+	}
+}
+
+#[derive(Educe, Serialize, Deserialize, new)]
+#[educe(Clone)]
+#[serde(bound = "")]
+pub struct AutoFolder<Step> {
+	buckets: usize,
+	marker: PhantomData<fn() -> Step>,
+}
+
+impl<Item: ToF64> FolderSync<Item> for AutoFolder<StepA> {
+	type State = TDigest;
+	type Done = TDigest;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		TDigest::new(COMPRESSION)
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: Item) {
+		state.push(item.to_f64())
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+impl FolderSync<TDigest> for AutoFolder<StepB> {
+	type State = TDigest;
+	type Done = Vec<Bucket>;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		TDigest::new(COMPRESSION)
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: TDigest) {
+		state.union_assign(item)
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		// Equal-frequency buckets, with edges at evenly spaced quantiles of the
+		// digest. Edges that coincide, as they do for heavily repeated values,
+		// are merged, so there may be fewer buckets than requested.
+		if state.is_empty() {
+			return Vec::new();
+		}
+		let buckets = u64_to_f64(self.buckets as u64);
+		let mut edges = Vec::with_capacity(self.buckets + 1);
+		for i in 0..=self.buckets {
+			let edge = state.quantile(u64_to_f64(i as u64) / buckets).unwrap();
+			if edges.last().map_or(true, |&last| edge > last) {
+				edges.push(edge);
+			}
+		}
+		if edges.len() == 1 {
+			// Every value is the same
+			return vec![Bucket {
+				lower: edges[0],
+				upper: edges[0],
+				count: f64_to_u64(state.count()),
+			}];
+		}
+		let mut cumulative = 0;
+		edges
+			.windows(2)
+			.enumerate()
+			.map(|(i, edges)| {
+				let (lower, upper) = (edges[0], edges[1]);
+				let total = if i == self.buckets - 1 || upper >= state.max().unwrap() {
+					f64_to_u64(state.count())
+				} else {
+					f64_to_u64((state.cdf(upper).unwrap() * state.count()).round())
+				};
+				let count = total.saturating_sub(cumulative);
+				cumulative = cumulative.max(total);
+				Bucket {
+					lower,
+					upper,
+					count,
+				}
+			})
+			.collect()
+	}
+}
+
+/// Counts values in logarithmically sized buckets: `[0, min)`, then
+/// `[min, min * base)`, `[min * base, min * base^2)` and so on.
+///
+/// NaN, infinite and negative values fall in no bucket, and are silently
+/// dropped rather than counted.
+#[must_use]
+pub struct HistogramLog<P> {
+	pipe: P,
+	min: f64,
+	base: f64,
+}
+impl<P> HistogramLog<P> {
+	pub fn new(pipe: P, min: f64, base: f64) -> Self {
+		assert!(min > 0.0, "min must be greater than 0");
+		assert!(base > 1.0, "base must be greater than 1");
+		// Bucket indices are u32, so every finite value must land in one that fits
+		assert!(
+			(f64::MAX.ln() - min.ln()) / base.ln() < f64::from(u32::MAX - 2),
+			"base is too close to 1 for the buckets to be indexed"
+		);
+		Self { pipe, min, base }
+	}
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item>, Item> ParallelSink<Item> for HistogramLog<P>
+	where
+		P::Output: ToF64,
+	{
+		folder_par_sink!(
+			LogFolder<StepA>,
+			LogFolder<StepB>,
+			self,
+			LogFolder::new(self.min, self.base),
+			LogFolder::new(self.min, self.base)
+		);
+	}
+}
+
+#[derive(Educe, Serialize, Deserialize, new)]
+#[educe(Clone)]
+#[serde(bound = "")]
+pub struct LogFolder<Step> {
+	min: f64,
+	base: f64,
+	marker: PhantomData<fn() -> Step>,
+}
+
+impl<Step> LogFolder<Step> {
+	/// The lower edge of bucket `i`; bucket 0 is `[0, min)`.
+	fn lower(&self, i: u32) -> f64 {
+		if i == 0 {
+			0.0
+		} else {
+			self.min * self.base.powf(f64::from(i - 1))
+		}
+	}
+}
+
+impl<Item: ToF64> FolderSync<Item> for LogFolder<StepA> {
+	type State = BTreeMap<u32, u64>;
+	type Done = BTreeMap<u32, u64>;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		BTreeMap::new()
+	}
+	#[inline(always)]
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+	fn push(&mut self, state: &mut Self::State, item: Item) {
+		let item = item.to_f64();
+		if !item.is_finite() || item < 0.0 {
+			return;
+		}
+		let mut i = if item < self.min {
+			0
+		} else {
+			((item / self.min).ln() / self.base.ln()).floor() as u32 + 1
+		};
+		// Correct for rounding in the logarithm
+		while i > 0 && self.lower(i) > item {
+			i -= 1;
+		}
+		while self.lower(i + 1) <= item {
+			i += 1;
+		}
+		*state.entry(i).or_default() += 1;
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+impl FolderSync<BTreeMap<u32, u64>> for LogFolder<StepB> {
+	type State = BTreeMap<u32, u64>;
+	type Done = Vec<Bucket>;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		BTreeMap::new()
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: BTreeMap<u32, u64>) {
+		for (i, count) in item {
+			*state.entry(i).or_default() += count;
+		}
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+			.into_iter()
+			.map(|(i, count)| Bucket {
+				lower: self.lower(i),
+				upper: self.lower(i + 1),
+				count,
+			})
+			.collect()
+	}
+}
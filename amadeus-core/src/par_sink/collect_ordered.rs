@@ -0,0 +1,106 @@
+#![allow(clippy::type_complexity)]
+
+use derive_new::new;
+use educe::Educe;
+use itertools::Itertools;
+use replace_with::replace_with_or_default;
+use serde::{Deserialize, Serialize};
+use std::{iter::FromIterator, marker::PhantomData};
+
+use super::{folder_par_sink, FolderSync, FolderSyncReducer, ParallelPipe, ParallelSink};
+use crate::par_stream::Position;
+
+/// Collects items into `A` in the order they appear in the source, rather than
+/// the order in which tasks happen to complete.
+#[derive(new)]
+#[must_use]
+pub struct CollectOrdered<P, A> {
+	pipe: P,
+	marker: PhantomData<fn() -> A>,
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item, Output = (Position, T)>, Item, T, A> ParallelSink<Item>
+		for CollectOrdered<P, A>
+	where
+		T: Send + 'static,
+		A: FromIterator<T> + 'static,
+	{
+		folder_par_sink!(
+			CollectOrderedFolder<T, A, StepA>,
+			CollectOrderedFolder<T, A, StepB>,
+			self,
+			CollectOrderedFolder::new(),
+			CollectOrderedFolder::new()
+		);
+	}
+}
+
+#[derive(Educe, Serialize, Deserialize, new)]
+#[educe(Clone)]
+#[serde(bound = "")]
+pub struct CollectOrderedFolder<T, A, Step> {
+	marker: PhantomData<fn() -> (T, A, Step)>,
+}
+
+pub struct StepA;
+pub struct StepB;
+
+/// Runs of consecutive items, each tagged with the partition they came from.
+type Runs<T> = Vec<(usize, Vec<T>)>;
+
+impl<T, A> FolderSync<(Position, T)> for CollectOrderedFolder<T, A, StepA> {
+	type State = Runs<T>;
+	type Done = Self::State;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		Vec::new()
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, (position, item): (Position, T)) {
+		match state.last_mut() {
+			Some((partition, run)) if *partition == position.partition => run.push(item),
+			_ => state.push((position.partition, vec![item])),
+		}
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+impl<T, A> FolderSync<Runs<T>> for CollectOrderedFolder<T, A, StepB>
+where
+	A: FromIterator<T>,
+{
+	type State = Runs<T>;
+	type Done = A;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		Vec::new()
+	}
+	fn push(&mut self, state: &mut Self::State, mut runs: Runs<T>) {
+		// Tasks are handed to each worker in order, so this is typically already sorted
+		runs.sort_by_key(|&(partition, _)| partition);
+		replace_with_or_default(state, |state| {
+			state
+				.into_iter()
+				.merge_by(runs, |a, b| a.0 <= b.0)
+				.coalesce(|mut a, b| {
+					if a.0 == b.0 {
+						a.1.extend(b.1);
+						Ok(a)
+					} else {
+						Err((a, b))
+					}
+				})
+				.collect()
+		})
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state.into_iter().flat_map(|(_, run)| run).collect()
+	}
+}
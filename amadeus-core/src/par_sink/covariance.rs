@@ -0,0 +1,215 @@
+use derive_new::new;
+use educe::Educe;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+use super::{folder_par_sink, FolderSync, FolderSyncReducer, ParallelPipe, ParallelSink};
+use crate::util::u64_to_f64;
+
+#[derive(new)]
+#[must_use]
+pub struct Covariance<P> {
+	pipe: P,
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item, Output = (f64, f64)>, Item> ParallelSink<Item> for Covariance<P> {
+		folder_par_sink!(
+			CovarianceFolder<StepA>,
+			CovarianceFolder<StepB>,
+			self,
+			CovarianceFolder::new(),
+			CovarianceFolder::new()
+		);
+	}
+}
+
+#[derive(new)]
+#[must_use]
+pub struct PearsonCorrelation<P> {
+	pipe: P,
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item, Output = (f64, f64)>, Item> ParallelSink<Item> for PearsonCorrelation<P> {
+		folder_par_sink!(
+			CovarianceFolder<StepA>,
+			CorrelationFolder,
+			self,
+			CovarianceFolder::new(),
+			CorrelationFolder
+		);
+	}
+}
+
+#[derive(Educe, Serialize, Deserialize, new)]
+#[educe(Clone)]
+#[serde(bound = "")]
+pub struct CovarianceFolder<Step> {
+	marker: PhantomData<fn() -> Step>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CorrelationFolder;
+
+pub struct StepA;
+pub struct StepB;
+
+/// The count, means, and co-moments (sums of the products of differences from
+/// the means) of a stream of rows of values. These are updated with Welford's
+/// algorithm and merged with [Chan et al.'s](https://en.wikipedia.org/wiki/Algorithms_for_calculating_variance#Parallel_algorithm),
+/// which avoids the catastrophic cancellation of the naïve sum-of-products
+/// formulae.
+///
+/// This underlies the other sinks that summarise the spread of values, such as
+/// [`Moments`](super::Moments) and [`LinearRegression`](super::LinearRegression).
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
+pub struct CoMoments {
+	count: u64,
+	means: Vec<f64>,
+	/// Row-major, `means.len()` square.
+	co_moments: Vec<f64>,
+}
+
+impl CoMoments {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	/// Add a row. Every row must have the same number of values.
+	pub fn push(&mut self, values: &[f64]) {
+		let n = values.len();
+		if self.count == 0 && self.means.len() != n {
+			self.means = vec![0.0; n];
+			self.co_moments = vec![0.0; n * n];
+		}
+		assert_eq!(
+			self.means.len(),
+			n,
+			"every row must have the same number of values"
+		);
+		self.count += 1;
+		let count = u64_to_f64(self.count);
+		// (x - mean_old) * (y - mean_new) = (x - mean_old) * (y - mean_old) * (n - 1) / n
+		let weight = (count - 1.0) / count;
+		for (i, (x, mean_x)) in values.iter().zip(&self.means).enumerate() {
+			let row = &mut self.co_moments[i * n..(i + 1) * n];
+			for (co_moment, (y, mean_y)) in row.iter_mut().zip(values.iter().zip(&self.means)) {
+				*co_moment += (x - mean_x) * (y - mean_y) * weight;
+			}
+		}
+		for (mean, value) in self.means.iter_mut().zip(values) {
+			*mean += (value - *mean) / count;
+		}
+	}
+	pub fn merge(&mut self, other: Self) {
+		if other.count == 0 {
+			return;
+		}
+		if self.count == 0 {
+			*self = other;
+			return;
+		}
+		assert_eq!(
+			self.means.len(),
+			other.means.len(),
+			"every row must have the same number of values"
+		);
+		let n = self.means.len();
+		let (count_a, count_b) = (u64_to_f64(self.count), u64_to_f64(other.count));
+		let count = count_a + count_b;
+		let weight = count_a * count_b / count;
+		let deltas = self
+			.means
+			.iter()
+			.zip(&other.means)
+			.map(|(a, b)| b - a)
+			.collect::<Vec<_>>();
+		for (mean, delta) in self.means.iter_mut().zip(&deltas) {
+			*mean += delta * count_b / count;
+		}
+		for i in 0..n {
+			for j in 0..n {
+				self.co_moments[i * n + j] +=
+					other.co_moments[i * n + j] + deltas[i] * deltas[j] * weight;
+			}
+		}
+		self.count += other.count;
+	}
+	/// The number of rows.
+	pub fn count(&self) -> u64 {
+		self.count
+	}
+	/// The mean of each value, or empty if there are no rows.
+	pub fn means(&self) -> &[f64] {
+		&self.means
+	}
+	/// The sum of the products of the differences of the `i`th and `j`th values
+	/// from their means, or 0 if there are no rows.
+	pub fn co_moment(&self, i: usize, j: usize) -> f64 {
+		if self.count == 0 {
+			return 0.0;
+		}
+		self.co_moments[i * self.means.len() + j]
+	}
+}
+
+impl FolderSync<(f64, f64)> for CovarianceFolder<StepA> {
+	type State = CoMoments;
+	type Done = CoMoments;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		CoMoments::new()
+	}
+
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: (f64, f64)) {
+		state.push(&[item.0, item.1])
+	}
+
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+impl FolderSync<CoMoments> for CovarianceFolder<StepB> {
+	type State = CoMoments;
+	type Done = f64;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		CoMoments::new()
+	}
+
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: CoMoments) {
+		state.merge(item)
+	}
+
+	/// The population covariance.
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state.co_moment(0, 1) / u64_to_f64(state.count())
+	}
+}
+
+impl FolderSync<CoMoments> for CorrelationFolder {
+	type State = CoMoments;
+	type Done = f64;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		CoMoments::new()
+	}
+
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: CoMoments) {
+		state.merge(item)
+	}
+
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state.co_moment(0, 1) / (state.co_moment(0, 0) * state.co_moment(1, 1)).sqrt()
+	}
+}
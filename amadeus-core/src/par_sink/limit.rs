@@ -0,0 +1,169 @@
+use derive_new::new;
+use educe::Educe;
+use futures::{ready, Stream};
+use itertools::Itertools;
+use pin_project::pin_project;
+use replace_with::replace_with_or_default;
+use serde::{Deserialize, Serialize};
+use std::{
+	marker::PhantomData, mem, pin::Pin, task::{Context, Poll}
+};
+
+use super::{
+	DistributedPipe, DistributedSink, Final, FolderSync, FolderSyncReducer, Inter, ParallelPipe, ParallelSink, Reducer, ReducerProcessSend, ReducerSend
+};
+use crate::{par_stream::Position, pipe::Sink, pool::ProcessSend};
+
+/// Takes `n` items, after skipping `skip` items, in the order they appear in
+/// the source.
+///
+/// Each worker stops reading as soon as it holds enough items, as any further
+/// items it would see come later in the source.
+#[derive(new)]
+#[must_use]
+pub struct Limit<P> {
+	pipe: P,
+	skip: usize,
+	n: usize,
+}
+
+impl<P: ParallelPipe<Item, Output = (Position, T)>, Item, T> ParallelSink<Item> for Limit<P>
+where
+	T: Send + 'static,
+{
+	type Done = Vec<T>;
+	type Pipe = P;
+	type ReduceA = LimitReducer<T>;
+	type ReduceC = FolderSyncReducer<Vec<(Position, T)>, LimitFolder<T>, Final>;
+
+	fn reducers(self) -> (Self::Pipe, Self::ReduceA, Self::ReduceC) {
+		let limit = self.skip.saturating_add(self.n);
+		(
+			self.pipe,
+			LimitReducer::new(limit),
+			FolderSyncReducer::new(LimitFolder::new(self.skip, limit)),
+		)
+	}
+}
+impl<P: DistributedPipe<Item, Output = (Position, T)>, Item, T> DistributedSink<Item> for Limit<P>
+where
+	T: ProcessSend + 'static,
+{
+	type Done = Vec<T>;
+	type Pipe = P;
+	type ReduceA = LimitReducer<T>;
+	type ReduceB = FolderSyncReducer<Vec<(Position, T)>, LimitFolder<T>, Inter>;
+	type ReduceC = FolderSyncReducer<Vec<(Position, T)>, LimitFolder<T>, Final>;
+
+	fn reducers(self) -> (Self::Pipe, Self::ReduceA, Self::ReduceB, Self::ReduceC) {
+		let limit = self.skip.saturating_add(self.n);
+		(
+			self.pipe,
+			LimitReducer::new(limit),
+			FolderSyncReducer::new(LimitFolder::new(self.skip, limit)),
+			FolderSyncReducer::new(LimitFolder::new(self.skip, limit)),
+		)
+	}
+}
+
+#[derive(Educe, Serialize, Deserialize, new)]
+#[educe(Clone)]
+#[serde(bound = "")]
+pub struct LimitReducer<T> {
+	limit: usize,
+	marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Reducer<(Position, T)> for LimitReducer<T> {
+	type Done = Vec<(Position, T)>;
+	type Async = LimitReducerAsync<T>;
+
+	fn into_async(self) -> Self::Async {
+		LimitReducerAsync {
+			items: Vec::new(),
+			limit: self.limit,
+		}
+	}
+}
+impl<T> ReducerProcessSend<(Position, T)> for LimitReducer<T>
+where
+	T: ProcessSend + 'static,
+{
+	type Done = Vec<(Position, T)>;
+}
+impl<T> ReducerSend<(Position, T)> for LimitReducer<T>
+where
+	T: Send + 'static,
+{
+	type Done = Vec<(Position, T)>;
+}
+
+#[pin_project]
+#[derive(Serialize, Deserialize)]
+#[serde(
+	bound(serialize = "T: Serialize"),
+	bound(deserialize = "T: Deserialize<'de>")
+)]
+pub struct LimitReducerAsync<T> {
+	items: Vec<(Position, T)>,
+	limit: usize,
+}
+
+impl<T> Sink<(Position, T)> for LimitReducerAsync<T> {
+	type Done = Vec<(Position, T)>;
+
+	#[inline(always)]
+	fn poll_forward(
+		self: Pin<&mut Self>, cx: &mut Context,
+		mut stream: Pin<&mut impl Stream<Item = (Position, T)>>,
+	) -> Poll<Self::Done> {
+		let self_ = self.project();
+		while self_.items.len() < *self_.limit {
+			if let Some(item) = ready!(stream.as_mut().poll_next(cx)) {
+				self_.items.push(item);
+			} else {
+				break;
+			}
+		}
+		Poll::Ready(mem::take(self_.items))
+	}
+}
+
+#[derive(Educe, Serialize, Deserialize, new)]
+#[educe(Clone)]
+#[serde(bound = "")]
+pub struct LimitFolder<T> {
+	skip: usize,
+	limit: usize,
+	marker: PhantomData<fn() -> T>,
+}
+
+impl<T> FolderSync<Vec<(Position, T)>> for LimitFolder<T> {
+	type State = Vec<(Position, T)>;
+	type Done = Vec<T>;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		Vec::new()
+	}
+	fn push(&mut self, state: &mut Self::State, mut items: Vec<(Position, T)>) {
+		// Items from any one worker are already in order, so this is cheap
+		items.sort_by_key(|&(position, _)| position);
+		let limit = self.limit;
+		replace_with_or_default(state, |state| {
+			state
+				.into_iter()
+				.merge_by(items, |a, b| a.0 <= b.0)
+				.take(limit)
+				.collect()
+		})
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+			.into_iter()
+			.skip(self.skip)
+			.map(|(_, item)| item)
+			.collect()
+	}
+}
@@ -0,0 +1,134 @@
+use derive_new::new;
+use educe::Educe;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, marker::PhantomData};
+
+use super::{
+	folder_par_sink, CoMoments, FolderSync, FolderSyncReducer, ParallelPipe, ParallelSink
+};
+
+/// Pivots this small relative to the largest co-moment are treated as zero, in
+/// which case the coefficients aren't uniquely determined.
+const SINGULAR_TOLERANCE: f64 = 1e-12;
+
+#[derive(new)]
+#[must_use]
+pub struct LinearRegression<P> {
+	pipe: P,
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item, Output = (Vec<f64>, f64)>, Item> ParallelSink<Item> for LinearRegression<P> {
+		folder_par_sink!(
+			LinearRegressionFolder<StepA>,
+			LinearRegressionFolder<StepB>,
+			self,
+			LinearRegressionFolder::new(),
+			LinearRegressionFolder::new()
+		);
+	}
+}
+
+#[derive(Educe, Serialize, Deserialize, new)]
+#[educe(Clone)]
+#[serde(bound = "")]
+pub struct LinearRegressionFolder<Step> {
+	marker: PhantomData<fn() -> Step>,
+}
+
+pub struct StepA;
+pub struct StepB;
+
+/// Solve for the intercept followed by the coefficient of each feature, from the
+/// co-moments of the features followed by the target, i.e. on centred data.
+fn solve(state: &CoMoments) -> Vec<f64> {
+	let n = state.means().len();
+	if n == 0 {
+		return Vec::new();
+	}
+	let features = n - 1;
+	// Augmented matrix of the centred normal equations: Cxx β = Cxy
+	let mut a = (0..features)
+		.map(|i| (0..n).map(|j| state.co_moment(i, j)).collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+	let scale = a
+		.iter()
+		.flat_map(|row| &row[..features])
+		.fold(0.0_f64, |max, x| max.max(x.abs()));
+	// Gaussian elimination with partial pivoting
+	for col in 0..features {
+		let pivot = (col..features)
+			.max_by(|&i, &j| {
+				a[i][col]
+					.abs()
+					.partial_cmp(&a[j][col].abs())
+					.unwrap_or(Ordering::Equal)
+			})
+			.unwrap();
+		if a[pivot][col].abs() <= scale * SINGULAR_TOLERANCE {
+			return vec![f64::NAN; n];
+		}
+		a.swap(col, pivot);
+		for row in col + 1..features {
+			let factor = a[row][col] / a[col][col];
+			for k in col..n {
+				a[row][k] -= factor * a[col][k];
+			}
+		}
+	}
+	let mut coefficients = vec![0.0; features];
+	for row in (0..features).rev() {
+		let sum = (row + 1..features)
+			.map(|k| a[row][k] * coefficients[k])
+			.sum::<f64>();
+		coefficients[row] = (a[row][features] - sum) / a[row][row];
+	}
+	let intercept = state.means()[features]
+		- coefficients
+			.iter()
+			.zip(state.means())
+			.map(|(coefficient, mean)| coefficient * mean)
+			.sum::<f64>();
+	Some(intercept).into_iter().chain(coefficients).collect()
+}
+
+impl FolderSync<(Vec<f64>, f64)> for LinearRegressionFolder<StepA> {
+	type State = CoMoments;
+	type Done = CoMoments;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		CoMoments::new()
+	}
+
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, (mut features, target): (Vec<f64>, f64)) {
+		features.push(target);
+		state.push(&features)
+	}
+
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+impl FolderSync<CoMoments> for LinearRegressionFolder<StepB> {
+	type State = CoMoments;
+	type Done = Vec<f64>;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		CoMoments::new()
+	}
+
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: CoMoments) {
+		state.merge(item)
+	}
+
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		solve(&state)
+	}
+}
@@ -0,0 +1,199 @@
+use derive_new::new;
+use educe::Educe;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+use super::{
+	folder_par_sink, CoMoments, FolderSync, FolderSyncReducer, ParallelPipe, ParallelSink
+};
+use crate::util::u64_to_f64;
+
+#[derive(new)]
+#[must_use]
+pub struct Moments<P> {
+	pipe: P,
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item>, Item> ParallelSink<Item> for Moments<P>
+	where
+		P::Output: ToF64,
+	{
+		folder_par_sink!(
+			MomentsFolder<StepA>,
+			MomentsFolder<StepB>,
+			self,
+			MomentsFolder::new(),
+			MomentsFolder::new()
+		);
+	}
+}
+
+/// Numeric types that can be summarised by [`moments()`](crate::par_stream::ParallelStream::moments).
+pub trait ToF64 {
+	/// Convert to the nearest `f64`.
+	fn to_f64(self) -> f64;
+}
+macro_rules! to_f64 {
+	($($t:ty)*) => ($(
+		impl ToF64 for $t {
+			#[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
+			#[inline(always)]
+			fn to_f64(self) -> f64 {
+				self as f64
+			}
+		}
+	)*);
+}
+to_f64!(u8 i8 u16 i16 u32 i32 u64 i64 u128 i128 usize isize f32);
+impl ToF64 for f64 {
+	#[inline(always)]
+	fn to_f64(self) -> f64 {
+		self
+	}
+}
+
+/// The result of [`moments()`](crate::par_stream::ParallelStream::moments).
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct MomentsSummary {
+	/// The number of values.
+	pub count: u64,
+	/// The mean.
+	pub mean: f64,
+	/// The population variance.
+	pub variance: f64,
+	/// The population skewness.
+	pub skewness: f64,
+	/// The population excess kurtosis, i.e. 0 for a normal distribution.
+	pub kurtosis: f64,
+}
+
+#[derive(Educe, Serialize, Deserialize, new)]
+#[educe(Clone)]
+#[serde(bound = "")]
+pub struct MomentsFolder<Step> {
+	marker: PhantomData<fn() -> Step>,
+}
+
+pub struct StepA;
+pub struct StepB;
+
+/// The [`CoMoments`] of the values, plus the sums of the 3rd and 4th powers of
+/// their differences from the mean. The latter are updated and merged with the
+/// one-pass formulae of [Pébay](https://www.osti.gov/servlets/purl/1028931).
+#[derive(Serialize, Deserialize, new)]
+pub struct MomentsState {
+	#[new(default)]
+	moments: CoMoments,
+	#[new(default)]
+	m3: f64,
+	#[new(default)]
+	m4: f64,
+}
+
+impl MomentsState {
+	fn mean(&self) -> f64 {
+		self.moments.means().first().copied().unwrap_or(0.0)
+	}
+	fn m2(&self) -> f64 {
+		self.moments.co_moment(0, 0)
+	}
+	fn push(&mut self, value: f64) {
+		let count_prev = u64_to_f64(self.moments.count());
+		let count = count_prev + 1.0;
+		let (mean, m2) = (self.mean(), self.m2());
+		let delta = value - mean;
+		let delta_n = delta / count;
+		let delta_n2 = delta_n * delta_n;
+		let term = delta * delta_n * count_prev;
+		self.m4 += term * delta_n2 * (count * count - 3.0 * count + 3.0) + 6.0 * delta_n2 * m2
+			- 4.0 * delta_n * self.m3;
+		self.m3 += term * delta_n * (count - 2.0) - 3.0 * delta_n * m2;
+		self.moments.push(&[value]);
+	}
+	fn merge(&mut self, other: Self) {
+		if other.moments.count() == 0 {
+			return;
+		}
+		if self.moments.count() == 0 {
+			*self = other;
+			return;
+		}
+		let (count_a, count_b) = (
+			u64_to_f64(self.moments.count()),
+			u64_to_f64(other.moments.count()),
+		);
+		let count = count_a + count_b;
+		let (m2_a, m2_b) = (self.m2(), other.m2());
+		let delta = other.mean() - self.mean();
+		let delta2 = delta * delta;
+		let weight = count_a * count_b / count;
+		self.m4 += other.m4
+			+ delta2
+				* delta2 * weight
+				* (count_a * count_a - count_a * count_b + count_b * count_b)
+				/ (count * count)
+			+ 6.0 * delta2 * (count_a * count_a * m2_b + count_b * count_b * m2_a)
+				/ (count * count)
+			+ 4.0 * delta * (count_a * other.m3 - count_b * self.m3) / count;
+		self.m3 += other.m3
+			+ delta2 * delta * weight * (count_a - count_b) / count
+			+ 3.0 * delta * (count_a * m2_b - count_b * m2_a) / count;
+		self.moments.merge(other.moments);
+	}
+	fn summary(&self) -> MomentsSummary {
+		let count = u64_to_f64(self.moments.count());
+		let m2 = self.m2();
+		MomentsSummary {
+			count: self.moments.count(),
+			mean: if self.moments.count() != 0 {
+				self.mean()
+			} else {
+				f64::NAN
+			},
+			variance: m2 / count,
+			skewness: count.sqrt() * self.m3 / m2.powf(1.5),
+			kurtosis: count * self.m4 / (m2 * m2) - 3.0,
+		}
+	}
+}
+
+impl<Item: ToF64> FolderSync<Item> for MomentsFolder<StepA> {
+	type State = MomentsState;
+	type Done = MomentsState;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		MomentsState::new()
+	}
+
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: Item) {
+		state.push(item.to_f64())
+	}
+
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+impl FolderSync<MomentsState> for MomentsFolder<StepB> {
+	type State = MomentsState;
+	type Done = MomentsSummary;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		MomentsState::new()
+	}
+
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: MomentsState) {
+		state.merge(item)
+	}
+
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state.summary()
+	}
+}
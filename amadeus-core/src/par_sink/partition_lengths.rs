@@ -0,0 +1,91 @@
+use derive_new::new;
+use educe::Educe;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+use super::{folder_par_sink, FolderSync, FolderSyncReducer, ParallelPipe, ParallelSink};
+use crate::par_stream::Position;
+
+/// Counts the items in each partition, and resolves to the index of the first
+/// item of each partition were the stream numbered contiguously, followed by
+/// the total number of items.
+#[derive(new)]
+#[must_use]
+pub struct PartitionLengths<P> {
+	pipe: P,
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item, Output = (Position, T)>, Item, T> ParallelSink<Item>
+		for PartitionLengths<P>
+	{
+		folder_par_sink!(
+			PartitionLengthsFolder<StepA>,
+			PartitionLengthsFolder<StepB>,
+			self,
+			PartitionLengthsFolder::new(),
+			PartitionLengthsFolder::new()
+		);
+	}
+}
+
+#[derive(Educe, Serialize, Deserialize, new)]
+#[educe(Clone)]
+#[serde(bound = "")]
+pub struct PartitionLengthsFolder<Step> {
+	marker: PhantomData<fn() -> Step>,
+}
+
+pub struct StepA;
+pub struct StepB;
+
+impl<T> FolderSync<(Position, T)> for PartitionLengthsFolder<StepA> {
+	type State = Vec<usize>;
+	type Done = Self::State;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		Vec::new()
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, (position, _item): (Position, T)) {
+		if state.len() <= position.partition {
+			state.resize(position.partition + 1, 0);
+		}
+		state[position.partition] += 1;
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+impl FolderSync<Vec<usize>> for PartitionLengthsFolder<StepB> {
+	type State = Vec<usize>;
+	type Done = Vec<usize>;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		Vec::new()
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, lengths: Vec<usize>) {
+		if state.len() < lengths.len() {
+			state.resize(lengths.len(), 0);
+		}
+		for (state, length) in state.iter_mut().zip(lengths) {
+			*state += length;
+		}
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		let mut offset = 0;
+		let mut offsets = Vec::with_capacity(state.len() + 1);
+		for length in state {
+			offsets.push(offset);
+			offset += length;
+		}
+		offsets.push(offset);
+		offsets
+	}
+}
@@ -1,17 +1,19 @@
 #![allow(clippy::type_complexity)]
 
 use amadeus_streaming::{
-	HyperLogLogMagnitude, SampleUnstable as SASampleUnstable, Sort as SASort, Top
+	BloomFilter as SABloomFilter, HyperLogLogMagnitude, Sample as SASample, SampleStratified as SASampleStratified, SampleUnstable as SASampleUnstable, SampleWeighted as SASampleWeighted, Sort as SASort, Top
 };
 use derive_new::new;
+use educe::Educe;
 use rand::thread_rng;
 use serde::{Deserialize, Serialize};
 use serde_closure::traits;
-use std::{cmp::Ordering, hash::Hash};
+use std::{cmp::Ordering, hash::Hash, marker::PhantomData};
 
 use super::{
 	folder_par_sink, FolderSync, FolderSyncReducer, ParallelPipe, ParallelSink, SumFolder, SumZeroFolder
 };
+use crate::par_stream::Position;
 
 #[derive(new)]
 #[must_use]
@@ -55,6 +57,210 @@ impl<Item> FolderSync<Item> for SampleUnstableFolder {
 	}
 }
 
+#[derive(new)]
+#[must_use]
+pub struct Sample<P> {
+	pipe: P,
+	samples: usize,
+	seed: u64,
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item, Output = (Position, T)>, Item, T> ParallelSink<Item> for Sample<P>
+	where
+		T: Send + 'static,
+	{
+		folder_par_sink!(
+			SampleFolder<StepA>,
+			SampleFolder<StepB>,
+			self,
+			SampleFolder::new(self.samples, self.seed),
+			SampleFolder::new(self.samples, self.seed)
+		);
+	}
+}
+
+#[derive(Educe, Serialize, Deserialize, new)]
+#[educe(Clone)]
+#[serde(bound = "")]
+pub struct SampleFolder<Step> {
+	samples: usize,
+	seed: u64,
+	marker: PhantomData<fn() -> Step>,
+}
+
+pub struct StepA;
+pub struct StepB;
+
+impl<T> FolderSync<(Position, T)> for SampleFolder<StepA> {
+	type State = SASample<T>;
+	type Done = Self::State;
+
+	fn zero(&mut self) -> Self::State {
+		SASample::new(self.samples, self.seed)
+	}
+	fn push(&mut self, state: &mut Self::State, (position, item): (Position, T)) {
+		state.push(&position, item)
+	}
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+impl<T> FolderSync<SASample<T>> for SampleFolder<StepB> {
+	type State = SASample<T>;
+	type Done = Self::State;
+
+	fn zero(&mut self) -> Self::State {
+		SASample::new(self.samples, self.seed)
+	}
+	fn push(&mut self, state: &mut Self::State, item: SASample<T>) {
+		*state += item;
+	}
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+#[derive(new)]
+#[must_use]
+pub struct SampleWeighted<P, F> {
+	pipe: P,
+	samples: usize,
+	seed: u64,
+	f: F,
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item, Output = (Position, T)>, Item, T, F> ParallelSink<Item> for SampleWeighted<P, F>
+	where
+		F: for<'a> traits::FnMut<(&'a T,), Output = f64> + Clone + Send + 'static,
+		T: Send + 'static,
+	{
+		folder_par_sink!(
+			SampleWeightedFolder<F>,
+			SampleWeightedMergeFolder,
+			self,
+			SampleWeightedFolder::new(self.samples, self.seed, self.f),
+			SampleWeightedMergeFolder::new(self.samples, self.seed)
+		);
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, new)]
+pub struct SampleWeightedFolder<F> {
+	samples: usize,
+	seed: u64,
+	f: F,
+}
+
+impl<T, F> FolderSync<(Position, T)> for SampleWeightedFolder<F>
+where
+	F: for<'a> traits::FnMut<(&'a T,), Output = f64>,
+{
+	type State = SASampleWeighted<T>;
+	type Done = Self::State;
+
+	fn zero(&mut self) -> Self::State {
+		SASampleWeighted::new(self.samples, self.seed)
+	}
+	fn push(&mut self, state: &mut Self::State, (position, item): (Position, T)) {
+		let weight = self.f.call_mut((&item,));
+		state.push(&position, item, weight)
+	}
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, new)]
+pub struct SampleWeightedMergeFolder {
+	samples: usize,
+	seed: u64,
+}
+
+impl<T> FolderSync<SASampleWeighted<T>> for SampleWeightedMergeFolder {
+	type State = SASampleWeighted<T>;
+	type Done = Self::State;
+
+	fn zero(&mut self) -> Self::State {
+		SASampleWeighted::new(self.samples, self.seed)
+	}
+	fn push(&mut self, state: &mut Self::State, item: SASampleWeighted<T>) {
+		*state += item;
+	}
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+#[derive(new)]
+#[must_use]
+pub struct SampleStratified<P> {
+	pipe: P,
+	samples: usize,
+	seed: u64,
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item, Output = (Position, (A, B))>, Item, A, B> ParallelSink<Item> for SampleStratified<P>
+	where
+		A: Hash + Eq + Send + 'static,
+		B: Send + 'static,
+	{
+		folder_par_sink!(
+			SampleStratifiedFolder<StepA>,
+			SampleStratifiedFolder<StepB>,
+			self,
+			SampleStratifiedFolder::new(self.samples, self.seed),
+			SampleStratifiedFolder::new(self.samples, self.seed)
+		);
+	}
+}
+
+#[derive(Educe, Serialize, Deserialize, new)]
+#[educe(Clone)]
+#[serde(bound = "")]
+pub struct SampleStratifiedFolder<Step> {
+	samples: usize,
+	seed: u64,
+	marker: PhantomData<fn() -> Step>,
+}
+
+impl<A, B> FolderSync<(Position, (A, B))> for SampleStratifiedFolder<StepA>
+where
+	A: Hash + Eq,
+{
+	type State = SASampleStratified<A, B>;
+	type Done = Self::State;
+
+	fn zero(&mut self) -> Self::State {
+		SASampleStratified::new(self.samples, self.seed)
+	}
+	fn push(&mut self, state: &mut Self::State, (position, (key, item)): (Position, (A, B))) {
+		state.push(key, &position, item)
+	}
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+impl<A, B> FolderSync<SASampleStratified<A, B>> for SampleStratifiedFolder<StepB>
+where
+	A: Hash + Eq,
+{
+	type State = SASampleStratified<A, B>;
+	type Done = Self::State;
+
+	fn zero(&mut self) -> Self::State {
+		SASampleStratified::new(self.samples, self.seed)
+	}
+	fn push(&mut self, state: &mut Self::State, item: SASampleStratified<A, B>) {
+		*state += item;
+	}
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
 #[derive(new)]
 #[must_use]
 pub struct Sort<P, F> {
@@ -154,6 +360,64 @@ where
 	}
 }
 
+#[derive(new)]
+#[must_use]
+pub struct TopKBy<P, F, G> {
+	pipe: P,
+	key: F,
+	weight: G,
+	k: usize,
+	probability: f64,
+	tolerance: f64,
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item>, Item, F, G, K> ParallelSink<Item> for TopKBy<P, F, G>
+	where
+		F: for<'a> traits::FnMut<(&'a P::Output,), Output = K> + Clone + Send + 'static,
+		G: for<'a> traits::FnMut<(&'a P::Output,), Output = u64> + Clone + Send + 'static,
+		K: Clone + Hash + Eq + Send + 'static,
+	{
+		folder_par_sink!(
+			TopKByFolder<F, G>,
+			SumZeroFolder<Top<K, u64>>,
+			self,
+			TopKByFolder::new(self.key, self.weight, self.k, self.probability, self.tolerance),
+			SumZeroFolder::new(Top::new(self.k, self.probability, self.tolerance, ()))
+		);
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, new)]
+pub struct TopKByFolder<F, G> {
+	key: F,
+	weight: G,
+	k: usize,
+	probability: f64,
+	tolerance: f64,
+}
+
+impl<Item, F, G, K> FolderSync<Item> for TopKByFolder<F, G>
+where
+	F: for<'a> traits::FnMut<(&'a Item,), Output = K>,
+	G: for<'a> traits::FnMut<(&'a Item,), Output = u64>,
+	K: Clone + Hash + Eq,
+{
+	type State = Top<K, u64>;
+	type Done = Self::State;
+
+	fn zero(&mut self) -> Self::State {
+		Top::new(self.k, self.probability, self.tolerance, ())
+	}
+	fn push(&mut self, state: &mut Self::State, item: Item) {
+		let weight = self.weight.call_mut((&item,));
+		state.push(self.key.call_mut((&item,)), &weight)
+	}
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
 #[derive(new)]
 #[must_use]
 pub struct MostDistinct<P> {
@@ -211,3 +475,50 @@ where
 		state
 	}
 }
+
+#[derive(new)]
+#[must_use]
+pub struct BloomFilter<P> {
+	pipe: P,
+	capacity: usize,
+	false_positive_rate: f64,
+}
+
+impl_par_dist! {
+	impl<P: ParallelPipe<Item>, Item> ParallelSink<Item> for BloomFilter<P>
+	where
+		P::Output: Hash + 'static,
+	{
+		folder_par_sink!(
+			BloomFilterFolder,
+			SumZeroFolder<SABloomFilter<P::Output>>,
+			self,
+			BloomFilterFolder::new(self.capacity, self.false_positive_rate),
+			SumZeroFolder::new(SABloomFilter::new(self.capacity, self.false_positive_rate))
+		);
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, new)]
+pub struct BloomFilterFolder {
+	capacity: usize,
+	false_positive_rate: f64,
+}
+
+impl<Item> FolderSync<Item> for BloomFilterFolder
+where
+	Item: Hash,
+{
+	type State = SABloomFilter<Item>;
+	type Done = Self::State;
+
+	fn zero(&mut self) -> Self::State {
+		SABloomFilter::new(self.capacity, self.false_positive_rate)
+	}
+	fn push(&mut self, state: &mut Self::State, item: Item) {
+		state.push(&item)
+	}
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
@@ -81,6 +81,10 @@ impl FolderSync<SDState> for SDFolder<StepB> {
 
 	#[inline(always)]
 	fn push(&mut self, state: &mut Self::State, item: SDState) {
+		// Merging two empty states would otherwise give 0/0
+		if item.count == 0 {
+			return;
+		}
 		let (s1, s2) = (u64_to_f64(state.count), u64_to_f64(item.count));
 		let meandiffsq = (state.mean - item.mean) * (state.mean - item.mean);
 		let mean = ((s1 * state.mean) + (s2 * item.mean)) / (s1 + s2);
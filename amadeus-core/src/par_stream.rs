@@ -4,26 +4,35 @@
 #![allow(clippy::too_many_lines, unused_qualifications)]
 
 mod chain;
+mod chunks;
 mod cloned;
+mod enumerate;
 mod filter;
 mod filter_map_sync;
 mod flat_map;
 mod flat_map_sync;
+mod flatten;
 mod identity;
 mod inspect;
 mod join;
 mod map;
+mod map_async;
 mod map_sync;
+mod sample_fraction;
 mod sum_type;
+mod take;
 mod update;
+mod zip;
 
 use async_trait::async_trait;
 use either::Either;
-use futures::{future, pin_mut, stream::StreamExt as _, Stream};
+use futures::{future, pin_mut, stream::StreamExt as _, Future, Stream};
 use indexmap::IndexMap;
 use serde_closure::{traits, FnOnce};
 use std::{
-	cmp::Ordering, hash::Hash, iter, ops, pin::Pin, task::{Context, Poll}, vec
+	cmp::Ordering, hash::Hash, iter, ops, pin::Pin, sync::{
+		atomic::{self, AtomicBool}, Arc
+	}, task::{Context, Poll}, time::Duration, vec
 };
 
 use super::{par_pipe::*, par_sink::*};
@@ -32,7 +41,7 @@ use crate::{
 };
 
 pub use self::{
-	chain::*, cloned::*, filter::*, filter_map_sync::*, flat_map::*, flat_map_sync::*, identity::*, inspect::*, join::*, map::*, map_sync::*, update::*
+	chain::*, chunks::*, cloned::*, enumerate::*, filter::*, filter_map_sync::*, flat_map::*, flat_map_sync::*, flatten::*, identity::*, inspect::*, join::*, map::*, map_async::*, map_sync::*, sample_fraction::*, take::*, update::*, zip::*
 };
 
 #[must_use]
@@ -103,6 +112,63 @@ macro_rules! stream {
 				$assert_stream(Filter::new(self, f))
 			}
 
+			#[inline]
+			fn sample_fraction(self, fraction: f64, seed: u64) -> SampleFraction<Self>
+			where
+				Self::Item: Hash,
+				Self: Sized,
+			{
+				$assert_stream(SampleFraction::new(
+					self,
+					::amadeus_streaming::SampleFraction::new(fraction, seed),
+				))
+			}
+
+			#[inline]
+			fn map_async<Fut, F>(self, concurrency: usize, f: F) -> MapAsync<Self, F>
+			where
+				F: $fns::FnMut(Self::Item) -> Fut + Clone + $send + 'static,
+				Fut: Future,
+				Self: Sized,
+			{
+				$assert_stream(MapAsync::new(self, concurrency, f))
+			}
+
+			#[inline]
+			fn filter_async<Fut, F>(self, concurrency: usize, f: F) -> FilterAsync<Self, F>
+			where
+				F: $fns::FnMut(&Self::Item) -> Fut + Clone + $send + 'static,
+				Fut: Future<Output = bool>,
+				Self: Sized,
+			{
+				$assert_stream(FilterAsync::new(self, concurrency, f))
+			}
+
+			#[inline]
+			fn chunks(self, n: usize) -> Chunks<Self>
+			where
+				Self: Sized,
+			{
+				$assert_stream(Chunks::new(self, n))
+			}
+
+			#[inline]
+			fn chunks_timeout(self, n: usize, timeout: Duration) -> ChunksTimeout<Self>
+			where
+				Self: Sized,
+			{
+				$assert_stream(ChunksTimeout::new(self, n, timeout))
+			}
+
+			#[inline]
+			fn flatten(self) -> Flatten<Self>
+			where
+				Self::Item: IntoIterator,
+				Self: Sized,
+			{
+				$assert_stream(Flatten::new(self))
+			}
+
 			#[inline]
 			fn left_join<K, V1, V2>(self, right: impl IntoIterator<Item = (K, V2)>) -> LeftJoin<Self, K, V1, V2>
 			where
@@ -134,6 +200,47 @@ macro_rules! stream {
 				$assert_stream(Chain::new(self, chain.$into_stream_fn()))
 			}
 
+			#[inline]
+			fn positions(self) -> Positions<Self>
+			where
+				Self: Sized,
+			{
+				$assert_stream(Positions::new(self))
+			}
+
+			/// Pairs each item with its index in the stream, counting contiguously
+			/// from 0 in the order items appear in the source.
+			///
+			/// The stream is run once up front to count the items in each
+			/// partition, so it must be `Clone`, and yield the same partitions each
+			/// time it's run. Adaptors aren't `Clone`, so this is typically called
+			/// on a source, such as an iterator's [`par`](crate::into_par_stream::IteratorExt::par). Use [`positions`](Self::positions) to avoid the
+			/// extra pass where a contiguous index isn't needed.
+			#[inline]
+			async fn enumerate<P>(self, pool: &P) -> Enumerate<Self>
+			where
+				P: $pool,
+				Self::Item: 'static,
+				Self::Task: 'static,
+				Self: Clone + Sized,
+			{
+				let offsets = self
+					.clone()
+					.positions()
+					.pipe(pool, PartitionLengths::new(Identity))
+					.await;
+				$assert_stream(Enumerate::new(self, offsets))
+			}
+
+			#[inline]
+			fn zip<B>(self, other: B) -> Zip<Self, B::$xxx, Self::Task>
+			where
+				B: $into_stream,
+				Self: Sized,
+			{
+				$assert_stream(Zip::new(self, other.$into_stream_fn()))
+			}
+
 			#[inline]
 			async fn for_each<P, F>(self, pool: &P, f: F)
 			where
@@ -177,6 +284,45 @@ macro_rules! stream {
 					.await
 			}
 
+			#[inline]
+			async fn histogram_buckets<P>(self, pool: &P, edges: &[f64]) -> Vec<Bucket>
+			where
+				P: $pool,
+				Self::Item: ToF64 + 'static,
+				Self::Task: 'static,
+				Self: Sized,
+			{
+				self.pipe(pool, $pipe::<Self::Item>::histogram_buckets(Identity, edges))
+					.await
+			}
+
+			#[inline]
+			async fn histogram_auto<P>(self, pool: &P, buckets: usize) -> Vec<Bucket>
+			where
+				P: $pool,
+				Self::Item: ToF64 + 'static,
+				Self::Task: 'static,
+				Self: Sized,
+			{
+				self.pipe(pool, $pipe::<Self::Item>::histogram_auto(Identity, buckets))
+					.await
+			}
+
+			/// Counts the items in logarithmically sized buckets, as per
+			/// [`HistogramLog`](crate::par_sink::HistogramLog). NaN, infinite and
+			/// negative items aren't counted.
+			#[inline]
+			async fn histogram_log<P>(self, pool: &P, min: f64, base: f64) -> Vec<Bucket>
+			where
+				P: $pool,
+				Self::Item: ToF64 + 'static,
+				Self::Task: 'static,
+				Self: Sized,
+			{
+				self.pipe(pool, $pipe::<Self::Item>::histogram_log(Identity, min, base))
+					.await
+			}
+
 			#[inline]
 			async fn sort_n_by<P, F>(self, pool: &P, n: usize, cmp: F) -> ::amadeus_streaming::Sort<Self::Item, F>
 			where
@@ -239,6 +385,66 @@ macro_rules! stream {
 				.await
 			}
 
+			#[inline]
+			async fn moments<P>(self, pool: &P) -> MomentsSummary
+			where
+				P: $pool,
+				Self::Item: ToF64 + 'static,
+				Self::Task: 'static,
+				Self: Sized,
+			{
+				self.pipe(pool, $pipe::<Self::Item>::moments(Identity))
+				.await
+			}
+
+			#[inline]
+			async fn covariance<P>(self, pool: &P) -> f64
+			where
+				P: $pool,
+				Self::Item: 'static,
+				Self::Task: 'static,
+				Self: $stream<Item = (f64, f64)> + Sized,
+			{
+				self.pipe(pool, $pipe::<Self::Item>::covariance(Identity))
+				.await
+			}
+
+			#[inline]
+			async fn pearson_correlation<P>(self, pool: &P) -> f64
+			where
+				P: $pool,
+				Self::Item: 'static,
+				Self::Task: 'static,
+				Self: $stream<Item = (f64, f64)> + Sized,
+			{
+				self.pipe(pool, $pipe::<Self::Item>::pearson_correlation(Identity))
+				.await
+			}
+
+			#[inline]
+			async fn linear_regression<P>(self, pool: &P) -> Vec<f64>
+			where
+				P: $pool,
+				Self::Item: 'static,
+				Self::Task: 'static,
+				Self: $stream<Item = (Vec<f64>, f64)> + Sized,
+			{
+				self.pipe(pool, $pipe::<Self::Item>::linear_regression(Identity))
+				.await
+			}
+
+			#[inline]
+			async fn quantiles<P>(self, pool: &P, quantiles: &[f64]) -> Vec<f64>
+			where
+				P: $pool,
+				Self::Item: 'static,
+				Self::Task: 'static,
+				Self: $stream<Item = f64> + Sized,
+			{
+				self.pipe(pool, $pipe::<Self::Item>::quantiles(Identity, quantiles))
+				.await
+			}
+
 			#[inline]
 			async fn combine<P, F>(self, pool: &P, f: F) -> Option<Self::Item>
 			where
@@ -347,6 +553,31 @@ macro_rules! stream {
 				.await
 			}
 
+			/// The `k` keys with the greatest summed weight, estimated with a
+			/// count-min sketch. With the given `probability`, each estimate
+			/// exceeds the true weight by at most `tolerance` times the total
+			/// weight. Lower tolerances and higher probabilities use more memory;
+			/// `0.99` and `0.002` are good defaults.
+			#[inline]
+			async fn top_k_by<P, F, G, K>(
+				self, pool: &P, key: F, weight: G, k: usize, probability: f64, tolerance: f64,
+			) -> ::amadeus_streaming::Top<K, u64>
+			where
+				P: $pool,
+				F: $fns::FnMut(&Self::Item) -> K + Clone + $send + 'static,
+				G: $fns::FnMut(&Self::Item) -> u64 + Clone + $send + 'static,
+				K: Hash + Eq + Clone + $send + 'static,
+				Self::Item: 'static,
+				Self::Task: 'static,
+				Self: Sized,
+			{
+				self.pipe(
+					pool,
+					$pipe::<Self::Item>::top_k_by(Identity, key, weight, k, probability, tolerance),
+				)
+				.await
+			}
+
 			#[inline]
 			async fn most_distinct<P, A, B>(
 				self, pool: &P, n: usize, probability: f64, tolerance: f64, error_rate: f64,
@@ -371,6 +602,23 @@ macro_rules! stream {
 				.await
 			}
 
+			#[inline]
+			async fn bloom_filter<P>(
+				self, pool: &P, capacity: usize, false_positive_rate: f64,
+			) -> ::amadeus_streaming::BloomFilter<Self::Item>
+			where
+				P: $pool,
+				Self::Item: Hash + 'static,
+				Self::Task: 'static,
+				Self: Sized,
+			{
+				self.pipe(
+					pool,
+					$pipe::<Self::Item>::bloom_filter(Identity, capacity, false_positive_rate),
+				)
+				.await
+			}
+
 			#[inline]
 			async fn sample_unstable<P>(
 				self, pool: &P, samples: usize,
@@ -388,6 +636,59 @@ macro_rules! stream {
 				.await
 			}
 
+			#[inline]
+			async fn sample<P>(
+				self, pool: &P, samples: usize, seed: u64,
+			) -> ::amadeus_streaming::Sample<Self::Item>
+			where
+				P: $pool,
+				Self::Item: $send + 'static,
+				Self::Task: 'static,
+				Self: Sized,
+			{
+				self.positions()
+					.pipe(pool, $pipe::<(Position, Self::Item)>::sample(Identity, samples, seed))
+					.await
+			}
+
+			#[inline]
+			async fn sample_weighted<P, F>(
+				self, pool: &P, samples: usize, seed: u64, f: F,
+			) -> ::amadeus_streaming::SampleWeighted<Self::Item>
+			where
+				P: $pool,
+				F: $fns::FnMut(&Self::Item) -> f64 + Clone + $send + 'static,
+				Self::Item: $send + 'static,
+				Self::Task: 'static,
+				Self: Sized,
+			{
+				self.positions()
+					.pipe(
+						pool,
+						$pipe::<(Position, Self::Item)>::sample_weighted(Identity, samples, seed, f),
+					)
+					.await
+			}
+
+			#[inline]
+			async fn sample_stratified<P, A, B>(
+				self, pool: &P, samples: usize, seed: u64,
+			) -> ::amadeus_streaming::SampleStratified<A, B>
+			where
+				P: $pool,
+				Self: $stream<Item = (A, B)> + Sized,
+				A: Hash + Eq + $send + 'static,
+				B: $send + 'static,
+				Self::Task: 'static,
+			{
+				self.positions()
+					.pipe(
+						pool,
+						$pipe::<(Position, Self::Item)>::sample_stratified(Identity, samples, seed),
+					)
+					.await
+			}
+
 			#[inline]
 			async fn all<P, F>(self, pool: &P, f: F) -> bool
 			where
@@ -413,6 +714,69 @@ macro_rules! stream {
 				self.pipe(pool, $pipe::<Self::Item>::any(Identity, f))
 					.await
 			}
+
+			#[inline]
+			async fn collect_ordered<P, B>(self, pool: &P) -> B
+			where
+				P: $pool,
+				B: iter::FromIterator<Self::Item> + 'static,
+				Self::Item: $send + 'static,
+				Self::Task: 'static,
+				Self: Sized,
+			{
+				self.positions()
+					.pipe(pool, $pipe::<(Position, Self::Item)>::collect_ordered(Identity))
+					.await
+			}
+
+			/// Skips the first `n` items, in the order they appear in the source.
+			///
+			/// The positions of those items are found up front, reading no further
+			/// into the stream than needed, so it must be `Clone`, and yield the
+			/// same partitions each time it's run.
+			#[inline]
+			async fn skip<P>(self, pool: &P, n: usize) -> Skip<Self>
+			where
+				P: $pool,
+				Self::Item: 'static,
+				Self::Task: 'static,
+				Self: Clone + Sized,
+			{
+				let positions = MapSync::new(self.clone().positions(), ItemPosition::new())
+					.pipe(pool, $pipe::<(Position, Position)>::take(Identity, n))
+					.await;
+				$assert_stream(Skip::new(self, positions, n))
+			}
+
+			/// Takes the first `n` items, in the order they appear in the source.
+			///
+			/// As with [`skip`](Self::skip), the positions of those items are found
+			/// up front. The resulting stream then only runs the partitions they
+			/// came from.
+			#[inline]
+			async fn take<P>(self, pool: &P, n: usize) -> Take<Self>
+			where
+				P: $pool,
+				Self::Item: 'static,
+				Self::Task: 'static,
+				Self: Clone + Sized,
+			{
+				let positions = MapSync::new(self.clone().positions(), ItemPosition::new())
+					.pipe(pool, $pipe::<(Position, Position)>::take(Identity, n))
+					.await;
+				$assert_stream(Take::new(self, positions))
+			}
+
+			#[inline]
+			async fn limit<P>(self, pool: &P, offset: usize, n: usize) -> Take<Skip<Self>>
+			where
+				P: $pool,
+				Self::Item: 'static,
+				Self::Task: 'static,
+				Self: Clone + Sized,
+			{
+				self.skip(pool, offset).await.take(pool, n).await
+			}
 		}
 
 		#[inline(always)]
@@ -475,20 +839,30 @@ stream!(ParallelStream ParallelPipe ParallelSink FromParallelStream IntoParallel
 			);
 		}
 
+		// Set once the result is known, so that workers stop early rather than relying
+		// on the pool cancelling them when their handles are dropped
+		let cancel = Arc::new(AtomicBool::new(false));
 		let handles = tasks
 			.into_iter()
 			.filter(|tasks| !tasks.is_empty())
 			.map(|tasks| {
 				let reduce_a = reduce_a.clone();
+				let cancel = cancel.clone();
 				pool.spawn(move || async move {
 					let sink = reduce_a.into_async();
 					pin_mut!(sink);
 					// this is faster than stream::iter(tasks.into_iter().map(StreamTask::into_async)).flatten().sink(sink).await
-					for task in tasks.into_iter().map(StreamTask::into_async) {
-							pin_mut!(task);
-							if let Some(ret) = sink.send_all(&mut task).await {
-									return ret;
-							}
+					for task in tasks {
+						if cancel.load(atomic::Ordering::Relaxed) {
+							break;
+						}
+						let task = task
+							.into_async()
+							.take_while(|_| future::ready(!cancel.load(atomic::Ordering::Relaxed)));
+						pin_mut!(task);
+						if let Some(ret) = sink.send_all(&mut task).await {
+							return ret;
+						}
 					}
 					sink.done().await
 				})
@@ -499,7 +873,9 @@ stream!(ParallelStream ParallelPipe ParallelSink FromParallelStream IntoParallel
 		});
 		let reduce_c = reduce_c.into_async();
 		pin_mut!(reduce_c);
-		stream.sink(reduce_c).await
+		let ret = stream.sink(reduce_c).await;
+		cancel.store(true, atomic::Ordering::Relaxed);
+		ret
 	}
 
 	async fn pipe<P, ParSink, A>(self, pool: &P, sink: ParSink) -> A
@@ -638,7 +1014,10 @@ stream!(DistributedStream DistributedPipe DistributedSink FromDistributedStream
 			);
 		}
 
-		let handles = tasks
+		// Identifies this reduction to the processes, so that they can be told to
+		// stop early once the result is known
+		let id = rand::random::<u64>();
+		let mut handles = tasks
 			.into_iter()
 			.filter(|tasks| !tasks.is_empty())
 			.map(|tasks| {
@@ -687,20 +1066,29 @@ stream!(DistributedStream DistributedPipe DistributedSink FromDistributedStream
 							tasks.iter().map(Vec::len).collect::<Vec<_>>()
 						);
 					}
+					let cancellation = crate::pool::register_cancellation(id);
+					let cancel = cancellation.flag();
 					let handles = tasks
 						.into_iter()
 						.filter(|tasks| !tasks.is_empty())
 						.map(|tasks| {
 							let reduce_a = reduce_a.clone();
+							let cancel = cancel.clone();
 							pool.spawn(move || async move {
 								let sink = reduce_a.into_async();
 								pin_mut!(sink);
 								// this is faster than stream::iter(tasks.into_iter().map(StreamTask::into_async)).flatten().sink(sink).await
-								for task in tasks.into_iter().map(StreamTask::into_async) {
-										pin_mut!(task);
-										if let Some(ret) = sink.send_all(&mut task).await {
-												return ret;
-										}
+								for task in tasks {
+									if cancel.load(atomic::Ordering::Relaxed) {
+										break;
+									}
+									let task = task.into_async().take_while(|_| {
+										future::ready(!cancel.load(atomic::Ordering::Relaxed))
+									});
+									pin_mut!(task);
+									if let Some(ret) = sink.send_all(&mut task).await {
+										return ret;
+									}
 								}
 								sink.done().await
 							})
@@ -715,17 +1103,30 @@ stream!(DistributedStream DistributedPipe DistributedSink FromDistributedStream
 					let reduce_b = reduce_b.into_async();
 					async move {
 						pin_mut!(reduce_b);
-						stream.sink(reduce_b).await
+						let ret = stream.sink(reduce_b).await;
+						cancel.store(true, atomic::Ordering::Relaxed);
+						drop(cancellation);
+						ret
 					}
 				}))
 			})
 			.collect::<futures::stream::FuturesUnordered<_>>();
-		let stream = handles.map(|item| {
+		let stream = (&mut handles).map(|item| {
 			item.unwrap_or_else(|err| panic!("Amadeus: task '<unnamed>' panicked at '{}'", err))
 		});
 		let reduce_c = reduce_c.into_async();
 		pin_mut!(reduce_c);
-		stream.sink(reduce_c).await
+		let ret = stream.sink(reduce_c).await;
+		if !handles.is_empty() {
+			// Failing to cancel only means the remaining work runs to completion
+			let _ = pool
+				.spawn_all(FnOnce!(move |_pool: &P::ThreadPool| {
+					crate::pool::cancel(id);
+					future::ready(())
+				}))
+				.await;
+		}
+		ret
 	}
 
 	async fn pipe<P, DistSink, A>(self, pool: &P, sink: DistSink) -> A
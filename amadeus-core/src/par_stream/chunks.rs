@@ -0,0 +1,147 @@
+use pin_project::pin_project;
+use serde::{Deserialize, Serialize};
+use std::{
+	pin::Pin, task::{Context, Poll}, time::Duration
+};
+
+use super::{ParallelPipe, ParallelStream, PipeTask, StreamTask};
+
+/// Groups consecutive items within each task into `Vec`s of `n` items. The
+/// last chunk of each task may be shorter.
+#[pin_project]
+#[must_use]
+pub struct Chunks<P> {
+	#[pin]
+	pipe: P,
+	n: usize,
+}
+impl<P> Chunks<P> {
+	pub fn new(pipe: P, n: usize) -> Self {
+		assert_ne!(n, 0, "chunk size must be greater than 0");
+		Self { pipe, n }
+	}
+}
+
+impl_par_dist! {
+	impl<P: ParallelStream> ParallelStream for Chunks<P> {
+		type Item = Vec<P::Item>;
+		type Task = ChunksTask<P::Task>;
+
+		fn size_hint(&self) -> (usize, Option<usize>) {
+			(0, self.pipe.size_hint().1)
+		}
+		fn next_task(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Task>> {
+			let self_ = self.project();
+			let n = *self_.n;
+			self_
+				.pipe
+				.next_task(cx)
+				.map(|task| task.map(|task| ChunksTask { task, n }))
+		}
+	}
+
+	impl<P: ParallelPipe<Input>, Input> ParallelPipe<Input> for Chunks<P> {
+		type Output = Vec<P::Output>;
+		type Task = ChunksTask<P::Task>;
+
+		fn task(&self) -> Self::Task {
+			let task = self.pipe.task();
+			let n = self.n;
+			ChunksTask { task, n }
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChunksTask<C> {
+	task: C,
+	n: usize,
+}
+
+impl<C: StreamTask> StreamTask for ChunksTask<C> {
+	type Item = Vec<C::Item>;
+	type Async = crate::pipe::Chunks<C::Async, C::Item>;
+
+	fn into_async(self) -> Self::Async {
+		crate::pipe::Chunks::new(self.task.into_async(), self.n)
+	}
+}
+impl<C: PipeTask<Input>, Input> PipeTask<Input> for ChunksTask<C> {
+	type Output = Vec<C::Output>;
+	type Async = crate::pipe::Chunks<C::Async, C::Output>;
+
+	fn into_async(self) -> Self::Async {
+		crate::pipe::Chunks::new(self.task.into_async(), self.n)
+	}
+}
+
+/// Like [`Chunks`], but also yields a partial chunk if `timeout` elapses after
+/// its first item was received.
+#[pin_project]
+#[must_use]
+pub struct ChunksTimeout<P> {
+	#[pin]
+	pipe: P,
+	n: usize,
+	timeout: Duration,
+}
+impl<P> ChunksTimeout<P> {
+	pub fn new(pipe: P, n: usize, timeout: Duration) -> Self {
+		assert_ne!(n, 0, "chunk size must be greater than 0");
+		Self { pipe, n, timeout }
+	}
+}
+
+impl_par_dist! {
+	impl<P: ParallelStream> ParallelStream for ChunksTimeout<P> {
+		type Item = Vec<P::Item>;
+		type Task = ChunksTimeoutTask<P::Task>;
+
+		fn size_hint(&self) -> (usize, Option<usize>) {
+			(0, self.pipe.size_hint().1)
+		}
+		fn next_task(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Task>> {
+			let self_ = self.project();
+			let (n, timeout) = (*self_.n, *self_.timeout);
+			self_
+				.pipe
+				.next_task(cx)
+				.map(|task| task.map(|task| ChunksTimeoutTask { task, n, timeout }))
+		}
+	}
+
+	impl<P: ParallelPipe<Input>, Input> ParallelPipe<Input> for ChunksTimeout<P> {
+		type Output = Vec<P::Output>;
+		type Task = ChunksTimeoutTask<P::Task>;
+
+		fn task(&self) -> Self::Task {
+			let task = self.pipe.task();
+			let (n, timeout) = (self.n, self.timeout);
+			ChunksTimeoutTask { task, n, timeout }
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChunksTimeoutTask<C> {
+	task: C,
+	n: usize,
+	timeout: Duration,
+}
+
+impl<C: StreamTask> StreamTask for ChunksTimeoutTask<C> {
+	type Item = Vec<C::Item>;
+	type Async = crate::pipe::ChunksTimeout<C::Async, C::Item>;
+
+	fn into_async(self) -> Self::Async {
+		crate::pipe::ChunksTimeout::new(self.task.into_async(), self.n, self.timeout)
+	}
+}
+impl<C: PipeTask<Input>, Input> PipeTask<Input> for ChunksTimeoutTask<C> {
+	type Output = Vec<C::Output>;
+	type Async = crate::pipe::ChunksTimeout<C::Async, C::Output>;
+
+	fn into_async(self) -> Self::Async {
+		crate::pipe::ChunksTimeout::new(self.task.into_async(), self.n, self.timeout)
+	}
+}
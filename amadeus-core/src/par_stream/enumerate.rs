@@ -0,0 +1,172 @@
+use derive_new::new;
+use futures::Stream;
+use pin_project::pin_project;
+use serde::{Deserialize, Serialize};
+use std::{
+	fmt, pin::Pin, task::{Context, Poll}
+};
+
+use super::{ParallelStream, StreamTask};
+
+/// The position of an item within a stream: the index of the task it came from
+/// (in the order tasks are yielded by `next_task`), and its offset within that
+/// task.
+///
+/// Positions are deterministic for a given source regardless of the pool or
+/// the number of threads or processes, and order items as they appear in the
+/// source.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Debug, new)]
+pub struct Position {
+	pub partition: usize,
+	pub offset: usize,
+}
+impl fmt::Display for Position {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}:{}", self.partition, self.offset)
+	}
+}
+
+/// Pairs each item with its [`Position`]. Unlike [`Enumerate`] this needs no
+/// counting pass, but positions aren't contiguous across partitions.
+#[pin_project]
+#[derive(new)]
+#[must_use]
+pub struct Positions<P> {
+	#[pin]
+	pipe: P,
+	#[new(default)]
+	partition: usize,
+}
+
+impl_par_dist! {
+	impl<P: ParallelStream> ParallelStream for Positions<P> {
+		type Item = (Position, P::Item);
+		type Task = PositionsTask<P::Task>;
+
+		fn size_hint(&self) -> (usize, Option<usize>) {
+			self.pipe.size_hint()
+		}
+		fn next_task(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Task>> {
+			let self_ = self.project();
+			let partition = self_.partition;
+			self_.pipe.next_task(cx).map(|task| {
+				task.map(|task| {
+					let task = PositionsTask::new(task, *partition);
+					*partition += 1;
+					task
+				})
+			})
+		}
+	}
+}
+
+#[pin_project]
+#[derive(Serialize, Deserialize, new)]
+pub struct PositionsTask<T> {
+	#[pin]
+	task: T,
+	partition: usize,
+	#[new(default)]
+	offset: usize,
+}
+
+impl<C: StreamTask> StreamTask for PositionsTask<C> {
+	type Item = (Position, C::Item);
+	type Async = PositionsTask<C::Async>;
+
+	fn into_async(self) -> Self::Async {
+		PositionsTask::new(self.task.into_async(), self.partition)
+	}
+}
+
+impl<C: Stream> Stream for PositionsTask<C> {
+	type Item = (Position, C::Item);
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+		let self_ = self.project();
+		let (partition, offset) = (*self_.partition, self_.offset);
+		self_.task.poll_next(cx).map(|item| {
+			item.map(|item| {
+				let position = Position::new(partition, *offset);
+				*offset += 1;
+				(position, item)
+			})
+		})
+	}
+}
+
+/// Pairs each item with its index in the stream, counting contiguously from 0
+/// in the order of [`Position`].
+///
+/// `offsets` holds the index of the first item of each partition followed by
+/// the total, as resolved by [`PartitionLengths`](crate::par_sink::PartitionLengths).
+#[pin_project]
+#[derive(new)]
+#[must_use]
+pub struct Enumerate<P> {
+	#[pin]
+	pipe: P,
+	offsets: Vec<usize>,
+	#[new(default)]
+	partition: usize,
+}
+
+impl_par_dist! {
+	impl<P: ParallelStream> ParallelStream for Enumerate<P> {
+		type Item = (usize, P::Item);
+		type Task = EnumerateTask<P::Task>;
+
+		fn size_hint(&self) -> (usize, Option<usize>) {
+			self.pipe.size_hint()
+		}
+		fn next_task(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Task>> {
+			let self_ = self.project();
+			let (offsets, partition) = (self_.offsets, self_.partition);
+			self_.pipe.next_task(cx).map(|task| {
+				task.map(|task| {
+					// Trailing partitions with no items weren't seen while counting
+					let offset = offsets
+						.get(*partition)
+						.or_else(|| offsets.last())
+						.copied()
+						.unwrap_or(0);
+					*partition += 1;
+					EnumerateTask::new(task, offset)
+				})
+			})
+		}
+	}
+}
+
+#[pin_project]
+#[derive(Serialize, Deserialize, new)]
+pub struct EnumerateTask<T> {
+	#[pin]
+	task: T,
+	offset: usize,
+}
+
+impl<C: StreamTask> StreamTask for EnumerateTask<C> {
+	type Item = (usize, C::Item);
+	type Async = EnumerateTask<C::Async>;
+
+	fn into_async(self) -> Self::Async {
+		EnumerateTask::new(self.task.into_async(), self.offset)
+	}
+}
+
+impl<C: Stream> Stream for EnumerateTask<C> {
+	type Item = (usize, C::Item);
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+		let self_ = self.project();
+		let offset = self_.offset;
+		self_.task.poll_next(cx).map(|item| {
+			item.map(|item| {
+				let index = *offset;
+				*offset += 1;
+				(index, item)
+			})
+		})
+	}
+}
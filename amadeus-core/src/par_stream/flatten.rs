@@ -0,0 +1,77 @@
+use derive_new::new;
+use pin_project::pin_project;
+use serde::{Deserialize, Serialize};
+use std::{
+	pin::Pin, task::{Context, Poll}
+};
+
+use super::{ParallelPipe, ParallelStream, PipeTask, StreamTask};
+
+#[pin_project]
+#[derive(new)]
+#[must_use]
+pub struct Flatten<P> {
+	#[pin]
+	pipe: P,
+}
+
+impl_par_dist! {
+	impl<P: ParallelStream> ParallelStream for Flatten<P>
+	where
+		P::Item: IntoIterator,
+	{
+		type Item = <P::Item as IntoIterator>::Item;
+		type Task = FlattenTask<P::Task>;
+
+		fn size_hint(&self) -> (usize, Option<usize>) {
+			(0, None)
+		}
+		fn next_task(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Task>> {
+			self.project()
+				.pipe
+				.next_task(cx)
+				.map(|task| task.map(|task| FlattenTask { task }))
+		}
+	}
+
+	impl<P: ParallelPipe<Input>, Input> ParallelPipe<Input> for Flatten<P>
+	where
+		P::Output: IntoIterator,
+	{
+		type Output = <P::Output as IntoIterator>::Item;
+		type Task = FlattenTask<P::Task>;
+
+		fn task(&self) -> Self::Task {
+			let task = self.pipe.task();
+			FlattenTask { task }
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FlattenTask<C> {
+	task: C,
+}
+
+impl<C: StreamTask> StreamTask for FlattenTask<C>
+where
+	C::Item: IntoIterator,
+{
+	type Item = <C::Item as IntoIterator>::Item;
+	type Async = crate::pipe::FlattenSync<C::Async, <C::Item as IntoIterator>::IntoIter>;
+
+	fn into_async(self) -> Self::Async {
+		crate::pipe::FlattenSync::new(self.task.into_async())
+	}
+}
+impl<C: PipeTask<Input>, Input> PipeTask<Input> for FlattenTask<C>
+where
+	C::Output: IntoIterator,
+{
+	type Output = <C::Output as IntoIterator>::Item;
+	type Async = crate::pipe::FlattenSync<C::Async, <C::Output as IntoIterator>::IntoIter>;
+
+	fn into_async(self) -> Self::Async {
+		crate::pipe::FlattenSync::new(self.task.into_async())
+	}
+}
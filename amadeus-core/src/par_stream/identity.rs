@@ -2,11 +2,11 @@ use futures::Stream;
 use serde::{Deserialize, Serialize};
 use serde_closure::traits;
 use std::{
-	iter, pin::Pin, task::{Context, Poll}
+	iter, pin::Pin, task::{Context, Poll}, time::Duration
 };
 
 use super::{
-	All, Any, Collect, Combine, Count, Filter, FlatMap, Fold, ForEach, Fork, GroupBy, Histogram, Inspect, Map, Max, MaxBy, MaxByKey, Mean, Min, MinBy, MinByKey, MostDistinct, MostFrequent, ParallelPipe, Pipe, PipeTask, SampleUnstable, StdDev, Sum, Update
+	All, Any, BloomFilter, Chunks, ChunksTimeout, Collect, CollectOrdered, Combine, Count, Covariance, Filter, FilterAsync, FlatMap, Flatten, Fold, ForEach, Fork, GroupBy, Histogram, HistogramAuto, HistogramBuckets, HistogramLog, Inspect, Limit, LinearRegression, Map, MapAsync, Max, MaxBy, MaxByKey, Mean, Min, MinBy, MinByKey, Moments, MostDistinct, MostFrequent, ParallelPipe, PearsonCorrelation, Pipe, PipeTask, Quantiles, Sample, SampleFraction, SampleStratified, SampleUnstable, SampleWeighted, StdDev, Sum, TopKBy, Update
 };
 
 // TODO: add type parameter to Identity when type the type system includes HRTB in the ParallelPipe impl https://github.com/dtolnay/ghost/
@@ -83,6 +83,45 @@ mod workaround {
 			Filter::new(self, f)
 		}
 
+		#[inline]
+		pub fn sample_fraction(self, fraction: f64, seed: u64) -> SampleFraction<Self> {
+			SampleFraction::new(
+				self,
+				::amadeus_streaming::SampleFraction::new(fraction, seed),
+			)
+		}
+
+		#[inline]
+		pub fn map_async<F>(self, concurrency: usize, f: F) -> MapAsync<Self, F>
+		where
+			F: Clone + Send + 'static,
+		{
+			MapAsync::new(self, concurrency, f)
+		}
+
+		#[inline]
+		pub fn filter_async<F>(self, concurrency: usize, f: F) -> FilterAsync<Self, F>
+		where
+			F: Clone + Send + 'static,
+		{
+			FilterAsync::new(self, concurrency, f)
+		}
+
+		#[inline]
+		pub fn chunks(self, n: usize) -> Chunks<Self> {
+			Chunks::new(self, n)
+		}
+
+		#[inline]
+		pub fn chunks_timeout(self, n: usize, timeout: Duration) -> ChunksTimeout<Self> {
+			ChunksTimeout::new(self, n, timeout)
+		}
+
+		#[inline]
+		pub fn flatten(self) -> Flatten<Self> {
+			Flatten::new(self)
+		}
+
 		// #[must_use]
 		// #[inline]
 		// pub fn chain<C>(self, chain: C) -> Chain<Self, C::Iter>
@@ -120,6 +159,21 @@ mod workaround {
 			Histogram::new(self)
 		}
 
+		#[inline]
+		pub fn histogram_buckets(self, edges: &[f64]) -> HistogramBuckets<Self> {
+			HistogramBuckets::new(self, edges.to_vec())
+		}
+
+		#[inline]
+		pub fn histogram_auto(self, buckets: usize) -> HistogramAuto<Self> {
+			HistogramAuto::new(self, buckets)
+		}
+
+		#[inline]
+		pub fn histogram_log(self, min: f64, base: f64) -> HistogramLog<Self> {
+			HistogramLog::new(self, min, base)
+		}
+
 		#[inline]
 		pub fn count(self) -> Count<Self> {
 			Count::new(self)
@@ -143,6 +197,31 @@ mod workaround {
 			StdDev::new(self)
 		}
 
+		#[inline]
+		pub fn moments(self) -> Moments<Self> {
+			Moments::new(self)
+		}
+
+		#[inline]
+		pub fn covariance(self) -> Covariance<Self> {
+			Covariance::new(self)
+		}
+
+		#[inline]
+		pub fn pearson_correlation(self) -> PearsonCorrelation<Self> {
+			PearsonCorrelation::new(self)
+		}
+
+		#[inline]
+		pub fn linear_regression(self) -> LinearRegression<Self> {
+			LinearRegression::new(self)
+		}
+
+		#[inline]
+		pub fn quantiles(self, quantiles: &[f64]) -> Quantiles<Self> {
+			Quantiles::new(self, quantiles.to_vec())
+		}
+
 		#[inline]
 		pub fn combine<F>(self, f: F) -> Combine<Self, F>
 		where
@@ -200,6 +279,17 @@ mod workaround {
 			MostFrequent::new(self, n, probability, tolerance)
 		}
 
+		#[inline]
+		pub fn top_k_by<F, G>(
+			self, key: F, weight: G, k: usize, probability: f64, tolerance: f64,
+		) -> TopKBy<Self, F, G>
+		where
+			F: Clone + Send + 'static,
+			G: Clone + Send + 'static,
+		{
+			TopKBy::new(self, key, weight, k, probability, tolerance)
+		}
+
 		#[inline]
 		pub fn most_distinct(
 			self, n: usize, probability: f64, tolerance: f64, error_rate: f64,
@@ -207,11 +297,34 @@ mod workaround {
 			MostDistinct::new(self, n, probability, tolerance, error_rate)
 		}
 
+		#[inline]
+		pub fn bloom_filter(self, capacity: usize, false_positive_rate: f64) -> BloomFilter<Self> {
+			BloomFilter::new(self, capacity, false_positive_rate)
+		}
+
 		#[inline]
 		pub fn sample_unstable(self, samples: usize) -> SampleUnstable<Self> {
 			SampleUnstable::new(self, samples)
 		}
 
+		#[inline]
+		pub fn sample(self, samples: usize, seed: u64) -> Sample<Self> {
+			Sample::new(self, samples, seed)
+		}
+
+		#[inline]
+		pub fn sample_weighted<F>(self, samples: usize, seed: u64, f: F) -> SampleWeighted<Self, F>
+		where
+			F: Clone + Send + 'static,
+		{
+			SampleWeighted::new(self, samples, seed, f)
+		}
+
+		#[inline]
+		pub fn sample_stratified(self, samples: usize, seed: u64) -> SampleStratified<Self> {
+			SampleStratified::new(self, samples, seed)
+		}
+
 		#[inline]
 		pub fn all<F>(self, f: F) -> All<Self, F>
 		where
@@ -232,6 +345,21 @@ mod workaround {
 		pub fn collect<B>(self) -> Collect<Self, B> {
 			Collect::new(self)
 		}
+
+		#[inline]
+		pub fn collect_ordered<B>(self) -> CollectOrdered<Self, B> {
+			CollectOrdered::new(self)
+		}
+
+		#[inline]
+		pub fn take(self, n: usize) -> Limit<Self> {
+			Limit::new(self, 0, n)
+		}
+
+		#[inline]
+		pub fn limit(self, offset: usize, n: usize) -> Limit<Self> {
+			Limit::new(self, offset, n)
+		}
 	}
 }
 
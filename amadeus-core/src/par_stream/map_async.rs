@@ -0,0 +1,175 @@
+use derive_new::new;
+use futures::Future;
+use pin_project::pin_project;
+use serde::{Deserialize, Serialize};
+use serde_closure::traits::FnMut;
+use std::{
+	pin::Pin, task::{Context, Poll}
+};
+
+use super::{ParallelPipe, ParallelStream, PipeTask, StreamTask};
+
+/// Runs `f` on each item, with up to `concurrency` of the resulting futures
+/// running at once within each task. Results are yielded in order.
+#[pin_project]
+#[derive(new)]
+#[must_use]
+pub struct MapAsync<P, F> {
+	#[pin]
+	pipe: P,
+	concurrency: usize,
+	f: F,
+}
+
+impl_par_dist! {
+	impl<P: ParallelStream, F, Fut: Future> ParallelStream for MapAsync<P, F>
+	where
+		F: FnMut<(P::Item,), Output = Fut> + Clone + Send + 'static,
+	{
+		type Item = Fut::Output;
+		type Task = MapAsyncTask<P::Task, F>;
+
+		fn size_hint(&self) -> (usize, Option<usize>) {
+			self.pipe.size_hint()
+		}
+		fn next_task(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Task>> {
+			let self_ = self.project();
+			let (concurrency, f) = (*self_.concurrency, self_.f);
+			self_.pipe.next_task(cx).map(|task| {
+				task.map(|task| {
+					let f = f.clone();
+					MapAsyncTask { task, concurrency, f }
+				})
+			})
+		}
+	}
+
+	impl<P: ParallelPipe<Input>, F, Fut: Future, Input> ParallelPipe<Input> for MapAsync<P, F>
+	where
+		F: FnMut<(P::Output,), Output = Fut> + Clone + Send + 'static,
+	{
+		type Output = Fut::Output;
+		type Task = MapAsyncTask<P::Task, F>;
+
+		fn task(&self) -> Self::Task {
+			let task = self.pipe.task();
+			let concurrency = self.concurrency;
+			let f = self.f.clone();
+			MapAsyncTask { task, concurrency, f }
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MapAsyncTask<C, F> {
+	task: C,
+	concurrency: usize,
+	f: F,
+}
+
+impl<C: StreamTask, F, Fut: Future> StreamTask for MapAsyncTask<C, F>
+where
+	F: FnMut<(C::Item,), Output = Fut> + Clone,
+{
+	type Item = Fut::Output;
+	type Async = crate::pipe::MapAsync<C::Async, F, Fut>;
+
+	fn into_async(self) -> Self::Async {
+		crate::pipe::MapAsync::new(self.task.into_async(), self.concurrency, self.f)
+	}
+}
+impl<C: PipeTask<Input>, F, Fut: Future, Input> PipeTask<Input> for MapAsyncTask<C, F>
+where
+	F: FnMut<(C::Output,), Output = Fut> + Clone,
+{
+	type Output = Fut::Output;
+	type Async = crate::pipe::MapAsync<C::Async, F, Fut>;
+
+	fn into_async(self) -> Self::Async {
+		crate::pipe::MapAsync::new(self.task.into_async(), self.concurrency, self.f)
+	}
+}
+
+/// Filters items by the result of the future returned by `f`, with up to
+/// `concurrency` of those futures running at once within each task. The
+/// order of the remaining items is preserved.
+#[pin_project]
+#[derive(new)]
+#[must_use]
+pub struct FilterAsync<P, F> {
+	#[pin]
+	pipe: P,
+	concurrency: usize,
+	f: F,
+}
+
+impl_par_dist! {
+	impl<P: ParallelStream, F, Fut: Future<Output = bool>> ParallelStream for FilterAsync<P, F>
+	where
+		F: for<'a> FnMut<(&'a P::Item,), Output = Fut> + Clone + Send + 'static,
+	{
+		type Item = P::Item;
+		type Task = FilterAsyncTask<P::Task, F>;
+
+		fn size_hint(&self) -> (usize, Option<usize>) {
+			(0, self.pipe.size_hint().1)
+		}
+		fn next_task(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Task>> {
+			let self_ = self.project();
+			let (concurrency, f) = (*self_.concurrency, self_.f);
+			self_.pipe.next_task(cx).map(|task| {
+				task.map(|task| {
+					let f = f.clone();
+					FilterAsyncTask { task, concurrency, f }
+				})
+			})
+		}
+	}
+
+	impl<P: ParallelPipe<Input>, F, Fut: Future<Output = bool>, Input> ParallelPipe<Input>
+		for FilterAsync<P, F>
+	where
+		F: for<'a> FnMut<(&'a P::Output,), Output = Fut> + Clone + Send + 'static,
+	{
+		type Output = P::Output;
+		type Task = FilterAsyncTask<P::Task, F>;
+
+		fn task(&self) -> Self::Task {
+			let task = self.pipe.task();
+			let concurrency = self.concurrency;
+			let f = self.f.clone();
+			FilterAsyncTask { task, concurrency, f }
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FilterAsyncTask<C, F> {
+	task: C,
+	concurrency: usize,
+	f: F,
+}
+
+impl<C: StreamTask, F, Fut: Future<Output = bool>> StreamTask for FilterAsyncTask<C, F>
+where
+	F: for<'a> FnMut<(&'a C::Item,), Output = Fut> + Clone,
+{
+	type Item = C::Item;
+	type Async = crate::pipe::FilterAsync<C::Async, F, Fut, C::Item>;
+
+	fn into_async(self) -> Self::Async {
+		crate::pipe::FilterAsync::new(self.task.into_async(), self.concurrency, self.f)
+	}
+}
+impl<C: PipeTask<Input>, F, Fut: Future<Output = bool>, Input> PipeTask<Input>
+	for FilterAsyncTask<C, F>
+where
+	F: for<'a> FnMut<(&'a C::Output,), Output = Fut> + Clone,
+{
+	type Output = C::Output;
+	type Async = crate::pipe::FilterAsync<C::Async, F, Fut, C::Output>;
+
+	fn into_async(self) -> Self::Async {
+		crate::pipe::FilterAsync::new(self.task.into_async(), self.concurrency, self.f)
+	}
+}
@@ -0,0 +1,133 @@
+use amadeus_streaming::SampleFraction as SASampleFraction;
+use derive_new::new;
+use futures::{ready, Stream};
+use pin_project::pin_project;
+use serde::{Deserialize, Serialize};
+use std::{
+	hash::Hash, pin::Pin, task::{Context, Poll}
+};
+
+use super::{ParallelPipe, ParallelStream, PipeTask, StreamTask};
+use crate::pipe::Pipe;
+
+#[pin_project]
+#[derive(new)]
+#[must_use]
+pub struct SampleFraction<P> {
+	#[pin]
+	pipe: P,
+	sample: SASampleFraction,
+}
+
+impl_par_dist! {
+	impl<P: ParallelStream> ParallelStream for SampleFraction<P>
+	where
+		P::Item: Hash,
+	{
+		type Item = P::Item;
+		type Task = SampleFractionTask<P::Task>;
+
+		fn size_hint(&self) -> (usize, Option<usize>) {
+			(0, self.pipe.size_hint().1)
+		}
+		fn next_task(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Task>> {
+			let self_ = self.project();
+			let sample = *self_.sample;
+			self_
+				.pipe
+				.next_task(cx)
+				.map(|task| task.map(|task| SampleFractionTask { task, sample }))
+		}
+	}
+
+	impl<P: ParallelPipe<Input>, Input> ParallelPipe<Input> for SampleFraction<P>
+	where
+		P::Output: Hash,
+	{
+		type Output = P::Output;
+		type Task = SampleFractionTask<P::Task>;
+
+		fn task(&self) -> Self::Task {
+			let task = self.pipe.task();
+			let sample = self.sample;
+			SampleFractionTask { task, sample }
+		}
+	}
+}
+
+#[pin_project]
+#[derive(Serialize, Deserialize)]
+pub struct SampleFractionTask<T> {
+	#[pin]
+	task: T,
+	sample: SASampleFraction,
+}
+
+impl<C: StreamTask> StreamTask for SampleFractionTask<C>
+where
+	C::Item: Hash,
+{
+	type Item = C::Item;
+	type Async = SampleFractionTask<C::Async>;
+
+	fn into_async(self) -> Self::Async {
+		SampleFractionTask {
+			task: self.task.into_async(),
+			sample: self.sample,
+		}
+	}
+}
+impl<C: PipeTask<Input>, Input> PipeTask<Input> for SampleFractionTask<C>
+where
+	C::Output: Hash,
+{
+	type Output = C::Output;
+	type Async = SampleFractionTask<C::Async>;
+
+	fn into_async(self) -> Self::Async {
+		SampleFractionTask {
+			task: self.task.into_async(),
+			sample: self.sample,
+		}
+	}
+}
+
+impl<C: Stream> Stream for SampleFractionTask<C>
+where
+	C::Item: Hash,
+{
+	type Item = C::Item;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+		let mut self_ = self.project();
+		let sample = *self_.sample;
+		Poll::Ready(loop {
+			match ready!(self_.task.as_mut().poll_next(cx)) {
+				Some(t) if sample.sample(&t) => break Some(t),
+				Some(_) => (),
+				None => break None,
+			}
+		})
+	}
+}
+
+impl<C: Pipe<Input>, Input> Pipe<Input> for SampleFractionTask<C>
+where
+	C::Output: Hash,
+{
+	type Output = C::Output;
+
+	fn poll_next(
+		self: Pin<&mut Self>, cx: &mut Context, mut stream: Pin<&mut impl Stream<Item = Input>>,
+	) -> Poll<Option<Self::Output>> {
+		let mut self_ = self.project();
+		let sample = *self_.sample;
+		Poll::Ready(loop {
+			match ready!(self_.task.as_mut().poll_next(cx, stream.as_mut())) {
+				Some(t) if sample.sample(&t) => break Some(t),
+				Some(_) => (),
+				None => break None,
+			}
+		})
+	}
+}
@@ -0,0 +1,177 @@
+use futures::{stream, StreamExt};
+use pin_project::pin_project;
+use serde::{Deserialize, Serialize};
+use serde_closure::FnMutNamed;
+use std::{
+	pin::Pin, task::{Context, Poll}
+};
+
+use super::{ParallelStream, Position, StreamTask};
+
+/// Counts the items of each partition among `positions`, which are the first
+/// items of a stream in order, and so cover whole partitions but for the last.
+fn partition_counts(positions: &[Position]) -> Vec<usize> {
+	let mut counts = Vec::new();
+	for position in positions {
+		if counts.len() <= position.partition {
+			counts.resize(position.partition + 1, 0);
+		}
+		counts[position.partition] += 1;
+	}
+	counts
+}
+
+/// Skips the first `n` items, in the order they appear in the source.
+///
+/// Partitions that lie wholly within the skipped items aren't run at all.
+#[pin_project]
+#[derive(Clone)]
+#[must_use]
+pub struct Skip<P> {
+	#[pin]
+	pipe: P,
+	counts: Vec<usize>,
+	exhausted: bool,
+	partition: usize,
+}
+impl<P> Skip<P> {
+	/// `positions` are those of the first `n` items of `pipe`.
+	pub fn new(pipe: P, positions: Vec<Position>, n: usize) -> Self {
+		Self {
+			pipe,
+			counts: partition_counts(&positions),
+			exhausted: positions.len() < n,
+			partition: 0,
+		}
+	}
+}
+
+impl_par_dist! {
+	impl<P: ParallelStream> ParallelStream for Skip<P> {
+		type Item = P::Item;
+		type Task = SkipTask<P::Task>;
+
+		fn size_hint(&self) -> (usize, Option<usize>) {
+			if self.exhausted {
+				return (0, Some(0));
+			}
+			// Partitions before the last that the skipped items came from aren't run
+			let skipped = self.counts.len().saturating_sub(self.partition + 1);
+			let (lower, upper) = self.pipe.size_hint();
+			(
+				lower.saturating_sub(skipped),
+				upper.map(|upper| upper.saturating_sub(skipped)),
+			)
+		}
+		fn next_task(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Task>> {
+			let mut self_ = self.project();
+			if *self_.exhausted {
+				return Poll::Ready(None);
+			}
+			loop {
+				let task = match self_.pipe.as_mut().next_task(cx) {
+					Poll::Ready(Some(task)) => task,
+					poll => return poll.map(|_| None),
+				};
+				let partition = *self_.partition;
+				*self_.partition += 1;
+				if partition + 1 < self_.counts.len() {
+					continue;
+				}
+				let n = self_.counts.get(partition).copied().unwrap_or(0);
+				break Poll::Ready(Some(SkipTask { task, n }));
+			}
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SkipTask<T> {
+	task: T,
+	n: usize,
+}
+
+impl<C: StreamTask> StreamTask for SkipTask<C> {
+	type Item = C::Item;
+	type Async = stream::Skip<C::Async>;
+
+	fn into_async(self) -> Self::Async {
+		self.task.into_async().skip(self.n)
+	}
+}
+
+/// Takes the first `n` items, in the order they appear in the source.
+///
+/// No further partitions are run once the `n` items are accounted for, and
+/// each partition stops being read once it's yielded its share.
+#[pin_project]
+#[derive(Clone)]
+#[must_use]
+pub struct Take<P> {
+	#[pin]
+	pipe: P,
+	counts: Vec<usize>,
+	partition: usize,
+}
+impl<P> Take<P> {
+	/// `positions` are those of the first `n` items of `pipe`.
+	pub fn new(pipe: P, positions: Vec<Position>) -> Self {
+		Self {
+			pipe,
+			counts: partition_counts(&positions),
+			partition: 0,
+		}
+	}
+}
+
+impl_par_dist! {
+	impl<P: ParallelStream> ParallelStream for Take<P> {
+		type Item = P::Item;
+		type Task = TakeTask<P::Task>;
+
+		fn size_hint(&self) -> (usize, Option<usize>) {
+			let remaining = self.counts.len().saturating_sub(self.partition);
+			let (lower, upper) = self.pipe.size_hint();
+			(
+				lower.min(remaining),
+				Some(upper.map_or(remaining, |upper| upper.min(remaining))),
+			)
+		}
+		fn next_task(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Task>> {
+			let self_ = self.project();
+			let partition = *self_.partition;
+			if partition >= self_.counts.len() {
+				return Poll::Ready(None);
+			}
+			let n = self_.counts[partition];
+			let next = self_.partition;
+			self_.pipe.next_task(cx).map(|task| {
+				task.map(|task| {
+					*next += 1;
+					TakeTask { task, n }
+				})
+			})
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TakeTask<T> {
+	task: T,
+	n: usize,
+}
+
+impl<C: StreamTask> StreamTask for TakeTask<C> {
+	type Item = C::Item;
+	type Async = stream::Take<C::Async>;
+
+	fn into_async(self) -> Self::Async {
+		self.task.into_async().take(self.n)
+	}
+}
+
+FnMutNamed! {
+	pub type ItemPosition<T> = |self|item=> (Position, T)| -> (Position, Position) where ; where {
+		(item.0, item.0)
+	}
+}
@@ -0,0 +1,80 @@
+use derive_new::new;
+use futures::stream;
+use pin_project::pin_project;
+use serde::{Deserialize, Serialize};
+use std::{
+	pin::Pin, task::{Context, Poll}
+};
+
+use super::{ParallelStream, StreamTask};
+
+/// Pairs up the tasks of two streams, and then the items within each pair of
+/// tasks.
+///
+/// Both streams are expected to be partitioned identically, i.e. yield the same
+/// number of tasks, with corresponding tasks yielding the same number of items.
+/// Like [`Iterator::zip`], whichever is shorter determines the length.
+#[pin_project]
+#[derive(new)]
+#[must_use]
+pub struct Zip<A, B, T> {
+	#[pin]
+	a: A,
+	#[pin]
+	b: B,
+	/// A task of `a` awaiting its counterpart from `b`. `T` is `A::Task`, a
+	/// parameter as `A::Task` differs between `ParallelStream` and
+	/// `DistributedStream`.
+	#[new(default)]
+	pending: Option<T>,
+}
+
+impl_par_dist! {
+	impl<A: ParallelStream, B: ParallelStream> ParallelStream for Zip<A, B, A::Task> {
+		type Item = (A::Item, B::Item);
+		type Task = ZipTask<A::Task, B::Task>;
+
+		fn size_hint(&self) -> (usize, Option<usize>) {
+			let (a_lower, a_upper) = self.a.size_hint();
+			let (b_lower, b_upper) = self.b.size_hint();
+			(
+				a_lower.min(b_lower),
+				match (a_upper, b_upper) {
+					(Some(a), Some(b)) => Some(a.min(b)),
+					(a, None) => a,
+					(None, b) => b,
+				},
+			)
+		}
+		fn next_task(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Task>> {
+			let self_ = self.project();
+			let pending = self_.pending;
+			if pending.is_none() {
+				match self_.a.next_task(cx) {
+					Poll::Ready(Some(a)) => *pending = Some(a),
+					Poll::Ready(None) => return Poll::Ready(None),
+					Poll::Pending => return Poll::Pending,
+				}
+			}
+			self_.b.next_task(cx).map(|b| {
+				let a = pending.take().unwrap();
+				b.map(|b| ZipTask { a, b })
+			})
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ZipTask<A, B> {
+	a: A,
+	b: B,
+}
+
+impl<A: StreamTask, B: StreamTask> StreamTask for ZipTask<A, B> {
+	type Item = (A::Item, B::Item);
+	type Async = stream::Zip<A::Async, B::Async>;
+
+	fn into_async(self) -> Self::Async {
+		stream::StreamExt::zip(self.a.into_async(), self.b.into_async())
+	}
+}
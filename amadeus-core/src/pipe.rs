@@ -1,9 +1,11 @@
+mod chunks;
 mod filter;
 mod filter_map_sync;
 mod flat_map;
 mod flat_map_sync;
 mod flatten;
 mod map;
+mod map_async;
 
 use derive_new::new;
 use futures::{pin_mut, stream, Future, Stream};
@@ -12,7 +14,9 @@ use std::{
 	marker::PhantomData, mem, ops::DerefMut, pin::Pin, task::{Context, Poll}
 };
 
-pub use self::{filter::*, filter_map_sync::*, flat_map::*, flat_map_sync::*, flatten::*, map::*};
+pub use self::{
+	chunks::*, filter::*, filter_map_sync::*, flat_map::*, flat_map_sync::*, flatten::*, map::*, map_async::*
+};
 
 // Sink takes Input as an input parameter rather than associated type to accept
 // for<'a> &'a T, but this might not be necessary in future?
@@ -0,0 +1,159 @@
+use derive_new::new;
+use futures::{ready, Future, Stream};
+use pin_project::pin_project;
+use std::{
+	mem, pin::Pin, task::{Context, Poll}, time::Duration
+};
+use tokio::time::{delay_for, Delay};
+
+use super::Pipe;
+
+#[pin_project]
+#[derive(new)]
+pub struct Chunks<P, T> {
+	#[pin]
+	pipe: P,
+	n: usize,
+	#[new(default)]
+	buf: Vec<T>,
+}
+
+impl<P: Stream> Stream for Chunks<P, P::Item> {
+	type Item = Vec<P::Item>;
+
+	#[inline]
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+		let mut self_ = self.project();
+		Poll::Ready(loop {
+			if let Some(item) = ready!(self_.pipe.as_mut().poll_next(cx)) {
+				self_.buf.push(item);
+				if self_.buf.len() >= *self_.n {
+					break Some(mem::take(self_.buf));
+				}
+			} else if !self_.buf.is_empty() {
+				break Some(mem::take(self_.buf));
+			} else {
+				break None;
+			}
+		})
+	}
+}
+
+impl<P: Pipe<Input>, Input> Pipe<Input> for Chunks<P, P::Output> {
+	type Output = Vec<P::Output>;
+
+	#[inline]
+	fn poll_next(
+		self: Pin<&mut Self>, cx: &mut Context, mut stream: Pin<&mut impl Stream<Item = Input>>,
+	) -> Poll<Option<Self::Output>> {
+		let mut self_ = self.project();
+		Poll::Ready(loop {
+			if let Some(item) = ready!(self_.pipe.as_mut().poll_next(cx, stream.as_mut())) {
+				self_.buf.push(item);
+				if self_.buf.len() >= *self_.n {
+					break Some(mem::take(self_.buf));
+				}
+			} else if !self_.buf.is_empty() {
+				break Some(mem::take(self_.buf));
+			} else {
+				break None;
+			}
+		})
+	}
+}
+
+#[pin_project]
+#[derive(new)]
+pub struct ChunksTimeout<P, T> {
+	#[pin]
+	pipe: P,
+	n: usize,
+	timeout: Duration,
+	#[new(default)]
+	buf: Vec<T>,
+	#[pin]
+	#[new(default)]
+	delay: Option<Delay>,
+}
+
+impl<P: Stream> Stream for ChunksTimeout<P, P::Item> {
+	type Item = Vec<P::Item>;
+
+	#[inline]
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+		let mut self_ = self.project();
+		Poll::Ready(loop {
+			match self_.pipe.as_mut().poll_next(cx) {
+				Poll::Ready(Some(item)) => {
+					if self_.buf.is_empty() {
+						self_.delay.set(Some(delay_for(*self_.timeout)));
+					}
+					self_.buf.push(item);
+					if self_.buf.len() >= *self_.n {
+						self_.delay.set(None);
+						break Some(mem::take(self_.buf));
+					}
+				}
+				Poll::Ready(None) => {
+					self_.delay.set(None);
+					break if !self_.buf.is_empty() {
+						Some(mem::take(self_.buf))
+					} else {
+						None
+					};
+				}
+				Poll::Pending => {
+					if let Some(delay) = self_.delay.as_mut().as_pin_mut() {
+						ready!(delay.poll(cx));
+					} else {
+						return Poll::Pending;
+					}
+					self_.delay.set(None);
+					break Some(mem::take(self_.buf));
+				}
+			}
+		})
+	}
+}
+
+impl<P: Pipe<Input>, Input> Pipe<Input> for ChunksTimeout<P, P::Output> {
+	type Output = Vec<P::Output>;
+
+	#[inline]
+	fn poll_next(
+		self: Pin<&mut Self>, cx: &mut Context, mut stream: Pin<&mut impl Stream<Item = Input>>,
+	) -> Poll<Option<Self::Output>> {
+		let mut self_ = self.project();
+		Poll::Ready(loop {
+			match self_.pipe.as_mut().poll_next(cx, stream.as_mut()) {
+				Poll::Ready(Some(item)) => {
+					if self_.buf.is_empty() {
+						self_.delay.set(Some(delay_for(*self_.timeout)));
+					}
+					self_.buf.push(item);
+					if self_.buf.len() >= *self_.n {
+						self_.delay.set(None);
+						break Some(mem::take(self_.buf));
+					}
+				}
+				Poll::Ready(None) => {
+					self_.delay.set(None);
+					break if !self_.buf.is_empty() {
+						Some(mem::take(self_.buf))
+					} else {
+						None
+					};
+				}
+				Poll::Pending => {
+					if let Some(delay) = self_.delay.as_mut().as_pin_mut() {
+						ready!(delay.poll(cx));
+					} else {
+						return Poll::Pending;
+					}
+					self_.delay.set(None);
+					break Some(mem::take(self_.buf));
+				}
+			}
+		})
+	}
+}
@@ -68,3 +68,64 @@ where
 		})
 	}
 }
+
+#[pin_project]
+#[derive(new)]
+pub struct FlattenSync<P, U> {
+	#[pin]
+	pipe: P,
+	#[new(default)]
+	next: Option<U>,
+}
+
+impl<P: Stream> Stream for FlattenSync<P, <P::Item as IntoIterator>::IntoIter>
+where
+	P::Item: IntoIterator,
+{
+	type Item = <P::Item as IntoIterator>::Item;
+
+	#[inline]
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+		let mut self_ = self.project();
+		Poll::Ready(loop {
+			if let Some(s) = self_.next.as_mut() {
+				if let Some(item) = s.next() {
+					break Some(item);
+				} else {
+					*self_.next = None;
+				}
+			} else if let Some(s) = ready!(self_.pipe.as_mut().poll_next(cx)) {
+				*self_.next = Some(s.into_iter());
+			} else {
+				break None;
+			}
+		})
+	}
+}
+
+impl<P: Pipe<Input>, Input> Pipe<Input> for FlattenSync<P, <P::Output as IntoIterator>::IntoIter>
+where
+	P::Output: IntoIterator,
+{
+	type Output = <P::Output as IntoIterator>::Item;
+
+	#[inline]
+	fn poll_next(
+		self: Pin<&mut Self>, cx: &mut Context, mut stream: Pin<&mut impl Stream<Item = Input>>,
+	) -> Poll<Option<Self::Output>> {
+		let mut self_ = self.project();
+		Poll::Ready(loop {
+			if let Some(s) = self_.next.as_mut() {
+				if let Some(item) = s.next() {
+					break Some(item);
+				} else {
+					*self_.next = None;
+				}
+			} else if let Some(s) = ready!(self_.pipe.as_mut().poll_next(cx, stream.as_mut())) {
+				*self_.next = Some(s.into_iter());
+			} else {
+				break None;
+			}
+		})
+	}
+}
@@ -0,0 +1,201 @@
+use futures::{stream::FuturesOrdered, Future, Stream, StreamExt as _};
+use pin_project::pin_project;
+use serde_closure::traits::FnMut;
+use std::{
+	pin::Pin, task::{Context, Poll}
+};
+
+use super::Pipe;
+
+#[pin_project]
+pub struct MapAsync<P, F, Fut: Future> {
+	#[pin]
+	pipe: P,
+	f: F,
+	concurrency: usize,
+	pending: FuturesOrdered<Fut>,
+	done: bool,
+}
+impl<P, F, Fut: Future> MapAsync<P, F, Fut> {
+	pub fn new(pipe: P, concurrency: usize, f: F) -> Self {
+		assert_ne!(concurrency, 0, "concurrency must be greater than 0");
+		Self {
+			pipe,
+			f,
+			concurrency,
+			pending: FuturesOrdered::new(),
+			done: false,
+		}
+	}
+}
+
+impl<P: Stream, F, Fut> Stream for MapAsync<P, F, Fut>
+where
+	F: FnMut<(P::Item,), Output = Fut>,
+	Fut: Future,
+{
+	type Item = Fut::Output;
+
+	#[inline]
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+		let mut self_ = self.project();
+		while !*self_.done && self_.pending.len() < *self_.concurrency {
+			match self_.pipe.as_mut().poll_next(cx) {
+				Poll::Ready(Some(item)) => self_.pending.push_back(self_.f.call_mut((item,))),
+				Poll::Ready(None) => *self_.done = true,
+				Poll::Pending => break,
+			}
+		}
+		match self_.pending.poll_next_unpin(cx) {
+			Poll::Ready(None) if !*self_.done => Poll::Pending,
+			poll => poll,
+		}
+	}
+}
+
+impl<P: Pipe<Input>, F, Fut, Input> Pipe<Input> for MapAsync<P, F, Fut>
+where
+	F: FnMut<(P::Output,), Output = Fut>,
+	Fut: Future,
+{
+	type Output = Fut::Output;
+
+	#[inline]
+	fn poll_next(
+		self: Pin<&mut Self>, cx: &mut Context, mut stream: Pin<&mut impl Stream<Item = Input>>,
+	) -> Poll<Option<Self::Output>> {
+		let mut self_ = self.project();
+		while !*self_.done && self_.pending.len() < *self_.concurrency {
+			match self_.pipe.as_mut().poll_next(cx, stream.as_mut()) {
+				Poll::Ready(Some(item)) => self_.pending.push_back(self_.f.call_mut((item,))),
+				Poll::Ready(None) => *self_.done = true,
+				Poll::Pending => break,
+			}
+		}
+		match self_.pending.poll_next_unpin(cx) {
+			Poll::Ready(None) if !*self_.done => Poll::Pending,
+			poll => poll,
+		}
+	}
+}
+
+#[pin_project]
+pub struct FilterAsync<P, F, Fut: Future<Output = bool>, T> {
+	#[pin]
+	pipe: P,
+	f: F,
+	concurrency: usize,
+	pending: FuturesOrdered<FilterFuture<Fut, T>>,
+	done: bool,
+}
+impl<P, F, Fut: Future<Output = bool>, T> FilterAsync<P, F, Fut, T> {
+	pub fn new(pipe: P, concurrency: usize, f: F) -> Self {
+		assert_ne!(concurrency, 0, "concurrency must be greater than 0");
+		Self {
+			pipe,
+			f,
+			concurrency,
+			pending: FuturesOrdered::new(),
+			done: false,
+		}
+	}
+}
+
+impl<P: Stream, F, Fut> Stream for FilterAsync<P, F, Fut, P::Item>
+where
+	F: for<'a> FnMut<(&'a P::Item,), Output = Fut>,
+	Fut: Future<Output = bool>,
+{
+	type Item = P::Item;
+
+	#[inline]
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+		let mut self_ = self.project();
+		loop {
+			while !*self_.done && self_.pending.len() < *self_.concurrency {
+				match self_.pipe.as_mut().poll_next(cx) {
+					Poll::Ready(Some(item)) => {
+						let fut = self_.f.call_mut((&item,));
+						self_.pending.push_back(FilterFuture::new(fut, item))
+					}
+					Poll::Ready(None) => *self_.done = true,
+					Poll::Pending => break,
+				}
+			}
+			match self_.pending.poll_next_unpin(cx) {
+				Poll::Ready(Some(Some(item))) => break Poll::Ready(Some(item)),
+				Poll::Ready(Some(None)) => (),
+				Poll::Ready(None) if *self_.done => break Poll::Ready(None),
+				_ => break Poll::Pending,
+			}
+		}
+	}
+}
+
+impl<P: Pipe<Input>, F, Fut, Input> Pipe<Input> for FilterAsync<P, F, Fut, P::Output>
+where
+	F: for<'a> FnMut<(&'a P::Output,), Output = Fut>,
+	Fut: Future<Output = bool>,
+{
+	type Output = P::Output;
+
+	#[inline]
+	fn poll_next(
+		self: Pin<&mut Self>, cx: &mut Context, mut stream: Pin<&mut impl Stream<Item = Input>>,
+	) -> Poll<Option<Self::Output>> {
+		let mut self_ = self.project();
+		loop {
+			while !*self_.done && self_.pending.len() < *self_.concurrency {
+				match self_.pipe.as_mut().poll_next(cx, stream.as_mut()) {
+					Poll::Ready(Some(item)) => {
+						let fut = self_.f.call_mut((&item,));
+						self_.pending.push_back(FilterFuture::new(fut, item))
+					}
+					Poll::Ready(None) => *self_.done = true,
+					Poll::Pending => break,
+				}
+			}
+			match self_.pending.poll_next_unpin(cx) {
+				Poll::Ready(Some(Some(item))) => break Poll::Ready(Some(item)),
+				Poll::Ready(Some(None)) => (),
+				Poll::Ready(None) if *self_.done => break Poll::Ready(None),
+				_ => break Poll::Pending,
+			}
+		}
+	}
+}
+
+/// Resolves to the item if the predicate future resolves to `true`.
+#[pin_project]
+pub struct FilterFuture<Fut, T> {
+	#[pin]
+	fut: Fut,
+	item: Option<T>,
+}
+impl<Fut, T> FilterFuture<Fut, T> {
+	fn new(fut: Fut, item: T) -> Self {
+		Self {
+			fut,
+			item: Some(item),
+		}
+	}
+}
+impl<Fut, T> Future for FilterFuture<Fut, T>
+where
+	Fut: Future<Output = bool>,
+{
+	type Output = Option<T>;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+		let mut self_ = self.project();
+		self_.fut.as_mut().poll(cx).map(|keep| {
+			let item = self_.item.take().unwrap();
+			if keep {
+				Some(item)
+			} else {
+				None
+			}
+		})
+	}
+}
@@ -1,8 +1,11 @@
-use futures::future::BoxFuture;
+use futures::future::{self, BoxFuture, FutureExt};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use serde_closure::traits;
 use std::{
-	error::Error, future::Future, panic::{RefUnwindSafe, UnwindSafe}
+	error::Error, future::Future, panic::{RefUnwindSafe, UnwindSafe}, sync::{
+		atomic::{AtomicBool, Ordering}, Arc, Mutex
+	}
 };
 
 pub trait ProcessSend: Send + Serialize + for<'de> Deserialize<'de> {}
@@ -37,6 +40,21 @@ pub trait ProcessPool: Clone + Send + Sync + RefUnwindSafe + UnwindSafe + Unpin
 		F: traits::FnOnce(&Self::ThreadPool) -> Fut + ProcessSend + 'a,
 		Fut: Future<Output = T> + 'a,
 		T: ProcessSend + 'a;
+
+	/// Runs `work` once in every process, rather than in whichever is next.
+	///
+	/// The default spawns `work` [`processes`](Self::processes) times with
+	/// [`spawn`](Self::spawn), which only reaches every process if the pool
+	/// hands work to each in turn. Pools that can address their processes
+	/// should override it.
+	fn spawn_all<F, Fut, T>(&self, work: F) -> BoxFuture<'static, Result<Vec<T>>>
+	where
+		F: traits::FnOnce(&Self::ThreadPool) -> Fut + ProcessSend + Clone + 'static,
+		Fut: Future<Output = T> + 'static,
+		T: ProcessSend + 'static,
+	{
+		future::try_join_all((0..self.processes()).map(|_| self.spawn(work.clone()))).boxed()
+	}
 }
 
 pub trait ThreadPool: Clone + Send + Sync + RefUnwindSafe + UnwindSafe + Unpin {
@@ -92,6 +110,14 @@ where
 	{
 		(*self).spawn_unchecked(work)
 	}
+	fn spawn_all<F, Fut, T>(&self, work: F) -> BoxFuture<'static, Result<Vec<T>>>
+	where
+		F: traits::FnOnce(&Self::ThreadPool) -> Fut + ProcessSend + Clone + 'static,
+		Fut: Future<Output = T> + 'static,
+		T: ProcessSend + 'static,
+	{
+		(*self).spawn_all(work)
+	}
 }
 
 impl<P: ?Sized> ThreadPool for &P
@@ -119,3 +145,48 @@ where
 		(*self).spawn_unchecked(work)
 	}
 }
+
+/// Flags of the reductions running in this process, keyed by an id chosen by
+/// the process that spawned them, so that a reduction spread over several
+/// processes can be cancelled in all of them once its result is known.
+static CANCELLATIONS: Lazy<Mutex<Vec<(u64, Arc<AtomicBool>)>>> =
+	Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Registers a cancellation flag for the reduction `id` in this process, until
+/// the returned guard is dropped.
+pub(crate) fn register_cancellation(id: u64) -> Cancellation {
+	let flag = Arc::new(AtomicBool::new(false));
+	CANCELLATIONS.lock().unwrap().push((id, flag.clone()));
+	Cancellation { id, flag }
+}
+
+/// Sets the cancellation flag of the reduction `id`, if it's running in this
+/// process.
+pub(crate) fn cancel(id: u64) {
+	for (_, flag) in CANCELLATIONS
+		.lock()
+		.unwrap()
+		.iter()
+		.filter(|(id_, _)| *id_ == id)
+	{
+		flag.store(true, Ordering::Relaxed);
+	}
+}
+
+pub(crate) struct Cancellation {
+	id: u64,
+	flag: Arc<AtomicBool>,
+}
+impl Cancellation {
+	pub(crate) fn flag(&self) -> Arc<AtomicBool> {
+		self.flag.clone()
+	}
+}
+impl Drop for Cancellation {
+	fn drop(&mut self) {
+		CANCELLATIONS
+			.lock()
+			.unwrap()
+			.retain(|(id, flag)| *id != self.id || !Arc::ptr_eq(flag, &self.flag));
+	}
+}
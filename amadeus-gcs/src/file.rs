@@ -0,0 +1,499 @@
+use async_trait::async_trait;
+use futures::{future, future::LocalBoxFuture, lock::Mutex as AsyncMutex, FutureExt, TryStreamExt};
+use reqwest::{header, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap, convert::TryFrom, io, mem, sync::{Arc, Mutex}
+};
+use url::Url;
+
+use amadeus_core::{
+	file::{get_range, Directory, File, Page, Partition, PathBuf, RetryPolicy, SharedBuf}, util::IoError
+};
+
+use super::{error, Bucket, GcsCredentials, Object, Objects, CLIENT};
+
+/// The objects in a Google Cloud Storage bucket whose names start with a prefix.
+///
+/// Paths passed to [`partitions_filter`](Directory::partitions_filter) are
+/// the object names after the prefix, split on `/`. Directories that are
+/// filtered out aren't listed.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct GcsDirectory {
+	bucket: Bucket,
+	prefix: String,
+}
+impl GcsDirectory {
+	pub fn new(bucket: &str, prefix: &str) -> Self {
+		Self::new_with(bucket, prefix, GcsCredentials::Anonymous)
+	}
+	pub fn new_with(bucket: &str, prefix: &str, credentials: GcsCredentials) -> Self {
+		let bucket = Bucket {
+			name: bucket.to_owned(),
+			endpoint: None,
+			credentials,
+		};
+		let prefix = prefix.to_owned();
+		Self { bucket, prefix }
+	}
+	/// Connect to `endpoint` rather than `https://storage.googleapis.com`, for
+	/// example `http://localhost:4443` for fake-gcs-server.
+	pub fn endpoint(mut self, endpoint: Url) -> Self {
+		self.bucket.endpoint = Some(endpoint);
+		self
+	}
+	/// The object `name` under this directory's prefix, for example to
+	/// [create](GcsFile::create) it.
+	pub fn file(&self, name: &str) -> GcsFile {
+		GcsFile {
+			bucket: self.bucket.clone(),
+			name: format!("{}{}", self.prefix, name),
+		}
+	}
+}
+#[async_trait(?Send)]
+impl Directory for GcsDirectory {
+	async fn partitions_filter<F>(
+		self, mut f: F,
+	) -> Result<Vec<<Self as File>::Partition>, <Self as File>::Error>
+	where
+		F: FnMut(&PathBuf) -> bool,
+	{
+		let mut partitions = Vec::new();
+		let mut path = PathBuf::new();
+		walk(
+			&self.bucket,
+			&self.prefix,
+			&mut path,
+			&mut f,
+			&mut partitions,
+		)
+		.await?;
+		Ok(partitions)
+	}
+}
+#[async_trait(?Send)]
+impl File for GcsDirectory {
+	type Partition = GcsPartition;
+	type Error = IoError;
+
+	async fn partitions(self) -> Result<Vec<Self::Partition>, Self::Error> {
+		self.partitions_filter(|_| true).await
+	}
+}
+
+/// List the objects and directories directly under `prefix`, in name order.
+async fn list(bucket: &Bucket, prefix: &str) -> Result<Vec<(String, Option<u64>)>, IoError> {
+	let mut entries = Vec::new();
+	let mut page_token: Option<String> = None;
+	loop {
+		let mut url = bucket.url(None);
+		{
+			let mut query = url.query_pairs_mut();
+			let _ = query
+				.append_pair("prefix", prefix)
+				.append_pair("delimiter", "/");
+			if let Some(page_token) = &page_token {
+				let _ = query.append_pair("pageToken", page_token);
+			}
+		}
+		let res = bucket.send(|| CLIENT.get(url.clone())).await?;
+		let objects: Objects = res.json().await.map_err(error)?;
+		entries.extend(
+			objects
+				.items
+				.into_iter()
+				.map(|object| (object.name, Some(object.size))),
+		);
+		entries.extend(objects.prefixes.into_iter().map(|prefix| (prefix, None)));
+		page_token = objects.next_page_token;
+		if page_token.is_none() {
+			break;
+		}
+	}
+	entries.sort();
+	Ok(entries)
+}
+
+/// List the objects under `prefix`, at `path` relative to the directory being
+/// listed, descending into the directories that `f` accepts.
+fn walk<'a, F>(
+	bucket: &'a Bucket, prefix: &'a str, path: &'a mut PathBuf, f: &'a mut F,
+	partitions: &'a mut Vec<GcsPartition>,
+) -> LocalBoxFuture<'a, Result<(), IoError>>
+where
+	F: FnMut(&PathBuf) -> bool,
+{
+	Box::pin(async move {
+		for (name, len) in list(bucket, prefix).await? {
+			let component = &name[prefix.len()..];
+			match len {
+				None => {
+					path.push(component.trim_end_matches('/'));
+					if f(&*path) {
+						walk(bucket, &name, &mut *path, &mut *f, &mut *partitions).await?;
+					}
+					let _ = path.pop();
+				}
+				Some(len) => {
+					path.set_file_name(Some(component));
+					if f(&*path) {
+						partitions.push(GcsPartition {
+							bucket: bucket.clone(),
+							name: name.clone(),
+							len,
+						});
+					}
+					path.set_file_name::<&str>(None);
+				}
+			}
+		}
+		Ok(())
+	})
+}
+
+/// An object in Google Cloud Storage.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct GcsFile {
+	bucket: Bucket,
+	name: String,
+}
+impl GcsFile {
+	pub fn new(bucket: &str, name: &str) -> Self {
+		Self::new_with(bucket, name, GcsCredentials::Anonymous)
+	}
+	pub fn new_with(bucket: &str, name: &str, credentials: GcsCredentials) -> Self {
+		let bucket = Bucket {
+			name: bucket.to_owned(),
+			endpoint: None,
+			credentials,
+		};
+		let name = name.to_owned();
+		Self { bucket, name }
+	}
+	/// Connect to `endpoint` rather than `https://storage.googleapis.com`, for
+	/// example `http://localhost:4443` for fake-gcs-server.
+	pub fn endpoint(mut self, endpoint: Url) -> Self {
+		self.bucket.endpoint = Some(endpoint);
+		self
+	}
+	/// A [`Page`] that creates this object, or replaces it if it exists, once
+	/// [flushed](Page::flush).
+	///
+	/// Writes must together cover the object contiguously from offset 0, but
+	/// can arrive in any order and concurrently. Contiguous writes are sent
+	/// in chunks with a resumable upload as they arrive, and objects small
+	/// enough to fit in one chunk are uploaded in a single request.
+	pub fn create(self) -> GcsPage {
+		let inner = GcsPageInner::new(self.bucket, self.name, 0);
+		GcsPage {
+			inner: Arc::new(inner),
+		}
+	}
+}
+#[async_trait(?Send)]
+impl File for GcsFile {
+	type Partition = GcsPartition;
+	type Error = IoError;
+
+	async fn partitions(self) -> Result<Vec<Self::Partition>, Self::Error> {
+		let url = self.bucket.url(Some(&self.name));
+		let res = self.bucket.send(|| CLIENT.get(url.clone())).await?;
+		let object: Object = res.json().await.map_err(error)?;
+		Ok(vec![GcsPartition {
+			bucket: self.bucket,
+			name: self.name,
+			len: object.size,
+		}])
+	}
+}
+
+/// Finds the object's length and then reads it as its one [`GcsPartition`] would.
+#[async_trait(?Send)]
+impl Partition for GcsFile {
+	type Page = GcsPage;
+	type Error = IoError;
+
+	async fn pages(self) -> Result<Vec<Self::Page>, Self::Error> {
+		let partition = self.partitions().await?.pop().unwrap();
+		partition.pages().await
+	}
+	fn name(&self) -> Option<String> {
+		Some(self.name.clone())
+	}
+}
+
+/// An object in Google Cloud Storage, of known length.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct GcsPartition {
+	bucket: Bucket,
+	name: String,
+	len: u64,
+}
+#[async_trait(?Send)]
+impl Partition for GcsPartition {
+	type Page = GcsPage;
+	type Error = IoError;
+
+	async fn pages(self) -> Result<Vec<Self::Page>, Self::Error> {
+		let inner = GcsPageInner::new(self.bucket, self.name, self.len);
+		Ok(vec![GcsPage {
+			inner: Arc::new(inner),
+		}])
+	}
+	fn name(&self) -> Option<String> {
+		Some(self.name.clone())
+	}
+}
+
+/// The size of the chunks of resumable uploads. GCS requires all but the last
+/// to be a multiple of 256 KiB.
+const CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+struct GcsPageInner {
+	bucket: Bucket,
+	name: String,
+	len: u64,
+	write: Mutex<WriteState>,
+	/// Held while sending chunks, which must be sent in order.
+	upload: AsyncMutex<()>,
+}
+#[derive(Default)]
+struct WriteState {
+	/// Writes that aren't yet contiguous with `buffer`, by offset.
+	pending: BTreeMap<u64, Box<[u8]>>,
+	/// Contiguous writes that haven't been sent yet, starting at `offset`.
+	buffer: Vec<u8>,
+	/// The offset up to which writes have been sent.
+	offset: u64,
+	/// The resumable upload's session URI, once started.
+	session: Option<Url>,
+	/// Set once a chunk has failed to send.
+	error: Option<IoError>,
+}
+impl WriteState {
+	/// Move the pending writes contiguous with `buffer` into it.
+	fn gather(&mut self) {
+		loop {
+			let end = self.offset + u64::try_from(self.buffer.len()).unwrap();
+			match self.pending.remove(&end) {
+				Some(buf) if !buf.is_empty() => self.buffer.extend_from_slice(&buf),
+				_ => break,
+			}
+		}
+	}
+}
+impl GcsPageInner {
+	fn new(bucket: Bucket, name: String, len: u64) -> Self {
+		Self {
+			bucket,
+			name,
+			len,
+			write: Mutex::new(WriteState::default()),
+			upload: AsyncMutex::new(()),
+		}
+	}
+	async fn start_session(&self) -> Result<Url, IoError> {
+		let mut url = self.bucket.upload_url();
+		let _ = url
+			.query_pairs_mut()
+			.append_pair("uploadType", "resumable")
+			.append_pair("name", &self.name);
+		let res = self.bucket.send(|| CLIENT.post(url.clone())).await?;
+		let location = res
+			.headers()
+			.get(header::LOCATION)
+			.and_then(|location| location.to_str().ok())
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing session URI"))?;
+		url.join(location)
+			.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err).into())
+	}
+	/// Send `chunk` of the resumable upload, starting at `offset`. `total` is
+	/// the length of the object if this is the last chunk.
+	async fn send_chunk(
+		&self, session: &Url, offset: u64, chunk: Vec<u8>, total: Option<u64>,
+	) -> Result<(), IoError> {
+		let total = total.map_or_else(|| String::from("*"), |total| total.to_string());
+		let range = if chunk.is_empty() {
+			format!("bytes */{}", total)
+		} else {
+			let end = offset + u64::try_from(chunk.len()).unwrap() - 1;
+			format!("bytes {}-{}/{}", offset, end, total)
+		};
+		let _ = self
+			.bucket
+			.send(|| {
+				CLIENT
+					.put(session.clone())
+					.header(header::CONTENT_RANGE, range.clone())
+					.body(chunk.clone())
+			})
+			.await?;
+		Ok(())
+	}
+	/// Send the contiguous writes in whole chunks.
+	async fn append(&self) -> Result<(), IoError> {
+		let _upload = self.upload.lock().await;
+		loop {
+			let (offset, session, chunk) = {
+				let mut write = self.write.lock().unwrap();
+				if let Some(err) = &write.error {
+					return Err(err.clone());
+				}
+				write.gather();
+				if write.buffer.len() < CHUNK_SIZE {
+					return Ok(());
+				}
+				let rest = write.buffer.split_off(CHUNK_SIZE);
+				let chunk = mem::replace(&mut write.buffer, rest);
+				(write.offset, write.session.clone(), chunk)
+			};
+			let len = u64::try_from(chunk.len()).unwrap();
+			let res = async {
+				let session = match session {
+					Some(session) => session,
+					None => {
+						let session = self.start_session().await?;
+						self.write.lock().unwrap().session = Some(session.clone());
+						session
+					}
+				};
+				self.send_chunk(&session, offset, chunk, None).await
+			}
+			.await;
+			let mut write = self.write.lock().unwrap();
+			match res {
+				Ok(()) => write.offset += len,
+				Err(err) => {
+					write.error = Some(err.clone());
+					return Err(err);
+				}
+			}
+		}
+	}
+	/// Send what's left, completing the upload.
+	async fn finish(&self) -> Result<(), IoError> {
+		let _upload = self.upload.lock().await;
+		let (offset, session, chunk) = {
+			let mut write = self.write.lock().unwrap();
+			if let Some(err) = &write.error {
+				return Err(err.clone());
+			}
+			write.gather();
+			if write.pending.values().any(|buf| !buf.is_empty()) {
+				let err: IoError = io::Error::new(
+					io::ErrorKind::InvalidInput,
+					"GCS objects must be written contiguously from offset 0",
+				)
+				.into();
+				write.error = Some(err.clone());
+				return Err(err);
+			}
+			(
+				write.offset,
+				write.session.clone(),
+				mem::take(&mut write.buffer),
+			)
+		};
+		let total = offset + u64::try_from(chunk.len()).unwrap();
+		let res = match session {
+			Some(session) => self.send_chunk(&session, offset, chunk, Some(total)).await,
+			None => {
+				// Small enough to upload in one request
+				let mut url = self.bucket.upload_url();
+				let _ = url
+					.query_pairs_mut()
+					.append_pair("uploadType", "media")
+					.append_pair("name", &self.name);
+				self.bucket
+					.send(|| CLIENT.post(url.clone()).body(chunk.clone()))
+					.await
+					.map(drop)
+			}
+		};
+		let mut write = self.write.lock().unwrap();
+		match res {
+			Ok(()) => {
+				write.offset = total;
+				write.session = None;
+				Ok(())
+			}
+			Err(err) => {
+				write.error = Some(err.clone());
+				Err(err)
+			}
+		}
+	}
+}
+
+pub struct GcsPage {
+	inner: Arc<GcsPageInner>,
+}
+impl Page for GcsPage {
+	type Error = IoError;
+
+	fn len(&self) -> LocalBoxFuture<'static, Result<u64, Self::Error>> {
+		future::ready(Ok(self.inner.len)).boxed_local()
+	}
+	fn read(
+		&self, offset: u64, len: usize,
+	) -> LocalBoxFuture<'static, Result<SharedBuf, Self::Error>> {
+		let inner = self.inner.clone();
+		Box::pin(async move {
+			let len = len.min(usize::try_from(inner.len.saturating_sub(offset)).unwrap());
+			if len == 0 {
+				return Ok(SharedBuf::new());
+			}
+			let end = offset + u64::try_from(len).unwrap() - 1;
+			let mut url = inner.bucket.url(Some(&inner.name));
+			let _ = url.query_pairs_mut().append_pair("alt", "media");
+			let (inner, url) = (&inner, &url);
+			let buf = get_range(RetryPolicy::default(), offset, len, |start| async move {
+				let res = inner
+					.bucket
+					.send(|| {
+						CLIENT
+							.get(url.clone())
+							.header(header::RANGE, format!("bytes={}-{}", start, end))
+					})
+					.await?;
+				// A range covering the whole object may be answered with all of it
+				let start = if res.status() == StatusCode::PARTIAL_CONTENT {
+					start
+				} else {
+					0
+				};
+				Ok::<_, IoError>((res.bytes_stream().map_err(error), start))
+			})
+			.await?;
+			Ok(buf.into())
+		})
+	}
+	fn write(
+		&self, offset: u64, buf: Box<[u8]>,
+	) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
+		let inner = self.inner.clone();
+		Box::pin(async move {
+			{
+				let mut write = inner.write.lock().unwrap();
+				if let Some(err) = &write.error {
+					return Err(err.clone());
+				}
+				let end = write.offset + u64::try_from(write.buffer.len()).unwrap();
+				if offset < end || write.pending.contains_key(&offset) {
+					return Err(io::Error::new(
+						io::ErrorKind::InvalidInput,
+						"GCS objects must be written contiguously, without overlapping writes",
+					)
+					.into());
+				}
+				let _ = write.pending.insert(offset, buf);
+			}
+			inner.append().await
+		})
+	}
+	fn flush(&self) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
+		let inner = self.inner.clone();
+		Box::pin(async move { inner.finish().await })
+	}
+}
@@ -0,0 +1,213 @@
+//! Harmonious distributed data processing & analysis in Rust.
+//!
+//! <p style="font-family: 'Fira Sans',sans-serif;padding:0.3em 0"><strong>
+//! <a href="https://crates.io/crates/amadeus">📦&nbsp;&nbsp;Crates.io</a>&nbsp;&nbsp;│&nbsp;&nbsp;<a href="https://github.com/constellation-rs/amadeus">📑&nbsp;&nbsp;GitHub</a>&nbsp;&nbsp;│&nbsp;&nbsp;<a href="https://constellation.zulipchat.com/#narrow/stream/213231-amadeus">💬&nbsp;&nbsp;Chat</a>
+//! </strong></p>
+//!
+//! This is a support crate of [Amadeus](https://github.com/constellation-rs/amadeus) and is not intended to be used directly. These types are re-exposed in [`amadeus::source`](https://docs.rs/amadeus/0.3/amadeus/source/index.html).
+
+#![doc(html_root_url = "https://docs.rs/amadeus-gcs/0.4.1")]
+#![warn(
+	// missing_copy_implementations,
+	// missing_debug_implementations,
+	// missing_docs,
+	trivial_numeric_casts,
+	unused_import_braces,
+	unused_qualifications,
+	unused_results,
+	unreachable_pub,
+	clippy::pedantic,
+)]
+#![allow(
+	clippy::module_name_repetitions,
+	clippy::must_use_candidate,
+	clippy::missing_errors_doc
+)]
+#![deny(unsafe_code)]
+
+mod file;
+
+use futures::FutureExt;
+use once_cell::sync::Lazy;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::{io, time::Duration};
+use url::Url;
+
+use amadeus_core::{file::RetryPolicy, util::IoError};
+
+pub use file::{GcsDirectory, GcsFile, GcsPage, GcsPartition};
+
+static CLIENT: Lazy<Client> = Lazy::new(|| {
+	Client::builder()
+		.timeout(Duration::from_secs(120))
+		.redirect(reqwest::redirect::Policy::none())
+		.build()
+		.unwrap()
+});
+
+/// How requests to Google Cloud Storage are authorized.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum GcsCredentials {
+	/// No authorization, for publicly readable buckets.
+	Anonymous,
+	/// An OAuth 2.0 access token, as printed by `gcloud auth print-access-token`.
+	Token(String),
+}
+impl Default for GcsCredentials {
+	fn default() -> Self {
+		GcsCredentials::Anonymous
+	}
+}
+
+/// A bucket, and the endpoint and credentials to reach it with.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+struct Bucket {
+	name: String,
+	endpoint: Option<Url>,
+	credentials: GcsCredentials,
+}
+impl Bucket {
+	fn endpoint(&self) -> Url {
+		self.endpoint
+			.clone()
+			.unwrap_or_else(|| Url::parse("https://storage.googleapis.com").unwrap())
+	}
+	/// The JSON API URL of the bucket's objects, or of the object `object`.
+	fn url(&self, object: Option<&str>) -> Url {
+		let mut url = self.endpoint();
+		{
+			let mut segments = url
+				.path_segments_mut()
+				.expect("GCS endpoint can't be a base");
+			let _ =
+				segments
+					.pop_if_empty()
+					.extend(&["storage", "v1", "b", self.name.as_str(), "o"]);
+			if let Some(object) = object {
+				// Slashes are part of the name, so are escaped
+				let _ = segments.push(object);
+			}
+		}
+		url
+	}
+	/// The URL to upload objects to.
+	fn upload_url(&self) -> Url {
+		let mut url = self.endpoint();
+		let _ = url
+			.path_segments_mut()
+			.expect("GCS endpoint can't be a base")
+			.pop_if_empty()
+			.extend(&["upload", "storage", "v1", "b", self.name.as_str(), "o"]);
+		url
+	}
+
+	/// Send the request built by `request`, authorized with the bucket's
+	/// credentials. Connection errors, timeouts, 5xx and 429 responses are
+	/// retried as per the default [`RetryPolicy`]. Other error statuses are
+	/// returned as errors, with the message from the body if present.
+	async fn send<F>(&self, request: F) -> Result<Response, IoError>
+	where
+		F: Fn() -> RequestBuilder,
+	{
+		// Responses worth retrying are passed as `Err(Ok(response))`
+		let res = RetryPolicy::default()
+			.retry(
+				|| {
+					let mut req = request();
+					if let GcsCredentials::Token(token) = &self.credentials {
+						req = req.bearer_auth(token);
+					}
+					req.send().map(|res| match res {
+						Ok(res)
+							if res.status().is_server_error()
+								|| res.status() == StatusCode::TOO_MANY_REQUESTS =>
+						{
+							Err(Ok(res))
+						}
+						Ok(res) => Ok(res),
+						Err(err) => Err(Err(err)),
+					})
+				},
+				|err| match err {
+					Ok(_) => true,
+					Err(err) => err.is_connect() || err.is_timeout() || err.is_request(),
+				},
+			)
+			.await;
+		let res = match res {
+			Ok(res) | Err(Ok(res)) => res,
+			Err(Err(err)) => return Err(error(err)),
+		};
+		if res.status().is_client_error() || res.status().is_server_error() {
+			return Err(error_response(res).await);
+		}
+		Ok(res)
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Objects {
+	#[serde(default)]
+	items: Vec<Object>,
+	#[serde(default)]
+	prefixes: Vec<String>,
+	next_page_token: Option<String>,
+}
+#[derive(Deserialize)]
+struct Object {
+	name: String,
+	#[serde(deserialize_with = "size")]
+	size: u64,
+}
+/// The JSON API gives sizes as strings, but emulators may not.
+fn size<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Size {
+		String(String),
+		Number(u64),
+	}
+	match Size::deserialize(deserializer)? {
+		Size::String(size) => size.parse().map_err(de::Error::custom),
+		Size::Number(size) => Ok(size),
+	}
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+	error: ErrorMessage,
+}
+#[derive(Deserialize)]
+struct ErrorMessage {
+	message: String,
+}
+
+async fn error_response(res: Response) -> IoError {
+	let status = res.status();
+	let kind = match status {
+		StatusCode::NOT_FOUND => io::ErrorKind::NotFound,
+		StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => io::ErrorKind::PermissionDenied,
+		_ => io::ErrorKind::Other,
+	};
+	let message = match res.json::<ErrorResponse>().await {
+		Ok(ErrorResponse {
+			error: ErrorMessage { message },
+		}) => message,
+		Err(_) => format!("GCS request failed: {}", status),
+	};
+	io::Error::new(kind, message).into()
+}
+
+fn error(err: reqwest::Error) -> IoError {
+	let kind = if err.is_timeout() {
+		io::ErrorKind::TimedOut
+	} else {
+		io::ErrorKind::Other
+	};
+	io::Error::new(kind, err).into()
+}
@@ -0,0 +1,588 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{
+	future, future::LocalBoxFuture, lock::Mutex as AsyncMutex, FutureExt, Stream, TryStreamExt
+};
+use reqwest::{header, Method};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap, convert::TryFrom, io, sync::{Arc, Mutex}
+};
+use url::Url;
+
+use amadeus_core::{
+	file::{get_range, Directory, File, Page, Partition, PathBuf, RetryPolicy, SharedBuf}, util::IoError
+};
+
+use super::{
+	error, send, send_with, BlockLocation, FileStatus, FileType, Namenode, CLIENT, CLIENT_NO_REDIRECT
+};
+
+/// A directory in HDFS, accessed over WebHDFS, including the files in its
+/// subdirectories.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct HdfsDirectory {
+	namenode: Namenode,
+	path: String,
+	#[serde(default)]
+	split_blocks: bool,
+}
+impl HdfsDirectory {
+	/// The directory at `path` on the namenode whose WebHDFS endpoint is
+	/// `namenode`, like `http://namenode:9870`.
+	pub fn new(namenode: Url, path: &str) -> Self {
+		let namenode = Namenode {
+			url: namenode,
+			user: None,
+		};
+		let path = path.to_owned();
+		Self {
+			namenode,
+			path,
+			split_blocks: false,
+		}
+	}
+	/// Act as `user`, for clusters using simple authentication.
+	pub fn user(mut self, user: &str) -> Self {
+		self.namenode.user = Some(user.to_owned());
+		self
+	}
+	/// Whether to make a partition of each block of each file, rather than of
+	/// each file. See [`HdfsFile::split_blocks`].
+	pub fn split_blocks(mut self, split_blocks: bool) -> Self {
+		self.split_blocks = split_blocks;
+		self
+	}
+	/// The file `name` in this directory, for example to [create](HdfsFile::create) it.
+	pub fn file(&self, name: &str) -> HdfsFile {
+		HdfsFile {
+			namenode: self.namenode.clone(),
+			path: format!("{}/{}", self.path.trim_end_matches('/'), name),
+			split_blocks: self.split_blocks,
+		}
+	}
+}
+#[async_trait(?Send)]
+impl Directory for HdfsDirectory {
+	async fn partitions_filter<F>(
+		self, mut f: F,
+	) -> Result<Vec<<Self as File>::Partition>, <Self as File>::Error>
+	where
+		F: FnMut(&PathBuf) -> bool,
+	{
+		let mut files = Vec::new();
+		let mut path = PathBuf::new();
+		let mut ancestors = vec![self.path.clone()];
+		walk(
+			&self.namenode,
+			&self.path,
+			&mut path,
+			&mut f,
+			&mut ancestors,
+			&mut files,
+		)
+		.await?;
+		let partitions = files.into_iter().map(|(path, status)| {
+			partitions(
+				&self.namenode,
+				path,
+				status.length,
+				status.block_size,
+				self.split_blocks,
+			)
+		});
+		Ok(future::try_join_all(partitions)
+			.await?
+			.into_iter()
+			.flatten()
+			.collect())
+	}
+}
+#[async_trait(?Send)]
+impl File for HdfsDirectory {
+	type Partition = HdfsPartition;
+	type Error = IoError;
+
+	async fn partitions(self) -> Result<Vec<Self::Partition>, Self::Error> {
+		self.partitions_filter(|_| true).await
+	}
+}
+
+/// List the directory `dir`, at `path` relative to the directory being listed,
+/// descending into the subdirectories that `f` accepts.
+///
+/// Symlinks are followed, except to a directory that's already being walked,
+/// and dangling ones are skipped. `ancestors` are the directories being walked,
+/// with symlinks resolved.
+fn walk<'a, F>(
+	namenode: &'a Namenode, dir: &'a str, path: &'a mut PathBuf, f: &'a mut F,
+	ancestors: &'a mut Vec<String>, files: &'a mut Vec<(String, FileStatus)>,
+) -> LocalBoxFuture<'a, Result<(), IoError>>
+where
+	F: FnMut(&PathBuf) -> bool,
+{
+	Box::pin(async move {
+		for status in namenode.list_status(dir).await? {
+			let child = format!("{}/{}", dir.trim_end_matches('/'), status.path_suffix);
+			let real_dir = ancestors.last().unwrap().trim_end_matches('/');
+			let (status, real) = match status.type_ {
+				FileType::Symlink => {
+					let target = status.symlink.as_deref().unwrap_or("");
+					let real = resolve(real_dir, target);
+					// GETFILESTATUS follows the link, unlike LISTSTATUS
+					match namenode.file_status(&child).await {
+						Ok(target) => (
+							FileStatus {
+								path_suffix: status.path_suffix,
+								..target
+							},
+							real,
+						),
+						Err(err) => {
+							let err = io::Error::from(err);
+							if err.kind() == io::ErrorKind::NotFound {
+								continue;
+							}
+							return Err(err.into());
+						}
+					}
+				}
+				_ => {
+					let real = format!("{}/{}", real_dir, status.path_suffix);
+					(status, real)
+				}
+			};
+			match status.type_ {
+				FileType::Directory => {
+					let within = |dir: &str| {
+						dir == real || dir.starts_with(&format!("{}/", real.trim_end_matches('/')))
+					};
+					if ancestors.iter().any(|ancestor| within(ancestor)) {
+						continue;
+					}
+					path.push(status.path_suffix.as_str());
+					if f(&*path) {
+						ancestors.push(real);
+						walk(
+							namenode,
+							&child,
+							&mut *path,
+							&mut *f,
+							&mut *ancestors,
+							&mut *files,
+						)
+						.await?;
+						let _ = ancestors.pop();
+					}
+					let _ = path.pop();
+				}
+				FileType::File => {
+					path.set_file_name(Some(status.path_suffix.as_str()));
+					if f(&*path) {
+						files.push((child, status));
+					}
+					path.set_file_name::<&str>(None);
+				}
+				FileType::Symlink => unreachable!("GETFILESTATUS follows symlinks"),
+			}
+		}
+		Ok(())
+	})
+}
+
+/// The absolute path of the symlink target `target`, which may be a URI, or
+/// relative to the directory `dir` containing the symlink.
+fn resolve(dir: &str, target: &str) -> String {
+	if let Ok(url) = Url::parse(target) {
+		url.path().to_owned()
+	} else if target.starts_with('/') {
+		target.to_owned()
+	} else {
+		format!("{}/{}", dir, target)
+	}
+}
+
+/// The partitions of the file at `path`: one per block if `split_blocks`, or
+/// else one of the whole file.
+async fn partitions(
+	namenode: &Namenode, path: String, len: u64, block_size: u64, split_blocks: bool,
+) -> Result<Vec<HdfsPartition>, IoError> {
+	let blocks = namenode.block_locations(&path, 0, len).await?;
+	if split_blocks {
+		return Ok(blocks
+			.into_iter()
+			.map(|block| HdfsPartition {
+				namenode: namenode.clone(),
+				path: path.clone(),
+				offset: block.offset,
+				len: block.length,
+				block_size,
+				hosts: block.hosts,
+			})
+			.collect());
+	}
+	// The datanodes holding the most of the file come first
+	let mut held = BTreeMap::<String, u64>::new();
+	for block in blocks {
+		for host in block.hosts {
+			*held.entry(host).or_default() += block.length;
+		}
+	}
+	let mut hosts = held.into_iter().collect::<Vec<_>>();
+	hosts.sort_by(|(_, a), (_, b)| b.cmp(a));
+	Ok(vec![HdfsPartition {
+		namenode: namenode.clone(),
+		path,
+		offset: 0,
+		len,
+		block_size,
+		hosts: hosts.into_iter().map(|(host, _)| host).collect(),
+	}])
+}
+
+/// A file in HDFS, accessed over WebHDFS.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct HdfsFile {
+	namenode: Namenode,
+	path: String,
+	#[serde(default)]
+	split_blocks: bool,
+}
+impl HdfsFile {
+	/// The file at `path` on the namenode whose WebHDFS endpoint is
+	/// `namenode`, like `http://namenode:9870`.
+	pub fn new(namenode: Url, path: &str) -> Self {
+		let namenode = Namenode {
+			url: namenode,
+			user: None,
+		};
+		let path = path.to_owned();
+		Self {
+			namenode,
+			path,
+			split_blocks: false,
+		}
+	}
+	/// Act as `user`, for clusters using simple authentication.
+	pub fn user(mut self, user: &str) -> Self {
+		self.namenode.user = Some(user.to_owned());
+		self
+	}
+	/// Whether to make a partition of each block of this file, rather than
+	/// one of the whole file.
+	///
+	/// Each block's partition can then be processed near the datanodes storing
+	/// it. However, the built-in formats like [`Csv`](https://docs.rs/amadeus/0.4/amadeus/source/struct.Csv.html)
+	/// read each partition as a file in its own right, so this is only
+	/// useful where records don't span blocks, or when reading pages directly.
+	pub fn split_blocks(mut self, split_blocks: bool) -> Self {
+		self.split_blocks = split_blocks;
+		self
+	}
+	/// A [`Page`] that creates this file, or replaces it if it exists.
+	///
+	/// HDFS files can only be appended to, so writes must together cover the
+	/// file contiguously from offset 0. They can arrive in any order and
+	/// concurrently, and are buffered until they can be appended. The file
+	/// is created once the first write is sent, or on [flush](Page::flush)
+	/// if there are none.
+	pub fn create(self) -> HdfsPage {
+		let inner = Arc::new(HdfsPageInner::new(self.namenode, self.path, 0, 0, 0));
+		HdfsPage { inner }
+	}
+}
+#[async_trait(?Send)]
+impl File for HdfsFile {
+	type Partition = HdfsPartition;
+	type Error = IoError;
+
+	async fn partitions(self) -> Result<Vec<Self::Partition>, Self::Error> {
+		let status = self.namenode.file_status(&self.path).await?;
+		if status.type_ != FileType::File {
+			return Err(io::Error::new(
+				io::ErrorKind::Other,
+				format!("{} is not a file", self.path),
+			)
+			.into());
+		}
+		partitions(
+			&self.namenode,
+			self.path,
+			status.length,
+			status.block_size,
+			self.split_blocks,
+		)
+		.await
+	}
+}
+
+/// A file in HDFS, or with [`split_blocks`](HdfsFile::split_blocks) one block
+/// of it.
+///
+/// Reads are split at block boundaries, so that each is redirected by the
+/// namenode to a datanode holding a replica of the block being read, and
+/// [`hosts`](Self::hosts) gives the datanodes holding its data, for example to
+/// schedule work near them.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct HdfsPartition {
+	namenode: Namenode,
+	path: String,
+	/// Where in the file this partition begins.
+	offset: u64,
+	len: u64,
+	block_size: u64,
+	hosts: Vec<String>,
+}
+impl HdfsPartition {
+	/// The datanodes holding a replica of this partition's data, those
+	/// holding the most of it first.
+	pub fn hosts(&self) -> &[String] {
+		&self.hosts
+	}
+	/// The blocks of this partition and the datanodes storing them.
+	pub async fn block_locations(&self) -> Result<Vec<BlockLocation>, IoError> {
+		self.namenode
+			.block_locations(&self.path, self.offset, self.len)
+			.await
+	}
+}
+#[async_trait(?Send)]
+impl Partition for HdfsPartition {
+	type Page = HdfsPage;
+	type Error = IoError;
+
+	async fn pages(self) -> Result<Vec<Self::Page>, Self::Error> {
+		let inner = HdfsPageInner::new(
+			self.namenode,
+			self.path,
+			self.offset,
+			self.len,
+			self.block_size,
+		);
+		Ok(vec![HdfsPage {
+			inner: Arc::new(inner),
+		}])
+	}
+	fn name(&self) -> Option<String> {
+		Some(self.path.clone())
+	}
+}
+
+struct HdfsPageInner {
+	namenode: Namenode,
+	path: String,
+	/// Where in the file this page begins.
+	offset: u64,
+	len: u64,
+	block_size: u64,
+	write: Mutex<WriteState>,
+	/// Held while appending, as HDFS allows only one writer at a time.
+	upload: AsyncMutex<()>,
+}
+#[derive(Default)]
+struct WriteState {
+	/// Writes that haven't yet been appended, by offset.
+	pending: BTreeMap<u64, Box<[u8]>>,
+	/// The offset up to which writes have been appended.
+	offset: u64,
+	created: bool,
+	/// Set once a write has failed.
+	error: Option<IoError>,
+}
+impl WriteState {
+	/// Take the pending writes contiguous with those already appended.
+	fn take(&mut self) -> Vec<u8> {
+		let mut data = Vec::new();
+		let mut end = self.offset;
+		while let Some(buf) = self.pending.remove(&end) {
+			end += u64::try_from(buf.len()).unwrap();
+			data.extend_from_slice(&buf);
+		}
+		data
+	}
+}
+impl HdfsPageInner {
+	fn new(namenode: Namenode, path: String, offset: u64, len: u64, block_size: u64) -> Self {
+		Self {
+			namenode,
+			path,
+			offset,
+			len,
+			block_size,
+			write: Mutex::new(WriteState::default()),
+			upload: AsyncMutex::new(()),
+		}
+	}
+	/// Request the `length` bytes of the file at `start`, resolving to the
+	/// body and the offset it begins at. The namenode redirects to a datanode
+	/// holding the block.
+	async fn open(
+		&self, start: u64, length: u64,
+	) -> Result<(impl Stream<Item = Result<Bytes, IoError>>, u64), IoError> {
+		let mut url = self.namenode.url(&self.path, "OPEN");
+		let _ = url
+			.query_pairs_mut()
+			.append_pair("offset", &start.to_string())
+			.append_pair("length", &length.to_string());
+		let res = send(|| CLIENT.get(url.clone())).await?;
+		Ok((res.bytes_stream().map_err(error), start))
+	}
+	/// Create the file with `data`, or append `data` to it once created, when
+	/// it's `offset` bytes long. The namenode redirects to the datanode the
+	/// data is then sent to.
+	async fn upload(&self, created: bool, offset: u64, data: Vec<u8>) -> Result<(), IoError> {
+		let (method, url) = if created {
+			(Method::POST, self.namenode.url(&self.path, "APPEND"))
+		} else {
+			let mut url = self.namenode.url(&self.path, "CREATE");
+			let _ = url.query_pairs_mut().append_pair("overwrite", "true");
+			(Method::PUT, url)
+		};
+		let res = send(|| CLIENT_NO_REDIRECT.request(method.clone(), url.clone())).await?;
+		let location = res
+			.headers()
+			.get(header::LOCATION)
+			.and_then(|location| location.to_str().ok())
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing datanode redirect"))?
+			.to_owned();
+		let request = || {
+			CLIENT_NO_REDIRECT
+				.request(method.clone(), location.as_str())
+				.body(data.clone())
+		};
+		if !created {
+			// Creating with overwrite is idempotent, so can be retried
+			let _ = send(request).await?;
+			return Ok(());
+		}
+		// Appending isn't, so instead if it fails check whether it took effect
+		// regardless, for example if only the response was lost
+		let err = match send_with(RetryPolicy::default().max_attempts(1), request).await {
+			Ok(_) => return Ok(()),
+			Err(err) => err,
+		};
+		let len = offset + u64::try_from(data.len()).unwrap();
+		match self.namenode.file_status(&self.path).await {
+			Ok(status) if status.length == len => Ok(()),
+			_ => Err(err),
+		}
+	}
+	/// Append the pending writes that can be, until there are none.
+	async fn append(&self) -> Result<(), IoError> {
+		let _upload = self.upload.lock().await;
+		loop {
+			let (created, offset, data) = {
+				let mut write = self.write.lock().unwrap();
+				if let Some(err) = &write.error {
+					return Err(err.clone());
+				}
+				(write.created, write.offset, write.take())
+			};
+			if data.is_empty() {
+				return Ok(());
+			}
+			let len = u64::try_from(data.len()).unwrap();
+			let res = self.upload(created, offset, data).await;
+			let mut write = self.write.lock().unwrap();
+			match res {
+				Ok(()) => {
+					write.offset += len;
+					write.created = true;
+				}
+				Err(err) => {
+					write.error = Some(err.clone());
+					return Err(err);
+				}
+			}
+		}
+	}
+}
+
+pub struct HdfsPage {
+	inner: Arc<HdfsPageInner>,
+}
+impl Page for HdfsPage {
+	type Error = IoError;
+
+	fn len(&self) -> LocalBoxFuture<'static, Result<u64, Self::Error>> {
+		future::ready(Ok(self.inner.len)).boxed_local()
+	}
+	fn read(
+		&self, offset: u64, len: usize,
+	) -> LocalBoxFuture<'static, Result<SharedBuf, Self::Error>> {
+		let inner = self.inner.clone();
+		Box::pin(async move {
+			let len = len.min(usize::try_from(inner.len.saturating_sub(offset)).unwrap());
+			let start = inner.offset + offset;
+			let end = start + u64::try_from(len).unwrap();
+			let mut chunks = Vec::new();
+			let mut position = start;
+			while position < end {
+				// Don't cross a block boundary, so the read is served by one datanode
+				let block_end = match inner.block_size {
+					0 => end,
+					block_size => ((position / block_size + 1) * block_size).min(end),
+				};
+				let length = usize::try_from(block_end - position).unwrap();
+				let chunk = get_range(RetryPolicy::default(), position, length, |start| {
+					inner.open(start, block_end - start)
+				})
+				.await?;
+				chunks.push(chunk);
+				position = block_end;
+			}
+			// A read within one block is returned without copying
+			if chunks.len() == 1 {
+				return Ok(chunks.pop().unwrap().into());
+			}
+			Ok(chunks.concat().into())
+		})
+	}
+	fn write(
+		&self, offset: u64, buf: Box<[u8]>,
+	) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
+		let inner = self.inner.clone();
+		Box::pin(async move {
+			{
+				let mut write = inner.write.lock().unwrap();
+				if let Some(err) = &write.error {
+					return Err(err.clone());
+				}
+				if offset < write.offset || write.pending.contains_key(&offset) {
+					return Err(io::Error::new(
+						io::ErrorKind::InvalidInput,
+						"HDFS files must be written contiguously, without overlapping writes",
+					)
+					.into());
+				}
+				let _ = write.pending.insert(offset, buf);
+			}
+			inner.append().await
+		})
+	}
+	fn flush(&self) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
+		let inner = self.inner.clone();
+		Box::pin(async move {
+			inner.append().await?;
+			let _upload = inner.upload.lock().await;
+			let created = {
+				let mut write = inner.write.lock().unwrap();
+				if !write.pending.is_empty() {
+					let err: IoError = io::Error::new(
+						io::ErrorKind::InvalidInput,
+						"HDFS files must be written contiguously from offset 0",
+					)
+					.into();
+					write.error = Some(err.clone());
+					return Err(err);
+				}
+				write.created
+			};
+			if !created {
+				inner.upload(false, 0, Vec::new()).await?;
+				inner.write.lock().unwrap().created = true;
+			}
+			Ok(())
+		})
+	}
+}
@@ -0,0 +1,253 @@
+//! Harmonious distributed data processing & analysis in Rust.
+//!
+//! <p style="font-family: 'Fira Sans',sans-serif;padding:0.3em 0"><strong>
+//! <a href="https://crates.io/crates/amadeus">📦&nbsp;&nbsp;Crates.io</a>&nbsp;&nbsp;│&nbsp;&nbsp;<a href="https://github.com/constellation-rs/amadeus">📑&nbsp;&nbsp;GitHub</a>&nbsp;&nbsp;│&nbsp;&nbsp;<a href="https://constellation.zulipchat.com/#narrow/stream/213231-amadeus">💬&nbsp;&nbsp;Chat</a>
+//! </strong></p>
+//!
+//! This is a support crate of [Amadeus](https://github.com/constellation-rs/amadeus) and is not intended to be used directly. These types are re-exposed in [`amadeus::source`](https://docs.rs/amadeus/0.3/amadeus/source/index.html).
+
+#![doc(html_root_url = "https://docs.rs/amadeus-hdfs/0.4.1")]
+#![warn(
+	// missing_copy_implementations,
+	// missing_debug_implementations,
+	// missing_docs,
+	trivial_numeric_casts,
+	unused_import_braces,
+	unused_qualifications,
+	unused_results,
+	unreachable_pub,
+	clippy::pedantic,
+)]
+#![allow(
+	clippy::module_name_repetitions,
+	clippy::must_use_candidate,
+	clippy::missing_errors_doc
+)]
+#![deny(unsafe_code)]
+
+mod file;
+
+use futures::FutureExt;
+use once_cell::sync::Lazy;
+use reqwest::{redirect, Client, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{io, time::Duration};
+use url::Url;
+
+use amadeus_core::{file::RetryPolicy, util::IoError};
+
+pub use file::{HdfsDirectory, HdfsFile, HdfsPage, HdfsPartition};
+
+static CLIENT: Lazy<Client> = Lazy::new(|| {
+	Client::builder()
+		.timeout(Duration::from_secs(120))
+		.build()
+		.unwrap()
+});
+/// For `CREATE` and `APPEND`, where the namenode redirects to a datanode that
+/// the data is then sent to.
+static CLIENT_NO_REDIRECT: Lazy<Client> = Lazy::new(|| {
+	Client::builder()
+		.timeout(Duration::from_secs(120))
+		.redirect(redirect::Policy::none())
+		.build()
+		.unwrap()
+});
+
+/// The namenode's WebHDFS endpoint, like `http://namenode:9870`, and the user
+/// to act as.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+struct Namenode {
+	url: Url,
+	user: Option<String>,
+}
+impl Namenode {
+	/// The WebHDFS URL for operation `op` on the absolute HDFS path `path`.
+	fn url(&self, path: &str, op: &str) -> Url {
+		let mut url = self.url.clone();
+		let _ = url
+			.path_segments_mut()
+			.expect("WebHDFS URL can't be a base")
+			.pop_if_empty()
+			.extend(
+				["webhdfs", "v1"]
+					.iter()
+					.copied()
+					.chain(path.split('/').filter(|segment| !segment.is_empty())),
+			);
+		let _ = url.query_pairs_mut().clear().append_pair("op", op);
+		if let Some(user) = &self.user {
+			let _ = url.query_pairs_mut().append_pair("user.name", user);
+		}
+		url
+	}
+	async fn file_status(&self, path: &str) -> Result<FileStatus, IoError> {
+		let url = self.url(path, "GETFILESTATUS");
+		let res = send(|| CLIENT.get(url.clone())).await?;
+		let res: FileStatusResponse = res.json().await.map_err(error)?;
+		Ok(res.file_status)
+	}
+	async fn block_locations(
+		&self, path: &str, offset: u64, length: u64,
+	) -> Result<Vec<BlockLocation>, IoError> {
+		let mut url = self.url(path, "GETFILEBLOCKLOCATIONS");
+		let _ = url
+			.query_pairs_mut()
+			.append_pair("offset", &offset.to_string())
+			.append_pair("length", &length.to_string());
+		let res = send(|| CLIENT.get(url.clone())).await?;
+		let res: BlockLocationsResponse = res.json().await.map_err(error)?;
+		Ok(res.block_locations.block_location)
+	}
+	async fn list_status(&self, path: &str) -> Result<Vec<FileStatus>, IoError> {
+		let url = self.url(path, "LISTSTATUS");
+		let res = send(|| CLIENT.get(url.clone())).await?;
+		let res: FileStatusesResponse = res.json().await.map_err(error)?;
+		Ok(res.file_statuses.file_status)
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileStatus {
+	path_suffix: String,
+	#[serde(rename = "type")]
+	type_: FileType,
+	length: u64,
+	block_size: u64,
+	/// The target of a symlink.
+	#[serde(default)]
+	symlink: Option<String>,
+}
+#[derive(Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+enum FileType {
+	File,
+	Directory,
+	Symlink,
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FileStatusResponse {
+	file_status: FileStatus,
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FileStatusesResponse {
+	file_statuses: FileStatuses,
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FileStatuses {
+	file_status: Vec<FileStatus>,
+}
+
+/// Where the replicas of one block of a file are stored.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct BlockLocation {
+	pub offset: u64,
+	pub length: u64,
+	/// The datanodes holding a replica of this block.
+	pub hosts: Vec<String>,
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BlockLocationsResponse {
+	block_locations: BlockLocations,
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BlockLocations {
+	block_location: Vec<BlockLocation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RemoteExceptionResponse {
+	remote_exception: RemoteException,
+}
+#[derive(Deserialize)]
+struct RemoteException {
+	exception: String,
+	message: String,
+}
+
+/// Send the request built by `request`, retrying connection errors, timeouts,
+/// 429 and 502-504 responses as per the default [`RetryPolicy`]. Other error
+/// statuses are returned as errors, from the `RemoteException` in the body if
+/// present.
+async fn send<F>(request: F) -> Result<Response, IoError>
+where
+	F: Fn() -> RequestBuilder,
+{
+	send_with(RetryPolicy::default(), request).await
+}
+
+/// [`send`] with the given [`RetryPolicy`].
+async fn send_with<F>(policy: RetryPolicy, request: F) -> Result<Response, IoError>
+where
+	F: Fn() -> RequestBuilder,
+{
+	// Responses worth retrying are passed as `Err(Ok(response))`
+	let res = policy
+		.retry(
+			|| {
+				request().send().map(|res| match res {
+					Ok(res) => match res.status() {
+						StatusCode::TOO_MANY_REQUESTS
+						| StatusCode::BAD_GATEWAY
+						| StatusCode::SERVICE_UNAVAILABLE
+						| StatusCode::GATEWAY_TIMEOUT => Err(Ok(res)),
+						_ => Ok(res),
+					},
+					Err(err) => Err(Err(err)),
+				})
+			},
+			|err| match err {
+				Ok(_) => true,
+				Err(err) => err.is_connect() || err.is_timeout() || err.is_request(),
+			},
+		)
+		.await;
+	let res = match res {
+		Ok(res) | Err(Ok(res)) => res,
+		Err(Err(err)) => return Err(error(err)),
+	};
+	if res.status().is_client_error() || res.status().is_server_error() {
+		return Err(remote_exception(res).await);
+	}
+	Ok(res)
+}
+
+async fn remote_exception(res: Response) -> IoError {
+	let status = res.status();
+	let kind = match status {
+		StatusCode::NOT_FOUND => io::ErrorKind::NotFound,
+		StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => io::ErrorKind::PermissionDenied,
+		_ => io::ErrorKind::Other,
+	};
+	match res.json::<RemoteExceptionResponse>().await {
+		Ok(RemoteExceptionResponse {
+			remote_exception: RemoteException { exception, message },
+		}) => {
+			let kind = match &*exception {
+				"FileNotFoundException" => io::ErrorKind::NotFound,
+				"AccessControlException" | "SecurityException" => io::ErrorKind::PermissionDenied,
+				"FileAlreadyExistsException" => io::ErrorKind::AlreadyExists,
+				_ => kind,
+			};
+			io::Error::new(kind, format!("{}: {}", exception, message))
+		}
+		Err(_) => io::Error::new(kind, format!("WebHDFS request failed: {}", status)),
+	}
+	.into()
+}
+
+fn error(err: reqwest::Error) -> IoError {
+	let kind = if err.is_timeout() {
+		io::ErrorKind::TimedOut
+	} else {
+		io::ErrorKind::Other
+	};
+	io::Error::new(kind, err).into()
+}
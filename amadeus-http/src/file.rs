@@ -0,0 +1,262 @@
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use futures::{future, future::LocalBoxFuture, lock::Mutex, FutureExt, Stream, TryStreamExt};
+use reqwest::{header, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, io, sync::Arc};
+use url::Url;
+
+use amadeus_core::{
+	file::{
+		get_range, get_resumable, Directory, File, Page, Partition, PathBuf, RetryPolicy, SharedBuf
+	}, util::IoError
+};
+
+use super::{error, send, CLIENT};
+
+/// A set of files served over HTTP(S), given by their URLs.
+///
+/// Paths passed to [`partitions_filter`](Directory::partitions_filter) are the
+/// segments of each URL's path, as they appear in the URL.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct HttpDirectory {
+	urls: Vec<Url>,
+}
+impl HttpDirectory {
+	pub fn new(urls: Vec<Url>) -> Self {
+		Self { urls }
+	}
+}
+#[async_trait(?Send)]
+impl Directory for HttpDirectory {
+	async fn partitions_filter<F>(
+		self, mut f: F,
+	) -> Result<Vec<<Self as File>::Partition>, <Self as File>::Error>
+	where
+		F: FnMut(&PathBuf) -> bool,
+	{
+		Ok(self
+			.urls
+			.into_iter()
+			.filter(|url| {
+				let mut segments = url
+					.path_segments()
+					.into_iter()
+					.flatten()
+					.collect::<Vec<_>>();
+				let file_name = segments.pop().unwrap_or("");
+				let mut path = PathBuf::new();
+				for segment in segments {
+					path.push(segment);
+					if !f(&path) {
+						return false;
+					}
+				}
+				path.set_file_name(Some(file_name));
+				f(&path)
+			})
+			.map(HttpFile::new)
+			.collect())
+	}
+}
+#[async_trait(?Send)]
+impl File for HttpDirectory {
+	type Partition = HttpFile;
+	type Error = IoError;
+
+	async fn partitions(self) -> Result<Vec<Self::Partition>, Self::Error> {
+		self.partitions_filter(|_| true).await
+	}
+}
+
+/// A file served over HTTP(S).
+///
+/// Its length is taken from the `Content-Length` of a `HEAD` request, and
+/// pages are read with `Range` requests where the server supports them.
+/// Responses that are cut short are resumed from where they left off.
+///
+/// If the `HEAD` request fails, for example with `405 Method Not Allowed`, or
+/// its response has no `Content-Length`, for example as it's chunked, the whole
+/// file is instead downloaded with a `GET` request to find its length.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct HttpFile {
+	url: Url,
+}
+impl HttpFile {
+	pub fn new(url: Url) -> Self {
+		Self { url }
+	}
+	/// The whole file, downloaded as a stream of chunks and resumed from where it left off
+	/// should the response be cut short. Unlike reading its pages, this needs neither a `HEAD`
+	/// request nor support for `Range` requests.
+	pub fn bytes_stream(&self) -> impl Stream<Item = Result<Bytes, IoError>> + Send + Unpin {
+		let url = self.url.clone();
+		Box::pin(get_resumable(
+			RetryPolicy::default(),
+			0,
+			None,
+			move |start| {
+				let url = url.clone();
+				async move { get(&url, start, None).await }
+			},
+		))
+	}
+}
+#[async_trait(?Send)]
+impl File for HttpFile {
+	type Partition = HttpFile;
+	type Error = IoError;
+
+	async fn partitions(self) -> Result<Vec<Self::Partition>, Self::Error> {
+		Ok(vec![self])
+	}
+}
+#[async_trait(?Send)]
+impl Partition for HttpFile {
+	type Page = HttpPage;
+	type Error = IoError;
+
+	async fn pages(self) -> Result<Vec<Self::Page>, Self::Error> {
+		Ok(vec![HttpPage::new(self.url).await?])
+	}
+	fn name(&self) -> Option<String> {
+		Some(self.url.path().to_owned())
+	}
+}
+
+struct HttpPageInner {
+	url: Url,
+	len: u64,
+	ranges: bool,
+	body: Mutex<Option<Bytes>>,
+}
+impl HttpPageInner {
+	/// The whole file, downloaded the first time it's needed.
+	async fn body(&self) -> Result<Bytes, IoError> {
+		let mut body = self.body.lock().await;
+		if body.is_none() {
+			let len = usize::try_from(self.len).unwrap();
+			let body_ = get_range(RetryPolicy::default(), 0, len, |start| {
+				get(&self.url, start, None)
+			})
+			.await?;
+			*body = Some(body_);
+		}
+		Ok(body.clone().unwrap())
+	}
+}
+
+/// Request the bytes of `url` from `start` up to and including `end`, or to the end of the file if
+/// `end` is `None`, resolving to the body and the offset it begins at.
+async fn get(
+	url: &Url, start: u64, end: Option<u64>,
+) -> Result<(impl Stream<Item = Result<Bytes, IoError>> + Send, u64), IoError> {
+	let res = send(|| {
+		let request = CLIENT.get(url.clone());
+		match (start, end) {
+			(start, Some(end)) => request.header(header::RANGE, format!("bytes={}-{}", start, end)),
+			(0, None) => request,
+			(start, None) => request.header(header::RANGE, format!("bytes={}-", start)),
+		}
+	})
+	.await?;
+	// Servers that don't support ranges respond with the whole file
+	let start = if res.status() == StatusCode::PARTIAL_CONTENT {
+		start
+	} else {
+		0
+	};
+	Ok((res.bytes_stream().map_err(error), start))
+}
+
+/// A [`Page`] of an [`HttpFile`]. Writing isn't supported.
+///
+/// If the server doesn't advertise support for `Range` requests with an `Accept-Ranges: bytes`
+/// header, **the whole file is downloaded into memory** on the first read, and later reads are
+/// served from that. The same goes if the file's length couldn't be found with a `HEAD` request,
+/// in which case it's downloaded when the page is created.
+pub struct HttpPage {
+	inner: Arc<HttpPageInner>,
+}
+impl HttpPage {
+	async fn new(url: Url) -> Result<Self, IoError> {
+		let res = send(|| CLIENT.head(url.clone())).await.ok();
+		// Not `Response::content_length`, which is that of the (empty) body
+		let len = res.as_ref().and_then(|res| {
+			res.headers()
+				.get(header::CONTENT_LENGTH)?
+				.to_str()
+				.ok()?
+				.parse()
+				.ok()
+		});
+		let (len, ranges, body) = match (res, len) {
+			(Some(res), Some(len)) => {
+				let ranges = res
+					.headers()
+					.get_all(header::ACCEPT_RANGES)
+					.iter()
+					.filter_map(|units| units.to_str().ok())
+					.flat_map(|units| units.split(','))
+					.any(|unit| unit.trim().eq_ignore_ascii_case("bytes"));
+				(len, ranges, None)
+			}
+			_ => {
+				// Without a usable HEAD the length is only known by downloading the file, which
+				// is then kept to serve reads from
+				let body = get_resumable(RetryPolicy::default(), 0, None, |start| {
+					get(&url, start, None)
+				})
+				.try_fold(BytesMut::new(), |mut body, chunk| {
+					body.extend_from_slice(&chunk);
+					future::ok(body)
+				})
+				.await?
+				.freeze();
+				(u64::try_from(body.len()).unwrap(), false, Some(body))
+			}
+		};
+		let inner = Arc::new(HttpPageInner {
+			url,
+			len,
+			ranges,
+			body: Mutex::new(body),
+		});
+		Ok(Self { inner })
+	}
+}
+impl Page for HttpPage {
+	type Error = IoError;
+
+	fn len(&self) -> LocalBoxFuture<'static, Result<u64, Self::Error>> {
+		future::ready(Ok(self.inner.len)).boxed_local()
+	}
+	fn read(
+		&self, offset: u64, len: usize,
+	) -> LocalBoxFuture<'static, Result<SharedBuf, Self::Error>> {
+		let inner = self.inner.clone();
+		Box::pin(async move {
+			let len = len.min(usize::try_from(inner.len.saturating_sub(offset)).unwrap());
+			if len == 0 {
+				return Ok(SharedBuf::new());
+			}
+			if !inner.ranges {
+				let offset = usize::try_from(offset).unwrap();
+				let body = inner.body().await?;
+				return Ok(body.slice(offset..offset + len).into());
+			}
+			let end = offset + u64::try_from(len).unwrap() - 1;
+			let buf = get_range(RetryPolicy::default(), offset, len, |start| {
+				get(&inner.url, start, Some(end))
+			})
+			.await?;
+			Ok(buf.into())
+		})
+	}
+	fn write(
+		&self, _offset: u64, _buf: Box<[u8]>,
+	) -> LocalBoxFuture<'static, Result<(), Self::Error>> {
+		let err = io::Error::new(io::ErrorKind::Other, "HTTP files can't be written");
+		future::ready(Err(err.into())).boxed_local()
+	}
+}
@@ -0,0 +1,74 @@
+//! Harmonious distributed data processing & analysis in Rust.
+//!
+//! <p style="font-family: 'Fira Sans',sans-serif;padding:0.3em 0"><strong>
+//! <a href="https://crates.io/crates/amadeus">📦&nbsp;&nbsp;Crates.io</a>&nbsp;&nbsp;│&nbsp;&nbsp;<a href="https://github.com/constellation-rs/amadeus">📑&nbsp;&nbsp;GitHub</a>&nbsp;&nbsp;│&nbsp;&nbsp;<a href="https://constellation.zulipchat.com/#narrow/stream/213231-amadeus">💬&nbsp;&nbsp;Chat</a>
+//! </strong></p>
+//!
+//! This is a support crate of [Amadeus](https://github.com/constellation-rs/amadeus) and is not intended to be used directly. These types are re-exposed in [`amadeus::source`](https://docs.rs/amadeus/0.3/amadeus/source/index.html).
+
+#![doc(html_root_url = "https://docs.rs/amadeus-http/0.4.1")]
+#![warn(
+	// missing_copy_implementations,
+	// missing_debug_implementations,
+	// missing_docs,
+	trivial_numeric_casts,
+	unused_import_braces,
+	unused_qualifications,
+	unused_results,
+	unreachable_pub,
+	clippy::pedantic,
+)]
+#![allow(
+	clippy::module_name_repetitions,
+	clippy::must_use_candidate,
+	clippy::missing_errors_doc
+)]
+#![deny(unsafe_code)]
+
+mod file;
+
+use futures::FutureExt;
+use once_cell::sync::Lazy;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use std::{io, time::Duration};
+
+use amadeus_core::{file::RetryPolicy, util::IoError};
+
+pub use file::{HttpDirectory, HttpFile, HttpPage};
+
+static CLIENT: Lazy<Client> = Lazy::new(|| {
+	Client::builder()
+		.timeout(Duration::from_secs(120))
+		.build()
+		.unwrap()
+});
+
+/// Send the request built by `request`, retrying connection errors, timeouts,
+/// 5xx and 429 responses as per the default [`RetryPolicy`]. Other error
+/// statuses are returned as errors, with 404 as [`io::ErrorKind::NotFound`].
+async fn send<F>(request: F) -> Result<Response, IoError>
+where
+	F: Fn() -> RequestBuilder,
+{
+	RetryPolicy::default()
+		.retry(
+			|| request().send().map(|res| res?.error_for_status()),
+			|err| match err.status() {
+				Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+				None => err.is_connect() || err.is_timeout() || err.is_request(),
+			},
+		)
+		.await
+		.map_err(error)
+}
+
+fn error(err: reqwest::Error) -> IoError {
+	let kind = if err.status() == Some(StatusCode::NOT_FOUND) {
+		io::ErrorKind::NotFound
+	} else if err.is_timeout() {
+		io::ErrorKind::TimedOut
+	} else {
+		io::ErrorKind::Other
+	};
+	io::Error::new(kind, err).into()
+}
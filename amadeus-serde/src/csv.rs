@@ -2,7 +2,7 @@
 
 use csv::Error as InternalCsvError;
 use educe::Educe;
-use futures::{pin_mut, stream, AsyncReadExt, FutureExt, Stream, StreamExt};
+use futures::{stream, AsyncReadExt, FutureExt, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_closure::FnMutNamed;
 use std::{
@@ -10,7 +10,7 @@ use std::{
 };
 
 use amadeus_core::{
-	file::{File, Page, Partition}, into_par_stream::IntoDistributedStream, par_stream::DistributedStream, util::{DistParStream, ResultExpandIter}, Source
+	file::{decompress, Compression, File, Page, Partition}, into_par_stream::IntoDistributedStream, par_stream::DistributedStream, util::{DistParStream, ResultExpandIter}, Source
 };
 
 use super::{SerdeData, SerdeDeserializeGroup};
@@ -62,6 +62,7 @@ where
 	// quoting: bool,
 	// comment: Option<u8>,
 	partitions: Vec<File::Partition>,
+	compression: Option<Compression>,
 	marker: PhantomData<fn() -> Row>,
 }
 impl<F, Row> Csv<F, Row>
@@ -72,9 +73,16 @@ where
 	pub async fn new(file: F) -> Result<Self, <Self as Source>::Error> {
 		Ok(Self {
 			partitions: file.partitions().await.map_err(CsvError::File)?,
+			compression: None,
 			marker: PhantomData,
 		})
 	}
+	/// Decompress files with `compression`, rather than detecting it from their extension or
+	/// content. Use [`Compression::Uncompressed`] to read files as they are.
+	pub fn compression(mut self, compression: Compression) -> Self {
+		self.compression = Some(compression);
+		self
+	}
 	// pub fn open<Row>(files: Vec<PathBuf>) -> Csv<Row> {}
 	// pub fn create<Row>(files: Vec<PathBuf>) -> Csv<Row> {}
 }
@@ -86,14 +94,16 @@ type Output<P, Row, E> = std::pin::Pin<Box<dyn Stream<Item = Result<Row, Error<P
 type Output<P: Partition, Row, E> = impl Stream<Item = Result<Row, Error<P, E>>>;
 
 FnMutNamed! {
-	pub type Closure<P, Row, E> = |self|partition=> P| -> Output<P, Row, E>
+	pub type Closure<P, Row, E> = |self, compression: Option<Compression>|partition=> P| -> Output<P, Row, E>
 	where
 		P: Partition,
 		Row: SerdeData,
 		E: 'static
 	{
+		let compression = self.compression;
 		#[allow(clippy::let_and_return)]
 		let ret = async move {
+				let name = partition.name();
 				Ok(stream::iter(
 					partition
 						.pages()
@@ -101,11 +111,13 @@ FnMutNamed! {
 						.map_err(CsvError::Partition)?
 						.into_iter(),
 				)
-				.flat_map(|page| {
+				.flat_map(move |page| {
+					let name = name.clone();
 					async move {
 						let mut buf = Vec::with_capacity(10 * 1024 * 1024);
-						let reader = Page::reader(page);
-						pin_mut!(reader);
+						let mut reader = decompress(page, name.as_deref(), compression)
+							.await
+							.map_err(InternalCsvError::from)?;
 						let _ = reader
 							.read_to_end(&mut buf)
 							.await
@@ -162,7 +174,9 @@ where
 	}
 	#[allow(clippy::let_and_return)]
 	fn dist_stream(self) -> Self::DistStream {
-		self.partitions.into_dist_stream().flat_map(Closure::new())
+		self.partitions
+			.into_dist_stream()
+			.flat_map(Closure::new(self.compression))
 	}
 }
 
@@ -1,7 +1,7 @@
 #![allow(clippy::unsafe_derive_deserialize)] // https://github.com/rust-lang/rust-clippy/issues/5789
 
 use educe::Educe;
-use futures::{pin_mut, stream, AsyncReadExt, FutureExt, Stream, StreamExt};
+use futures::{stream, AsyncReadExt, FutureExt, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_closure::FnMutNamed;
 use serde_json::Error as InternalJsonError;
@@ -10,7 +10,7 @@ use std::{
 };
 
 use amadeus_core::{
-	file::{File, Page, Partition}, into_par_stream::IntoDistributedStream, par_stream::DistributedStream, util::{DistParStream, ResultExpandIter}, Source
+	file::{decompress, Compression, File, Page, Partition}, into_par_stream::IntoDistributedStream, par_stream::DistributedStream, util::{DistParStream, ResultExpandIter}, Source
 };
 
 use super::{SerdeData, SerdeDeserialize};
@@ -23,6 +23,7 @@ where
 	Row: SerdeData,
 {
 	partitions: Vec<File::Partition>,
+	compression: Option<Compression>,
 	marker: PhantomData<fn() -> Row>,
 }
 impl<F, Row> Json<F, Row>
@@ -33,9 +34,16 @@ where
 	pub async fn new(file: F) -> Result<Self, <Self as Source>::Error> {
 		Ok(Self {
 			partitions: file.partitions().await.map_err(JsonError::File)?,
+			compression: None,
 			marker: PhantomData,
 		})
 	}
+	/// Decompress files with `compression`, rather than detecting it from their extension or
+	/// content. Use [`Compression::Uncompressed`] to read files as they are.
+	pub fn compression(mut self, compression: Compression) -> Self {
+		self.compression = Some(compression);
+		self
+	}
 }
 
 type Error<P, E> = JsonError<E, <P as Partition>::Error, <<P as Partition>::Page as Page>::Error>;
@@ -45,14 +53,16 @@ type Output<P, Row, E> = std::pin::Pin<Box<dyn Stream<Item = Result<Row, Error<P
 type Output<P: Partition, Row, E> = impl Stream<Item = Result<Row, Error<P, E>>>;
 
 FnMutNamed! {
-	pub type Closure<P, Row, E> = |self|partition=> P| -> Output<P, Row, E>
+	pub type Closure<P, Row, E> = |self, compression: Option<Compression>|partition=> P| -> Output<P, Row, E>
 	where
 		P: Partition,
 		Row: SerdeData,
 		E: 'static
 	{
+		let compression = self.compression;
 		#[allow(clippy::let_and_return)]
 		let ret = async move {
+				let name = partition.name();
 				Ok(stream::iter(
 					partition
 						.pages()
@@ -60,14 +70,17 @@ FnMutNamed! {
 						.map_err(JsonError::Partition)?
 						.into_iter(),
 				)
-				.flat_map(|page| {
+				.flat_map(move |page| {
+					let name = name.clone();
 					async move {
 						let mut buf = Vec::with_capacity(10 * 1024 * 1024);
-						let reader = Page::reader(page);
-						pin_mut!(reader);
-						let buf = PassError::new(
-							reader.read_to_end(&mut buf).await.map(|_| Cursor::new(buf)),
-						);
+						let reader = decompress(page, name.as_deref(), compression).await;
+						let buf = PassError::new(match reader {
+							Ok(mut reader) => {
+								reader.read_to_end(&mut buf).await.map(|_| Cursor::new(buf))
+							}
+							Err(err) => Err(err),
+						});
 						Ok(stream::iter(
 							serde_json::Deserializer::from_reader(buf).into_iter().map(
 								|x: Result<SerdeDeserialize<Row>, InternalJsonError>| Ok(x?.0),
@@ -116,7 +129,9 @@ where
 	}
 	#[allow(clippy::let_and_return)]
 	fn dist_stream(self) -> Self::DistStream {
-		self.partitions.into_dist_stream().flat_map(Closure::new())
+		self.partitions
+			.into_dist_stream()
+			.flat_map(Closure::new(self.compression))
 	}
 }
 
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::{
+	convert::TryFrom, fmt, hash::{Hash, Hasher}, iter, marker::PhantomData, ops
+};
+use twox_hash::XxHash;
+
+use super::{f64_to_usize, usize_to_f64};
+use crate::traits::{Intersect, IntersectPlusUnionIsPlus, New, UnionAssign};
+
+/// An implementation of a [Bloom filter](https://en.wikipedia.org/wiki/Bloom_filter) data structure.
+///
+/// Membership queries never return a false negative, and return a false positive with at most the specified probability while the number of distinct elements pushed is within the specified capacity. Filters created with the same capacity and false positive rate can be unioned and intersected.
+///
+/// See [*Less Hashing, Same Performance: Building a Better Bloom Filter*](https://www.eecs.harvard.edu/~michaelm/postscripts/rsa2008.pdf) for background on the double hashing used to derive the `k` bit indices.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BloomFilter<V: ?Sized> {
+	bits: Box<[u64]>,
+	k: u32,
+	marker: PhantomData<fn(V)>,
+}
+
+impl<V: ?Sized> BloomFilter<V>
+where
+	V: Hash,
+{
+	/// Create an empty `BloomFilter` data structure sized to hold `capacity` distinct elements with the specified false positive rate.
+	pub fn new(capacity: usize, false_positive_rate: f64) -> Self {
+		assert!(0.0 < false_positive_rate && false_positive_rate < 1.0);
+		let capacity = usize_to_f64(capacity.max(1));
+		let ln2 = std::f64::consts::LN_2;
+		let m = (-capacity * false_positive_rate.ln() / (ln2 * ln2)).ceil();
+		let k = (m / capacity * ln2).round().max(1.0);
+		let words = (f64_to_usize(m) + 63) / 64;
+		Self {
+			bits: vec![0; words].into_boxed_slice(),
+			k: u32::try_from(f64_to_usize(k)).unwrap(),
+			marker: PhantomData,
+		}
+	}
+
+	/// Create an empty `BloomFilter` data structure, copying the capacity and false positive rate from `bloom`.
+	pub fn new_from(bloom: &Self) -> Self {
+		Self {
+			bits: vec![0; bloom.bits.len()].into_boxed_slice(),
+			k: bloom.k,
+			marker: PhantomData,
+		}
+	}
+
+	/// "Visit" an element.
+	#[inline]
+	pub fn push(&mut self, value: &V) {
+		for i in self.indices(value) {
+			self.bits[i / 64] |= 1 << (i % 64);
+		}
+	}
+
+	/// Whether `value` might have been pushed. False positives are possible, false negatives are not.
+	#[inline]
+	pub fn contains(&self, value: &V) -> bool {
+		self.indices(value)
+			.all(|i| self.bits[i / 64] & (1 << (i % 64)) != 0)
+	}
+
+	/// If no elements have been pushed.
+	pub fn is_empty(&self) -> bool {
+		self.bits.iter().all(|&word| word == 0)
+	}
+
+	/// Merge a `BloomFilter` into `self`, so that `self` contains every element of either.
+	pub fn union(&mut self, src: &Self) {
+		assert_eq!((self.bits.len(), self.k), (src.bits.len(), src.k));
+		for (word, src) in self.bits.iter_mut().zip(src.bits.iter()) {
+			*word |= src;
+		}
+	}
+
+	/// Intersect a `BloomFilter` into `self`, so that `self` contains only elements of both.
+	///
+	/// The result may have a higher false positive rate than a filter built from the intersection directly.
+	pub fn intersect(&mut self, src: &Self) {
+		assert_eq!((self.bits.len(), self.k), (src.bits.len(), src.k));
+		for (word, src) in self.bits.iter_mut().zip(src.bits.iter()) {
+			*word &= src;
+		}
+	}
+
+	/// Clears the `BloomFilter` data structure, as if it was new.
+	pub fn clear(&mut self) {
+		self.bits.iter_mut().for_each(|word| *word = 0);
+	}
+
+	fn indices(&self, value: &V) -> impl Iterator<Item = usize> {
+		let hash = |seed| {
+			let mut hasher = XxHash::with_seed(seed);
+			value.hash(&mut hasher);
+			hasher.finish()
+		};
+		let (a, b) = (hash(0), hash(1));
+		let m = self.bits.len() as u64 * 64;
+		(0..u64::from(self.k))
+			.map(move |i| usize::try_from(a.wrapping_add(i.wrapping_mul(b)) % m).unwrap())
+	}
+}
+
+impl<V: ?Sized> Clone for BloomFilter<V> {
+	fn clone(&self) -> Self {
+		Self {
+			bits: self.bits.clone(),
+			k: self.k,
+			marker: PhantomData,
+		}
+	}
+}
+impl<V: ?Sized> fmt::Debug for BloomFilter<V> {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		fmt.debug_struct("BloomFilter")
+			.field("bits", &(self.bits.len() * 64))
+			.field("k", &self.k)
+			.finish()
+	}
+}
+impl<V: ?Sized> New for BloomFilter<V>
+where
+	V: Hash,
+{
+	/// The capacity and false positive rate.
+	type Config = (usize, f64);
+	fn new(config: &Self::Config) -> Self {
+		Self::new(config.0, config.1)
+	}
+}
+impl<V: ?Sized> Intersect for BloomFilter<V>
+where
+	V: Hash,
+{
+	fn intersect<'a>(mut iter: impl Iterator<Item = &'a Self>) -> Option<Self>
+	where
+		Self: Sized + 'a,
+	{
+		let mut ret = iter.next()?.clone();
+		iter.for_each(|x| {
+			ret.intersect(x);
+		});
+		Some(ret)
+	}
+}
+impl<'a, V: ?Sized> UnionAssign<&'a BloomFilter<V>> for BloomFilter<V>
+where
+	V: Hash,
+{
+	fn union_assign(&mut self, rhs: &'a Self) {
+		self.union(rhs)
+	}
+}
+impl<'a, V: ?Sized> ops::AddAssign<&'a V> for BloomFilter<V>
+where
+	V: Hash,
+{
+	fn add_assign(&mut self, rhs: &'a V) {
+		self.push(rhs)
+	}
+}
+impl<'a, V: ?Sized> ops::AddAssign<&'a Self> for BloomFilter<V>
+where
+	V: Hash,
+{
+	fn add_assign(&mut self, rhs: &'a Self) {
+		self.union(rhs)
+	}
+}
+impl<V: ?Sized> IntersectPlusUnionIsPlus for BloomFilter<V> {
+	const VAL: bool = true;
+}
+impl<V: ?Sized> iter::Sum<BloomFilter<V>> for Option<BloomFilter<V>>
+where
+	V: Hash,
+{
+	fn sum<I>(mut iter: I) -> Self
+	where
+		I: Iterator<Item = BloomFilter<V>>,
+	{
+		let mut total = iter.next()?;
+		for bloom in iter {
+			total.union(&bloom);
+		}
+		Some(total)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn bloom_filter() {
+		let mut evens = BloomFilter::new(10_000, 0.01);
+		let mut odds = BloomFilter::new_from(&evens);
+		for i in 0..10_000_u32 {
+			if i % 2 == 0 {
+				evens.push(&i);
+			} else {
+				odds.push(&i);
+			}
+		}
+		assert!((0..10_000_u32).step_by(2).all(|i| evens.contains(&i)));
+		let false_positives = (1..10_000_u32)
+			.step_by(2)
+			.filter(|i| evens.contains(i))
+			.count();
+		assert!(false_positives < 100, "{}", false_positives);
+
+		let mut all = evens.clone();
+		all.union(&odds);
+		assert!((0..10_000_u32).all(|i| all.contains(&i)));
+		let none =
+			<BloomFilter<u32> as Intersect>::intersect(vec![&evens, &odds].into_iter()).unwrap();
+		let false_positives = (0..10_000_u32).filter(|i| none.contains(i)).count();
+		assert!(false_positives < 100, "{}", false_positives);
+		assert!(BloomFilter::<u32>::new_from(&none).is_empty());
+	}
+}
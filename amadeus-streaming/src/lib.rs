@@ -10,10 +10,12 @@
 //
 // This library is a work in progress. PRs are very welcome! Currently implemented algorithms include:
 //
+//  * Bloom filter
 //  * Count–min sketch
 //  * Top k (Count–min sketch plus a doubly linked hashmap to track heavy hitters / top k keys when ordered by aggregated value)
 //  * HyperLogLog
 //  * Reservoir sampling
+//  * t-digest
 //
 // A goal of this library is to enable composition of these algorithms; for example Top k + HyperLogLog to enable an approximate version of something akin to `SELECT key FROM table GROUP BY key ORDER BY COUNT(DISTINCT value) DESC LIMIT k`.
 //
@@ -56,17 +58,21 @@
 	clippy::unused_self
 )]
 
+mod bloom;
 mod count_min;
 mod distinct;
 mod linked_list;
 mod ordered_linked_list;
+mod quantile;
 mod sample;
 mod sort;
 mod top;
 mod traits;
 
+pub use bloom::*;
 pub use count_min::*;
 pub use distinct::*;
+pub use quantile::*;
 pub use sample::*;
 pub use sort::*;
 pub use top::*;
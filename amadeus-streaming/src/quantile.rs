@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, cmp::Ordering, f64::consts::PI, ops};
+
+use crate::traits::{New, UnionAssign};
+
+/// A [t-digest](https://github.com/tdunning/t-digest/blob/master/docs/t-digest-paper/histo.pdf): a mergeable sketch for estimating quantiles of a stream of `f64`s. It uses `O(compression)` space.
+///
+/// Values are clustered into centroids, with the size of each centroid limited such that centroids near the tails hold few values. This gives accurate estimates of extreme quantiles like the 99th or 99.9th percentile, with an error that is typically well under 1% elsewhere.
+///
+/// This is the merging variant: values are buffered and periodically merged into the centroids in a single sorted pass, which also makes merging two digests cheap.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct TDigest {
+	compression: f64,
+	centroids: Vec<Centroid>,
+	buffer: Vec<f64>,
+	count: f64,
+	min: f64,
+	max: f64,
+}
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize, Debug)]
+struct Centroid {
+	mean: f64,
+	weight: f64,
+}
+
+impl TDigest {
+	/// Create an empty `TDigest` with the specified `compression`. Higher is more accurate but uses more space; 100 is typical.
+	pub fn new(compression: f64) -> Self {
+		assert!(compression >= 1.0);
+		Self {
+			compression,
+			centroids: Vec::new(),
+			buffer: Vec::new(),
+			count: 0.0,
+			min: f64::INFINITY,
+			max: f64::NEG_INFINITY,
+		}
+	}
+
+	/// "Visit" a value. NaNs are ignored.
+	pub fn push(&mut self, value: f64) {
+		if value.is_nan() {
+			return;
+		}
+		self.buffer.push(value);
+		self.count += 1.0;
+		self.min = self.min.min(value);
+		self.max = self.max.max(value);
+		if self.buffer.len() >= self.buffer_capacity() {
+			self.flush();
+		}
+	}
+
+	/// Estimate the value at quantile `q`, where `0 <= q <= 1`. Returns `None` if no values have been pushed.
+	pub fn quantile(&self, q: f64) -> Option<f64> {
+		assert!((0.0..=1.0).contains(&q), "quantile must be between 0 and 1");
+		if self.is_empty() {
+			return None;
+		}
+		if q == 0.0 {
+			return Some(self.min);
+		}
+		if q == 1.0 {
+			return Some(self.max);
+		}
+		let centroids = self.centroids();
+		let target = q * self.count;
+		// Each centroid is treated as centred at its cumulative midpoint, and values are interpolated linearly between neighbouring centroids, or the min/max at either end.
+		let mut prev = (0.0, self.min);
+		let mut cumulative = 0.0;
+		for centroid in centroids.iter() {
+			let mid = cumulative + centroid.weight / 2.0;
+			if target < mid {
+				return Some(interpolate(prev, (mid, centroid.mean), target));
+			}
+			cumulative += centroid.weight;
+			prev = (mid, centroid.mean);
+		}
+		Some(interpolate(prev, (self.count, self.max), target))
+	}
+
+	/// Estimate the fraction of values less than or equal to `value`. Returns `None` if no values have been pushed.
+	pub fn cdf(&self, value: f64) -> Option<f64> {
+		if self.is_empty() {
+			return None;
+		}
+		if value < self.min {
+			return Some(0.0);
+		}
+		if value >= self.max {
+			return Some(1.0);
+		}
+		let centroids = self.centroids();
+		let mut prev = (self.min, 0.0);
+		let mut cumulative = 0.0;
+		for centroid in centroids.iter() {
+			let mid = cumulative + centroid.weight / 2.0;
+			if value < centroid.mean {
+				return Some(interpolate(prev, (centroid.mean, mid), value) / self.count);
+			}
+			cumulative += centroid.weight;
+			prev = (centroid.mean, mid);
+		}
+		Some(interpolate(prev, (self.max, self.count), value) / self.count)
+	}
+
+	/// The number of values pushed.
+	pub fn count(&self) -> f64 {
+		self.count
+	}
+
+	/// Returns true if no values have been pushed.
+	pub fn is_empty(&self) -> bool {
+		self.count == 0.0
+	}
+
+	/// The smallest value pushed, or `None` if no values have been pushed.
+	pub fn min(&self) -> Option<f64> {
+		if !self.is_empty() {
+			Some(self.min)
+		} else {
+			None
+		}
+	}
+
+	/// The largest value pushed, or `None` if no values have been pushed.
+	pub fn max(&self) -> Option<f64> {
+		if !self.is_empty() {
+			Some(self.max)
+		} else {
+			None
+		}
+	}
+
+	/// Union another `TDigest` into this one. This is equivalent to having pushed all of its values into this one.
+	pub fn union(&mut self, src: &Self) {
+		if src.is_empty() {
+			return;
+		}
+		let centroids = self
+			.centroids
+			.drain(..)
+			.chain(self.buffer.drain(..).map(Centroid::from))
+			.chain(src.centroids.iter().copied())
+			.chain(src.buffer.iter().copied().map(Centroid::from))
+			.collect();
+		self.count += src.count;
+		self.min = self.min.min(src.min);
+		self.max = self.max.max(src.max);
+		self.centroids = compress(centroids, self.count, self.compression);
+	}
+
+	fn buffer_capacity(&self) -> usize {
+		// Amortises the cost of sorting and compressing
+		crate::f64_to_usize((self.compression * 5.0).ceil())
+	}
+
+	fn flush(&mut self) {
+		if self.buffer.is_empty() {
+			return;
+		}
+		let centroids = self
+			.centroids
+			.drain(..)
+			.chain(self.buffer.drain(..).map(Centroid::from))
+			.collect();
+		self.centroids = compress(centroids, self.count, self.compression);
+	}
+
+	fn centroids(&self) -> Cow<'_, [Centroid]> {
+		if self.buffer.is_empty() {
+			Cow::Borrowed(&self.centroids)
+		} else {
+			let centroids = self
+				.centroids
+				.iter()
+				.copied()
+				.chain(self.buffer.iter().copied().map(Centroid::from))
+				.collect();
+			Cow::Owned(compress(centroids, self.count, self.compression))
+		}
+	}
+}
+
+impl From<f64> for Centroid {
+	fn from(mean: f64) -> Self {
+		Self { mean, weight: 1.0 }
+	}
+}
+
+/// Merge adjacent centroids wherever the result stays within the size bound given by the `k1` scale function, `k(q) = compression / 2π * asin(2q - 1)`.
+fn compress(mut centroids: Vec<Centroid>, count: f64, compression: f64) -> Vec<Centroid> {
+	centroids.sort_by(|a, b| a.mean.partial_cmp(&b.mean).unwrap_or(Ordering::Equal));
+	let k = |q: f64| compression / (2.0 * PI) * (2.0 * q - 1.0).asin();
+	let k_inverse = |k: f64| {
+		if k >= compression / 4.0 {
+			1.0
+		} else {
+			((k * 2.0 * PI / compression).sin() + 1.0) / 2.0
+		}
+	};
+	let mut ret = Vec::with_capacity(crate::f64_to_usize(compression.ceil()));
+	let mut centroids = centroids.into_iter();
+	let mut current = if let Some(centroid) = centroids.next() {
+		centroid
+	} else {
+		return ret;
+	};
+	let mut weight_before = 0.0;
+	let mut q_limit = k_inverse(k(0.0) + 1.0);
+	for next in centroids {
+		let q = (weight_before + current.weight + next.weight) / count;
+		if q <= q_limit {
+			current.weight += next.weight;
+			current.mean += (next.mean - current.mean) * next.weight / current.weight;
+		} else {
+			weight_before += current.weight;
+			ret.push(current);
+			q_limit = k_inverse(k(weight_before / count) + 1.0);
+			current = next;
+		}
+	}
+	ret.push(current);
+	ret
+}
+
+fn interpolate((x0, y0): (f64, f64), (x1, y1): (f64, f64), x: f64) -> f64 {
+	if x1 <= x0 {
+		return y1;
+	}
+	y0 + (x - x0) / (x1 - x0) * (y1 - y0)
+}
+
+impl New for TDigest {
+	type Config = f64;
+	fn new(config: &Self::Config) -> Self {
+		Self::new(*config)
+	}
+}
+impl<'a> UnionAssign<&'a TDigest> for TDigest {
+	fn union_assign(&mut self, rhs: &'a Self) {
+		self.union(rhs)
+	}
+}
+impl UnionAssign for TDigest {
+	fn union_assign(&mut self, rhs: Self) {
+		self.union(&rhs)
+	}
+}
+impl<'a> ops::AddAssign<&'a f64> for TDigest {
+	fn add_assign(&mut self, rhs: &'a f64) {
+		self.push(*rhs)
+	}
+}
+impl<'a> ops::AddAssign<&'a Self> for TDigest {
+	fn add_assign(&mut self, rhs: &'a Self) {
+		self.union(rhs)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use rand::{self, seq::SliceRandom, SeedableRng};
+
+	#[test]
+	fn uniform() {
+		let mut rng =
+			rand::rngs::SmallRng::from_seed([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+		let mut values = (0..100_000).map(f64::from).collect::<Vec<_>>();
+		values.shuffle(&mut rng);
+		let mut digest = TDigest::new(100.0);
+		for &value in &values {
+			digest.push(value);
+		}
+		assert_eq!(digest.count(), 100_000.0);
+		assert_eq!(digest.quantile(0.0), Some(0.0));
+		assert_eq!(digest.quantile(1.0), Some(99_999.0));
+		for &q in &[0.001, 0.01, 0.1, 0.5, 0.9, 0.99, 0.999] {
+			let estimate = digest.quantile(q).unwrap();
+			let error = (estimate - q * 100_000.0).abs() / 100_000.0;
+			assert!(error < 0.005, "q: {}, estimate: {}", q, estimate);
+			let cdf = digest.cdf(q * 100_000.0).unwrap();
+			assert!((cdf - q).abs() < 0.005, "q: {}, cdf: {}", q, cdf);
+		}
+		assert!(digest.centroids().len() <= 200);
+	}
+
+	#[test]
+	fn union() {
+		let mut rng =
+			rand::rngs::SmallRng::from_seed([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+		let mut values = (0..100_000).map(f64::from).collect::<Vec<_>>();
+		values.shuffle(&mut rng);
+		let mut digests = values
+			.chunks(7_777)
+			.map(|chunk| {
+				let mut digest = TDigest::new(100.0);
+				for &value in chunk {
+					digest.push(value);
+				}
+				digest
+			})
+			.collect::<Vec<_>>();
+		let mut digest = digests.pop().unwrap();
+		for other in &digests {
+			digest.union_assign(other);
+		}
+		assert_eq!(digest.count(), 100_000.0);
+		for &q in &[0.01, 0.5, 0.99] {
+			let estimate = digest.quantile(q).unwrap();
+			let error = (estimate - q * 100_000.0).abs() / 100_000.0;
+			assert!(error < 0.005, "q: {}, estimate: {}", q, estimate);
+		}
+	}
+
+	#[test]
+	fn empty() {
+		let mut digest = TDigest::new(100.0);
+		assert_eq!(digest.quantile(0.5), None);
+		digest.push(f64::NAN);
+		assert!(digest.is_empty());
+		digest.push(1.0);
+		assert_eq!(digest.quantile(0.5), Some(1.0));
+		assert_eq!(digest.cdf(1.0), Some(1.0));
+	}
+}
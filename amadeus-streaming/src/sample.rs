@@ -1,6 +1,9 @@
 use rand::{self, Rng, SeedableRng};
 use serde::{de::Deserializer, ser::Serializer, Deserialize, Serialize};
-use std::{convert::TryFrom, fmt, iter, ops, vec};
+use std::{
+	borrow::Borrow, collections::{hash_map, HashMap}, convert::TryFrom, fmt, hash::{Hash, Hasher}, iter, ops, vec
+};
+use twox_hash::XxHash;
 
 /// Given population and sample sizes, returns true if this element is in the sample. Without replacement.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -217,6 +220,335 @@ impl<T> ops::AddAssign for SampleUnstable<T> {
 	}
 }
 
+/// Given a fraction and a seed, returns true if this element is in the sample.
+///
+/// Elements are chosen by a seeded hash, so the same elements are chosen
+/// regardless of order or how the population is divided, and equal elements
+/// are either all chosen or all not.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct SampleFraction {
+	fraction: f64,
+	seed: u64,
+}
+impl SampleFraction {
+	/// Create a `SampleFraction` that will choose each element with probability `fraction`.
+	pub fn new(fraction: f64, seed: u64) -> Self {
+		assert!(
+			(0.0..=1.0).contains(&fraction),
+			"fraction must be between 0 and 1"
+		);
+		Self { fraction, seed }
+	}
+
+	/// Returns whether or not this value is in the sample
+	pub fn sample<T: Hash + ?Sized>(&self, t: &T) -> bool {
+		unit_interval(seeded_hash(t, self.seed)) < self.fraction
+	}
+}
+
+/// The items with the smallest keys seen so far, up to a capacity. Items are
+/// buffered until there are twice as many as needed, then sorted and truncated,
+/// so that pushing is amortised `O(1)`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Reservoir<K, T> {
+	items: Vec<(K, T)>,
+	threshold: Option<K>,
+	n: usize,
+}
+impl<K, T> Reservoir<K, T>
+where
+	K: PartialOrd + Copy,
+{
+	fn new(n: usize) -> Self {
+		Self {
+			items: Vec::new(),
+			threshold: None,
+			n,
+		}
+	}
+	fn push(&mut self, key: K, t: T) {
+		if self.n == 0 || self.threshold.map_or(false, |threshold| key >= threshold) {
+			return;
+		}
+		self.items.push((key, t));
+		if self.items.len() >= self.n * 2 {
+			self.compact();
+		}
+	}
+	fn compact(&mut self) {
+		// Keys are never NaN
+		self.items
+			.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+		self.items.truncate(self.n);
+		if self.items.len() == self.n {
+			self.threshold = Some(self.items[self.n - 1].0);
+		}
+	}
+	fn merge(&mut self, other: Self) {
+		assert_eq!(self.n, other.n);
+		for (key, t) in other.items {
+			self.push(key, t);
+		}
+	}
+	fn into_iter(mut self) -> impl Iterator<Item = T> {
+		self.compact();
+		self.items.into_iter().map(|(_, t)| t)
+	}
+}
+
+/// Map 53 bits of a hash uniformly onto `[0,1)`.
+#[allow(clippy::cast_precision_loss)]
+fn unit_interval(hash: u64) -> f64 {
+	(hash >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+/// The hash of `t` with the given seed.
+fn seeded_hash<T: Hash + ?Sized>(t: &T, seed: u64) -> u64 {
+	let mut hasher = XxHash::with_seed(seed);
+	t.hash(&mut hasher);
+	hasher.finish()
+}
+
+/// A deterministic sample without replacement, by retaining the items with the
+/// `n` smallest seeded hashes of their positions.
+///
+/// Each item is pushed along with its position in the population, for example
+/// its index, which must be unique. Unlike [`SampleUnstable`], the result only
+/// depends on the seed and the positions and items pushed, not on the order
+/// they're pushed in or how they're divided among instances that are then
+/// summed. Items are returned in order of the hash of their position.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sample<T> {
+	reservoir: Reservoir<u64, T>,
+	seed: u64,
+}
+impl<T> Sample<T> {
+	/// Create a `Sample` that will provide a sample of size `samples`, chosen by `seed`.
+	pub fn new(samples: usize, seed: u64) -> Self {
+		Self {
+			reservoir: Reservoir::new(samples),
+			seed,
+		}
+	}
+
+	/// "Visit" this element, which is at `position` in the population.
+	pub fn push<P: Hash + ?Sized>(&mut self, position: &P, t: T) {
+		let key = seeded_hash(position, self.seed);
+		self.reservoir.push(key, t);
+	}
+}
+impl<T> IntoIterator for Sample<T> {
+	type Item = T;
+	type IntoIter = vec::IntoIter<T>;
+
+	fn into_iter(self) -> vec::IntoIter<T> {
+		self.reservoir.into_iter().collect::<Vec<_>>().into_iter()
+	}
+}
+impl<T> iter::Sum<Sample<T>> for Option<Sample<T>> {
+	fn sum<I>(mut iter: I) -> Self
+	where
+		I: Iterator<Item = Sample<T>>,
+	{
+		let mut total = iter.next()?;
+		for sample in iter {
+			total += sample;
+		}
+		Some(total)
+	}
+}
+impl<T> ops::Add for Sample<T> {
+	type Output = Self;
+
+	fn add(mut self, other: Self) -> Self {
+		self += other;
+		self
+	}
+}
+impl<T> ops::AddAssign for Sample<T> {
+	fn add_assign(&mut self, other: Self) {
+		assert_eq!(self.seed, other.seed);
+		self.reservoir.merge(other.reservoir);
+	}
+}
+
+/// A deterministic weighted sample without replacement, using the A-Res
+/// algorithm of [*Weighted random sampling with a reservoir*](https://doi.org/10.1016/j.ipl.2005.11.003).
+///
+/// Each item is given the key `u^(1/weight)`, where `u` is uniform in `(0,1)`
+/// and derived from a seeded hash of the item's position, and the `n` items
+/// with the largest keys are retained. As with [`Sample`], positions must be
+/// unique, and the result only depends on the seed and the positions and
+/// items pushed. Items with a weight that isn't positive are
+/// never sampled. Items are returned in descending order of their key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SampleWeighted<T> {
+	reservoir: Reservoir<f64, T>,
+	seed: u64,
+}
+impl<T> SampleWeighted<T> {
+	/// Create a `SampleWeighted` that will provide a sample of size `samples`, chosen by `seed`.
+	pub fn new(samples: usize, seed: u64) -> Self {
+		Self {
+			reservoir: Reservoir::new(samples),
+			seed,
+		}
+	}
+
+	/// "Visit" this element, which is at `position` in the population, with the
+	/// given weight.
+	#[allow(clippy::cast_precision_loss)]
+	pub fn push<P: Hash + ?Sized>(&mut self, position: &P, t: T, weight: f64) {
+		if weight.is_nan() || weight <= 0.0 {
+			return;
+		}
+		// Offset by half a step so u is never 0
+		let u = unit_interval(seeded_hash(position, self.seed)) + 0.5 / (1_u64 << 53) as f64;
+		// ln(u^(1/weight)) is monotonic with u^(1/weight) but doesn't underflow;
+		// negated as the reservoir retains the smallest keys
+		let key = -u.ln() / weight;
+		self.reservoir.push(key, t);
+	}
+}
+impl<T> IntoIterator for SampleWeighted<T> {
+	type Item = T;
+	type IntoIter = vec::IntoIter<T>;
+
+	fn into_iter(self) -> vec::IntoIter<T> {
+		self.reservoir.into_iter().collect::<Vec<_>>().into_iter()
+	}
+}
+impl<T> iter::Sum<SampleWeighted<T>> for Option<SampleWeighted<T>> {
+	fn sum<I>(mut iter: I) -> Self
+	where
+		I: Iterator<Item = SampleWeighted<T>>,
+	{
+		let mut total = iter.next()?;
+		for sample in iter {
+			total += sample;
+		}
+		Some(total)
+	}
+}
+impl<T> ops::Add for SampleWeighted<T> {
+	type Output = Self;
+
+	fn add(mut self, other: Self) -> Self {
+		self += other;
+		self
+	}
+}
+impl<T> ops::AddAssign for SampleWeighted<T> {
+	fn add_assign(&mut self, other: Self) {
+		assert_eq!(self.seed, other.seed);
+		self.reservoir.merge(other.reservoir);
+	}
+}
+
+/// A deterministic [`Sample`] of size `n` of each stratum, as identified by a key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(
+	serialize = "K: Serialize + Hash + Eq, T: Serialize",
+	deserialize = "K: Deserialize<'de> + Hash + Eq, T: Deserialize<'de>"
+))]
+pub struct SampleStratified<K, T> {
+	strata: HashMap<K, Sample<T>>,
+	samples: usize,
+	seed: u64,
+}
+impl<K, T> SampleStratified<K, T>
+where
+	K: Hash + Eq,
+{
+	/// Create a `SampleStratified` that will provide a sample of size `samples` of each stratum, chosen by `seed`.
+	pub fn new(samples: usize, seed: u64) -> Self {
+		Self {
+			strata: HashMap::new(),
+			samples,
+			seed,
+		}
+	}
+
+	/// "Visit" this element, which is at `position` in the population and
+	/// belongs to the stratum `key`.
+	pub fn push<P: Hash + ?Sized>(&mut self, key: K, position: &P, t: T) {
+		let (samples, seed) = (self.samples, self.seed);
+		self.strata
+			.entry(key)
+			.or_insert_with(|| Sample::new(samples, seed))
+			.push(position, t);
+	}
+
+	/// The number of strata seen.
+	pub fn len(&self) -> usize {
+		self.strata.len()
+	}
+
+	/// If `.len() == 0`
+	pub fn is_empty(&self) -> bool {
+		self.strata.is_empty()
+	}
+
+	/// The sample of the stratum `key`, if any of its elements have been visited.
+	pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&Sample<T>>
+	where
+		K: Borrow<Q>,
+		Q: Hash + Eq,
+	{
+		self.strata.get(key)
+	}
+}
+impl<K, T> IntoIterator for SampleStratified<K, T> {
+	type Item = (K, Sample<T>);
+	type IntoIter = hash_map::IntoIter<K, Sample<T>>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.strata.into_iter()
+	}
+}
+impl<K, T> iter::Sum<SampleStratified<K, T>> for Option<SampleStratified<K, T>>
+where
+	K: Hash + Eq,
+{
+	fn sum<I>(mut iter: I) -> Self
+	where
+		I: Iterator<Item = SampleStratified<K, T>>,
+	{
+		let mut total = iter.next()?;
+		for sample in iter {
+			total += sample;
+		}
+		Some(total)
+	}
+}
+impl<K, T> ops::Add for SampleStratified<K, T>
+where
+	K: Hash + Eq,
+{
+	type Output = Self;
+
+	fn add(mut self, other: Self) -> Self {
+		self += other;
+		self
+	}
+}
+impl<K, T> ops::AddAssign for SampleStratified<K, T>
+where
+	K: Hash + Eq,
+{
+	fn add_assign(&mut self, other: Self) {
+		assert_eq!((self.samples, self.seed), (other.samples, other.seed));
+		for (key, sample) in other.strata {
+			match self.strata.entry(key) {
+				hash_map::Entry::Occupied(mut entry) => *entry.get_mut() += sample,
+				hash_map::Entry::Vacant(entry) => {
+					let _ = entry.insert(sample);
+				}
+			}
+		}
+	}
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -256,4 +588,36 @@ mod test {
 		}
 		println!("{:#?}", hash);
 	}
+
+	#[test]
+	fn sample_deterministic() {
+		let mut whole = Sample::new(10, 42);
+		let mut parts = (0..4).map(|_| Sample::new(10, 42)).collect::<Vec<_>>();
+		for i in 0..1000 {
+			whole.push(&i, i);
+			parts[i % 4].push(&i, i);
+		}
+		let parts = parts.into_iter().sum::<Option<_>>().unwrap();
+		let whole = whole.into_iter().collect::<Vec<_>>();
+		assert_eq!(whole.len(), 10);
+		assert_eq!(whole, parts.into_iter().collect::<Vec<_>>());
+
+		let mut weighted = SampleWeighted::new(10, 42);
+		for i in 0..1000 {
+			weighted.push(&i, i, if i < 20 { 1000.0 } else { 1.0 });
+		}
+		let heavy = weighted.into_iter().filter(|&i| i < 20).count();
+		assert!(heavy >= 8, "{}", heavy);
+	}
+
+	#[test]
+	fn sample_duplicates() {
+		// Equal items at different positions are drawn independently
+		let mut sample = Sample::new(100, 42);
+		for i in 0..2000 {
+			sample.push(&i, if i < 1000 { 0 } else { i });
+		}
+		let zeros = sample.into_iter().filter(|&i| i == 0).count();
+		assert!(zeros > 25 && zeros < 75, "{}", zeros);
+	}
 }
@@ -0,0 +1,399 @@
+//! A sink that computes many aggregates over the columns of [`Group`] rows in a
+//! single pass.
+
+use amadeus_core::{
+	par_sink::{
+		CoMoments, DistributedSink, Final, FolderSync, FolderSyncReducer, Inter, ParallelSink
+	}, par_stream::Identity, util::u64_to_f64
+};
+use amadeus_streaming::HyperLogLog;
+use fxhash::FxBuildHasher;
+use linked_hash_map::LinkedHashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+	cmp::Ordering, fmt::{self, Display}, iter::FromIterator, marker::PhantomData, mem, sync::Arc
+};
+
+use super::{AmadeusOrd, Group, Value};
+
+/// The error rate of the HyperLogLog used to estimate distinct counts.
+const DISTINCT_ERROR_RATE: f64 = 0.01;
+
+/// An aggregate to compute over a column.
+///
+/// Null values, and rows missing the column, are ignored by every aggregate.
+/// Non-numeric values are ignored by the numeric aggregates [`Sum`](Self::Sum),
+/// [`Mean`](Self::Mean) and [`StdDev`](Self::StdDev). [`Min`](Self::Min) and
+/// [`Max`](Self::Max) compare numbers of different types by value, and order
+/// other values of different types by the name of their type, after numbers.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+pub enum Aggregate {
+	/// The number of non-null values, as a `u64`.
+	Count,
+	/// The sum of the numeric values, as an `f64`.
+	Sum,
+	/// The mean of the numeric values, as an `Option<f64>`.
+	Mean,
+	/// The population standard deviation of the numeric values, as an `Option<f64>`.
+	StdDev,
+	/// The smallest value, as ordered by [`AmadeusOrd`], as an `Option<Value>`.
+	Min,
+	/// The largest value, as ordered by [`AmadeusOrd`], as an `Option<Value>`.
+	Max,
+	/// An estimate of the number of distinct values, as an `f64`.
+	Distinct,
+}
+
+impl Display for Aggregate {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			Self::Count => "count",
+			Self::Sum => "sum",
+			Self::Mean => "mean",
+			Self::StdDev => "stddev",
+			Self::Min => "min",
+			Self::Max => "max",
+			Self::Distinct => "distinct",
+		})
+	}
+}
+
+/// Computes a runtime list of [`Aggregate`]s over the columns of a stream of
+/// [`Group`]s, or of [`Value`]s holding [`Group`]s or maps keyed by strings
+/// (such as JSON objects), in a single pass.
+///
+/// The result is a [`Group`] with a field for each aggregate, in the order they
+/// were added, named like `mean(column)`.
+///
+/// ```ignore
+/// let stats = stream
+/// 	.pipe(
+/// 		pool,
+/// 		Aggregations::new()
+/// 			.aggregate("time_taken", Aggregate::Mean)
+/// 			.aggregate("time_taken", Aggregate::Max)
+/// 			.aggregate("host", Aggregate::Distinct),
+/// 	)
+/// 	.await;
+/// let mean = stats.get("mean(time_taken)");
+/// ```
+#[derive(Clone, Default, Debug)]
+#[must_use]
+pub struct Aggregations {
+	aggregations: Vec<(String, Aggregate)>,
+}
+
+impl Aggregations {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	/// Add an aggregate over the column `column`.
+	///
+	/// # Panics
+	///
+	/// Panics if this aggregate over this column has already been added, as
+	/// their results would share a field name.
+	pub fn aggregate(mut self, column: impl Into<String>, aggregate: Aggregate) -> Self {
+		let column = column.into();
+		assert!(
+			!self.aggregations.contains(&(column.clone(), aggregate)),
+			"{}({}) has already been added",
+			aggregate,
+			column
+		);
+		self.aggregations.push((column, aggregate));
+		self
+	}
+}
+
+impl<S: Into<String>> FromIterator<(S, Aggregate)> for Aggregations {
+	fn from_iter<I>(iter: I) -> Self
+	where
+		I: IntoIterator<Item = (S, Aggregate)>,
+	{
+		iter.into_iter()
+			.fold(Self::new(), |self_, (column, aggregate)| {
+				self_.aggregate(column, aggregate)
+			})
+	}
+}
+
+macro_rules! impl_sink {
+	($($item:ty)*) => ($(
+		impl ParallelSink<$item> for Aggregations {
+			type Done = Group;
+			type Pipe = Identity;
+			type ReduceA = FolderSyncReducer<$item, AggregationsFolder<StepA>, Inter>;
+			type ReduceC = FolderSyncReducer<Vec<AggregateState>, AggregationsFolder<StepB>, Final>;
+
+			fn reducers(self) -> (Self::Pipe, Self::ReduceA, Self::ReduceC) {
+				let folder = AggregationsFolder::new(self.aggregations);
+				(
+					Identity,
+					FolderSyncReducer::new(folder.clone().step()),
+					FolderSyncReducer::new(folder.step()),
+				)
+			}
+		}
+		impl DistributedSink<$item> for Aggregations {
+			type Done = Group;
+			type Pipe = Identity;
+			type ReduceA = FolderSyncReducer<$item, AggregationsFolder<StepA>, Inter>;
+			type ReduceB = FolderSyncReducer<Vec<AggregateState>, AggregationsFolder<StepB>, Inter>;
+			type ReduceC = FolderSyncReducer<Vec<AggregateState>, AggregationsFolder<StepB>, Final>;
+
+			fn reducers(self) -> (Self::Pipe, Self::ReduceA, Self::ReduceB, Self::ReduceC) {
+				let folder = AggregationsFolder::new(self.aggregations);
+				(
+					Identity,
+					FolderSyncReducer::new(folder.clone().step()),
+					FolderSyncReducer::new(folder.clone().step()),
+					FolderSyncReducer::new(folder.step()),
+				)
+			}
+		}
+	)*);
+}
+impl_sink!(Group Value);
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct AggregationsFolder<Step> {
+	aggregations: Vec<(String, Aggregate)>,
+	marker: PhantomData<fn() -> Step>,
+}
+impl<Step> Clone for AggregationsFolder<Step> {
+	fn clone(&self) -> Self {
+		Self {
+			aggregations: self.aggregations.clone(),
+			marker: PhantomData,
+		}
+	}
+}
+impl AggregationsFolder<()> {
+	fn new(aggregations: Vec<(String, Aggregate)>) -> Self {
+		Self {
+			aggregations,
+			marker: PhantomData,
+		}
+	}
+}
+impl<Step> AggregationsFolder<Step> {
+	fn step<Step2>(self) -> AggregationsFolder<Step2> {
+		AggregationsFolder {
+			aggregations: self.aggregations,
+			marker: PhantomData,
+		}
+	}
+	fn states(&self) -> Vec<AggregateState> {
+		self.aggregations
+			.iter()
+			.map(|&(_, aggregate)| AggregateState::new(aggregate))
+			.collect()
+	}
+	fn push_row<'a>(&self, state: &mut [AggregateState], get: impl Fn(&str) -> Option<&'a Value>) {
+		for ((column, _), state) in self.aggregations.iter().zip(state) {
+			match get(column) {
+				None | Some(Value::Option(None)) => (),
+				Some(Value::Option(Some(value))) => state.push(&value.clone().into()),
+				Some(value) => state.push(value),
+			}
+		}
+	}
+}
+
+pub struct StepA;
+pub struct StepB;
+
+impl FolderSync<Group> for AggregationsFolder<StepA> {
+	type State = Vec<AggregateState>;
+	type Done = Vec<AggregateState>;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		self.states()
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: Group) {
+		self.push_row(state, |column| item.get(column))
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+impl FolderSync<Value> for AggregationsFolder<StepA> {
+	type State = Vec<AggregateState>;
+	type Done = Vec<AggregateState>;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		self.states()
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: Value) {
+		match &item {
+			Value::Group(group) => self.push_row(state, |column| group.get(column)),
+			Value::Map(map) => {
+				self.push_row(state, |column| map.get(&Value::String(column.to_owned())))
+			}
+			_ => self.push_row(state, |_| None),
+		}
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+impl FolderSync<Vec<AggregateState>> for AggregationsFolder<StepB> {
+	type State = Vec<AggregateState>;
+	type Done = Group;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		self.states()
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: Vec<AggregateState>) {
+		for (state, item) in state.iter_mut().zip(item) {
+			state.merge(item);
+		}
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		let field_names = self
+			.aggregations
+			.iter()
+			.enumerate()
+			.map(|(i, (column, aggregate))| (format!("{}({})", aggregate, column), i))
+			.collect::<LinkedHashMap<_, _, FxBuildHasher>>();
+		let fields = state.into_iter().map(AggregateState::done).collect();
+		Group::new(fields, Some(Arc::new(field_names)))
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum AggregateState {
+	Count(u64),
+	Sum(f64),
+	Mean(CoMoments),
+	StdDev(CoMoments),
+	Min(Option<Value>),
+	Max(Option<Value>),
+	Distinct(HyperLogLog<Value>),
+}
+
+impl AggregateState {
+	fn new(aggregate: Aggregate) -> Self {
+		match aggregate {
+			Aggregate::Count => Self::Count(0),
+			Aggregate::Sum => Self::Sum(0.0),
+			Aggregate::Mean => Self::Mean(CoMoments::new()),
+			Aggregate::StdDev => Self::StdDev(CoMoments::new()),
+			Aggregate::Min => Self::Min(None),
+			Aggregate::Max => Self::Max(None),
+			Aggregate::Distinct => Self::Distinct(HyperLogLog::new(DISTINCT_ERROR_RATE)),
+		}
+	}
+	fn push(&mut self, value: &Value) {
+		match self {
+			Self::Count(count) => *count += 1,
+			Self::Sum(sum) => {
+				if let Some(value) = numeric(value) {
+					*sum += value;
+				}
+			}
+			Self::Mean(moments) | Self::StdDev(moments) => {
+				if let Some(value) = numeric(value) {
+					moments.push(&[value]);
+				}
+			}
+			Self::Min(min) => extremum(min, value.clone(), Ordering::Less),
+			Self::Max(max) => extremum(max, value.clone(), Ordering::Greater),
+			Self::Distinct(hll) => hll.push(value),
+		}
+	}
+	fn merge(&mut self, other: Self) {
+		match (self, other) {
+			(Self::Count(a), Self::Count(b)) => *a += b,
+			(Self::Sum(a), Self::Sum(b)) => *a += b,
+			(Self::Mean(a), Self::Mean(b)) | (Self::StdDev(a), Self::StdDev(b)) => a.merge(b),
+			(Self::Min(a), Self::Min(Some(b))) => extremum(a, b, Ordering::Less),
+			(Self::Max(a), Self::Max(Some(b))) => extremum(a, b, Ordering::Greater),
+			(Self::Min(_), Self::Min(None)) | (Self::Max(_), Self::Max(None)) => (),
+			(Self::Distinct(a), Self::Distinct(b)) => a.union(&b),
+			_ => unreachable!(),
+		}
+	}
+	fn done(self) -> Value {
+		match self {
+			Self::Count(count) => Value::U64(count),
+			Self::Sum(sum) => Value::F64(sum),
+			Self::Mean(moments) => mean(&moments).into(),
+			Self::StdDev(moments) => stddev(&moments).into(),
+			Self::Min(value) | Self::Max(value) => value.into(),
+			Self::Distinct(hll) => Value::F64(hll.len()),
+		}
+	}
+}
+
+/// The mean of the values pushed to `moments` one at a time, if there are any.
+pub(crate) fn mean(moments: &CoMoments) -> Option<f64> {
+	moments.means().first().copied()
+}
+
+/// The population standard deviation of the values pushed to `moments` one at a
+/// time, if there are any.
+pub(crate) fn stddev(moments: &CoMoments) -> Option<f64> {
+	if moments.count() != 0 {
+		Some((moments.co_moment(0, 0) / u64_to_f64(moments.count())).sqrt())
+	} else {
+		None
+	}
+}
+
+/// Replace `current` with `value` if it's unset or `value` compares as `ordering` to it.
+pub(crate) fn extremum(current: &mut Option<Value>, value: Value, ordering: Ordering) {
+	if current
+		.as_ref()
+		.map_or(true, |current| cmp(&value, current) == ordering)
+	{
+		*current = Some(value);
+	}
+}
+
+/// A total order over values of any type, so that the min and max of a column
+/// holding values of several types, as JSON columns often do, are well-defined
+/// rather than panicking in [`AmadeusOrd`]. Numbers of different types are
+/// compared by value, and sort before values of other types, which are ordered
+/// by the name of their type.
+fn cmp(a: &Value, b: &Value) -> Ordering {
+	if mem::discriminant(a) == mem::discriminant(b) {
+		return a.amadeus_cmp(b);
+	}
+	match (numeric(a), numeric(b)) {
+		(Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+		(Some(_), None) => Ordering::Less,
+		(None, Some(_)) => Ordering::Greater,
+		(None, None) => a.type_name().cmp(b.type_name()),
+	}
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn numeric(value: &Value) -> Option<f64> {
+	Some(match *value {
+		Value::U8(value) => value.into(),
+		Value::I8(value) => value.into(),
+		Value::U16(value) => value.into(),
+		Value::I16(value) => value.into(),
+		Value::U32(value) => value.into(),
+		Value::I32(value) => value.into(),
+		Value::U64(value) => value as f64,
+		Value::I64(value) => value as f64,
+		Value::F32(value) => value.into(),
+		Value::F64(value) => value,
+		_ => return None,
+	})
+}
@@ -0,0 +1,394 @@
+//! A sink that profiles every column of a stream of [`Group`] rows in a single
+//! pass.
+
+use amadeus_core::{
+	par_pipe::{DistributedPipe, ParallelPipe}, par_sink::{
+		CoMoments, DistributedSink, Final, FolderSync, FolderSyncReducer, Inter, ParallelSink
+	}, par_stream::{DistributedStream, Identity, ParallelStream}, pool::{ProcessPool, ThreadPool}
+};
+use amadeus_streaming::{HyperLogLog, Top};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, cmp::Ordering, future::Future, marker::PhantomData, pin::Pin};
+
+use super::{
+	aggregate::{extremum, mean, numeric, stddev}, Group, Value
+};
+
+/// The error rate of the HyperLogLog used to estimate distinct counts.
+const DISTINCT_ERROR_RATE: f64 = 0.01;
+/// The probability and tolerance of the count-min sketch used to find the most
+/// frequent values.
+const TOP_PROBABILITY: f64 = 0.99;
+const TOP_TOLERANCE: f64 = 0.002;
+
+/// Profiles every column of a stream of [`Group`]s, or of [`Value`]s holding
+/// [`Group`]s or maps (such as JSON objects), in a single pass.
+///
+/// Columns are discovered as they're seen, so rows needn't share a schema;
+/// a column missing from a row counts as a null. A stream of other [`Value`]s
+/// is described as a single column named `value`.
+///
+/// ```ignore
+/// let description = stream.describe(pool, 10).await;
+/// for column in &description.columns {
+/// 	println!("{}: {:?} nulls: {}", column.name, column.types, column.nulls);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct Describe {
+	top: usize,
+}
+
+impl Describe {
+	/// Profile columns, tracking the `top` most frequent values of each.
+	pub fn new(top: usize) -> Self {
+		assert_ne!(top, 0, "top must be greater than 0");
+		Self { top }
+	}
+}
+
+/// The profile of a stream returned by [`Describe`].
+#[derive(Clone, Debug)]
+pub struct Description {
+	/// The number of rows.
+	pub rows: u64,
+	/// Each column, in the order they were first seen.
+	pub columns: Vec<ColumnDescription>,
+}
+
+/// The profile of a single column.
+#[derive(Clone, Debug)]
+pub struct ColumnDescription {
+	/// The name of the column, or its index if its [`Group`] had no field names.
+	pub name: String,
+	/// The number of rows in which the column was null or missing.
+	pub nulls: u64,
+	/// The number of non-null values of each type, most common first.
+	pub types: Vec<(String, u64)>,
+	/// The smallest non-null value, ordered as by [`Aggregate::Min`](crate::Aggregate::Min).
+	pub min: Option<Value>,
+	/// The largest non-null value, ordered as by [`Aggregate::Max`](crate::Aggregate::Max).
+	pub max: Option<Value>,
+	/// The mean of the numeric values.
+	pub mean: Option<f64>,
+	/// The population standard deviation of the numeric values.
+	pub stddev: Option<f64>,
+	/// An estimate of the number of distinct non-null values.
+	pub distinct: f64,
+	/// An estimate of the most frequent non-null values and their counts, most frequent first.
+	pub top: Vec<(Value, usize)>,
+}
+
+macro_rules! impl_sink {
+	($($item:ty)*) => ($(
+		impl ParallelSink<$item> for Describe {
+			type Done = Description;
+			type Pipe = Identity;
+			type ReduceA = FolderSyncReducer<$item, DescribeFolder<StepA>, Inter>;
+			type ReduceC = FolderSyncReducer<DescribeState, DescribeFolder<StepB>, Final>;
+
+			fn reducers(self) -> (Self::Pipe, Self::ReduceA, Self::ReduceC) {
+				(
+					Identity,
+					FolderSyncReducer::new(DescribeFolder::new(self.top)),
+					FolderSyncReducer::new(DescribeFolder::new(self.top)),
+				)
+			}
+		}
+		impl DistributedSink<$item> for Describe {
+			type Done = Description;
+			type Pipe = Identity;
+			type ReduceA = FolderSyncReducer<$item, DescribeFolder<StepA>, Inter>;
+			type ReduceB = FolderSyncReducer<DescribeState, DescribeFolder<StepB>, Inter>;
+			type ReduceC = FolderSyncReducer<DescribeState, DescribeFolder<StepB>, Final>;
+
+			fn reducers(self) -> (Self::Pipe, Self::ReduceA, Self::ReduceB, Self::ReduceC) {
+				(
+					Identity,
+					FolderSyncReducer::new(DescribeFolder::new(self.top)),
+					FolderSyncReducer::new(DescribeFolder::new(self.top)),
+					FolderSyncReducer::new(DescribeFolder::new(self.top)),
+				)
+			}
+		}
+	)*);
+}
+impl_sink!(Group Value);
+
+/// Adds [`describe`](Self::describe) to every [`ParallelStream`] of [`Group`]s
+/// or [`Value`]s.
+pub trait DescribeParallelStream: ParallelStream {
+	/// Profile every column in a single pass, tracking the `top` most frequent
+	/// values of each. See [`Describe`].
+	#[allow(clippy::type_complexity)]
+	fn describe<'a, P>(
+		self, pool: &'a P, top: usize,
+	) -> Pin<Box<dyn Future<Output = Description> + 'a>>
+	where
+		P: ThreadPool,
+		Describe: ParallelSink<Self::Item, Done = Description>,
+		<<Describe as ParallelSink<Self::Item>>::Pipe as ParallelPipe<Self::Item>>::Task: 'static,
+		<Describe as ParallelSink<Self::Item>>::ReduceA: 'static,
+		Self::Task: 'static,
+		Self: Sized + 'a,
+	{
+		self.pipe(pool, Describe::new(top))
+	}
+}
+impl<S: ParallelStream> DescribeParallelStream for S {}
+
+/// Adds [`describe`](Self::describe) to every [`DistributedStream`] of
+/// [`Group`]s or [`Value`]s.
+pub trait DescribeDistributedStream: DistributedStream {
+	/// Profile every column in a single pass, tracking the `top` most frequent
+	/// values of each. See [`Describe`].
+	#[allow(clippy::type_complexity)]
+	fn describe<'a, P>(
+		self, pool: &'a P, top: usize,
+	) -> Pin<Box<dyn Future<Output = Description> + 'a>>
+	where
+		P: ProcessPool,
+		Describe: DistributedSink<Self::Item, Done = Description>,
+		<<Describe as DistributedSink<Self::Item>>::Pipe as DistributedPipe<Self::Item>>::Task:
+			'static,
+		<Describe as DistributedSink<Self::Item>>::ReduceA: 'static,
+		<Describe as DistributedSink<Self::Item>>::ReduceB: 'static,
+		Self::Task: 'static,
+		Self: Sized + 'a,
+	{
+		self.pipe(pool, Describe::new(top))
+	}
+}
+impl<S: DistributedStream> DescribeDistributedStream for S {}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct DescribeFolder<Step> {
+	top: usize,
+	marker: PhantomData<fn() -> Step>,
+}
+impl<Step> Clone for DescribeFolder<Step> {
+	fn clone(&self) -> Self {
+		Self::new(self.top)
+	}
+}
+impl<Step> DescribeFolder<Step> {
+	fn new(top: usize) -> Self {
+		Self {
+			top,
+			marker: PhantomData,
+		}
+	}
+	fn push_group(&self, state: &mut DescribeState, group: &Group) {
+		let fields = group.fields();
+		if let Some(field_names) = group.field_names() {
+			for (i, (name, &index)) in field_names.iter().enumerate() {
+				self.push_value(state, i, name, &fields[index]);
+			}
+		} else {
+			for (i, value) in fields.iter().enumerate() {
+				self.push_value(state, i, &i.to_string(), value);
+			}
+		}
+	}
+	fn push_value(&self, state: &mut DescribeState, i: usize, name: &str, value: &Value) {
+		// Rows usually share a schema, so check the column at the same position first
+		let index = if state
+			.columns
+			.get(i)
+			.map_or(false, |(name_, _)| name_ == name)
+		{
+			i
+		} else if let Some(index) = state.columns.iter().position(|(name_, _)| name_ == name) {
+			index
+		} else {
+			state
+				.columns
+				.push((name.to_owned(), ColumnState::new(self.top)));
+			state.columns.len() - 1
+		};
+		let value = match value {
+			Value::Option(None) => return,
+			Value::Option(Some(value)) => Cow::Owned(value.clone().into()),
+			value => Cow::Borrowed(value),
+		};
+		state.columns[index].1.push(&value);
+	}
+}
+
+pub struct StepA;
+pub struct StepB;
+
+impl FolderSync<Group> for DescribeFolder<StepA> {
+	type State = DescribeState;
+	type Done = DescribeState;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		DescribeState::default()
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: Group) {
+		state.rows += 1;
+		self.push_group(state, &item)
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+impl FolderSync<Value> for DescribeFolder<StepA> {
+	type State = DescribeState;
+	type Done = DescribeState;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		DescribeState::default()
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: Value) {
+		state.rows += 1;
+		match &item {
+			Value::Group(group) => self.push_group(state, group),
+			Value::Map(map) => {
+				// Sorted so that columns are first seen in a consistent order
+				let mut fields = map
+					.iter()
+					.map(|(key, value)| {
+						let name = match key {
+							Value::String(key) => Cow::Borrowed(&**key),
+							key => Cow::Owned(format!("{:?}", key)),
+						};
+						(name, value)
+					})
+					.collect::<Vec<_>>();
+				fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+				for (i, (name, value)) in fields.into_iter().enumerate() {
+					self.push_value(state, i, &name, value);
+				}
+			}
+			value => self.push_value(state, 0, "value", value),
+		}
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		state
+	}
+}
+
+impl FolderSync<DescribeState> for DescribeFolder<StepB> {
+	type State = DescribeState;
+	type Done = Description;
+
+	#[inline(always)]
+	fn zero(&mut self) -> Self::State {
+		DescribeState::default()
+	}
+	#[inline(always)]
+	fn push(&mut self, state: &mut Self::State, item: DescribeState) {
+		state.rows += item.rows;
+		for (name, column) in item.columns {
+			if let Some((_, state)) = state.columns.iter_mut().find(|(name_, _)| *name_ == name) {
+				state.merge(column);
+			} else {
+				state.columns.push((name, column));
+			}
+		}
+	}
+	#[inline(always)]
+	fn done(&mut self, state: Self::State) -> Self::Done {
+		let rows = state.rows;
+		let columns = state
+			.columns
+			.into_iter()
+			.map(|(name, column)| column.done(name, rows))
+			.collect();
+		Description { rows, columns }
+	}
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct DescribeState {
+	rows: u64,
+	columns: Vec<(String, ColumnState)>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ColumnState {
+	values: u64,
+	types: Vec<(String, u64)>,
+	min: Option<Value>,
+	max: Option<Value>,
+	moments: CoMoments,
+	distinct: HyperLogLog<Value>,
+	top: Top<Value, usize>,
+}
+
+impl ColumnState {
+	fn new(top: usize) -> Self {
+		Self {
+			values: 0,
+			types: Vec::new(),
+			min: None,
+			max: None,
+			moments: CoMoments::new(),
+			distinct: HyperLogLog::new(DISTINCT_ERROR_RATE),
+			top: Top::new(top, TOP_PROBABILITY, TOP_TOLERANCE, ()),
+		}
+	}
+	fn push(&mut self, value: &Value) {
+		self.values += 1;
+		let type_name = value.type_name();
+		if let Some((_, count)) = self.types.iter_mut().find(|(name, _)| name == type_name) {
+			*count += 1;
+		} else {
+			self.types.push((type_name.to_owned(), 1));
+		}
+		extremum(&mut self.min, value.clone(), Ordering::Less);
+		extremum(&mut self.max, value.clone(), Ordering::Greater);
+		if let Some(value) = numeric(value) {
+			self.moments.push(&[value]);
+		}
+		self.distinct.push(value);
+		self.top.push(value.clone(), &1);
+	}
+	fn merge(&mut self, other: Self) {
+		self.values += other.values;
+		for (type_name, count) in other.types {
+			if let Some((_, count_)) = self.types.iter_mut().find(|(name, _)| *name == type_name) {
+				*count_ += count;
+			} else {
+				self.types.push((type_name, count));
+			}
+		}
+		if let Some(min) = other.min {
+			extremum(&mut self.min, min, Ordering::Less);
+		}
+		if let Some(max) = other.max {
+			extremum(&mut self.max, max, Ordering::Greater);
+		}
+		self.moments.merge(other.moments);
+		self.distinct.union(&other.distinct);
+		self.top += other.top;
+	}
+	fn done(mut self, name: String, rows: u64) -> ColumnDescription {
+		self.types.sort_by(|(_, a), (_, b)| b.cmp(a));
+		ColumnDescription {
+			name,
+			nulls: rows - self.values,
+			types: self.types,
+			min: self.min,
+			max: self.max,
+			mean: mean(&self.moments),
+			stddev: stddev(&self.moments),
+			distinct: self.distinct.len(),
+			top: self
+				.top
+				.iter()
+				.map(|(value, &count)| (value.clone(), count))
+				.collect(),
+		}
+	}
+}
@@ -77,9 +77,11 @@ macro_rules! tuple {
 	)
 }
 
+mod aggregate;
 mod array;
 mod data;
 mod decimal;
+mod describe;
 mod group;
 mod http;
 mod list;
@@ -94,7 +96,7 @@ use std::{
 };
 
 pub use self::{
-	array::{Bson, Enum, Json}, data::Data, decimal::Decimal, group::Group, http::{IpAddr, ParseAddrError, ParseUrlError, ParseWebpageError, Url, Webpage}, list::{List, ListVec}, ord::AmadeusOrd, time::{
+	aggregate::{Aggregate, Aggregations}, array::{Bson, Enum, Json}, data::Data, decimal::Decimal, describe::{ColumnDescription, Describe, DescribeDistributedStream, DescribeParallelStream, Description}, group::Group, http::{IpAddr, ParseAddrError, ParseUrlError, ParseWebpageError, Url, Webpage}, list::{List, ListVec}, ord::AmadeusOrd, time::{
 		Date, DateTime, DateTimeWithoutTimezone, DateWithoutTimezone, ParseDateError, Time, TimeWithoutTimezone, Timezone
 	}, value::{Schema, SchemaIncomplete, Value}, value_required::ValueRequired
 };
@@ -410,7 +410,7 @@ impl AmadeusOrd for Value {
 }
 
 impl Value {
-	fn type_name(&self) -> &'static str {
+	pub(crate) fn type_name(&self) -> &'static str {
 		match self {
 			Self::Bool(_value) => "bool",
 			Self::U8(_value) => "u8",
@@ -0,0 +1,104 @@
+#![cfg(nightly)]
+#![feature(test)]
+#![allow(clippy::suspicious_map)]
+
+extern crate test;
+
+use once_cell::sync::Lazy;
+use std::{fs, future::Future, path::PathBuf};
+use test::Bencher;
+use tokio::runtime::Runtime;
+
+use amadeus::{
+	amadeus_core::file::{LocalFile, Page}, prelude::*
+};
+
+static RT: Lazy<Runtime> = Lazy::new(|| {
+	tokio::runtime::Builder::new()
+		.threaded_scheduler()
+		.enable_all()
+		.build()
+		.unwrap()
+});
+static POOL: Lazy<ThreadPool> = Lazy::new(|| ThreadPool::new(None).unwrap());
+
+#[derive(Data, Clone, PartialEq, PartialOrd, Debug)]
+struct GameDerived {
+	a: String,
+	b: String,
+	c: String,
+	d: String,
+	e: u32,
+	f: String,
+}
+
+#[bench]
+fn local_read(b: &mut Bencher) {
+	let file = "amadeus-testing/csv/game.csv"; // 2,600,000 bytes
+	run(b, file, || async {
+		read(LocalFile::open(file).unwrap()).await;
+	})
+}
+
+#[bench]
+fn local_read_mmap(b: &mut Bencher) {
+	let file = "amadeus-testing/csv/game.csv"; // 2,600,000 bytes
+	run(b, file, || async {
+		// Safety: the test data isn't modified while it's being read
+		read(unsafe { LocalFile::open_mmap(file) }.unwrap()).await;
+	})
+}
+
+#[bench]
+fn local_csv(b: &mut Bencher) {
+	let file = "amadeus-testing/csv/game.csv"; // 2,600,000 bytes
+	run(b, file, || async {
+		let rows = Csv::<_, GameDerived>::new(PathBuf::from(file))
+			.await
+			.unwrap();
+		assert_eq!(
+			rows.par_stream()
+				.map(|row: Result<_, _>| row.unwrap())
+				.count(&*POOL)
+				.await,
+			100_000
+		);
+	})
+}
+
+#[bench]
+fn local_csv_mmap(b: &mut Bencher) {
+	let file = "amadeus-testing/csv/game.csv"; // 2,600,000 bytes
+	run(b, file, || async {
+		// Safety: the test data isn't modified while it's being read
+		let file = unsafe { MemoryMapped::new(PathBuf::from(file)) };
+		let rows = Csv::<_, GameDerived>::new(file).await.unwrap();
+		assert_eq!(
+			rows.par_stream()
+				.map(|row: Result<_, _>| row.unwrap())
+				.count(&*POOL)
+				.await,
+			100_000
+		);
+	})
+}
+
+/// Read the whole of `page` in 64 KiB chunks, as the format readers do.
+async fn read(page: LocalFile) {
+	let len = page.len().await.unwrap();
+	let mut offset = 0;
+	while offset < len {
+		offset += page.read(offset, 64 * 1024).await.unwrap().len() as u64;
+	}
+}
+
+fn run<F>(b: &mut Bencher, file: &str, mut task: impl FnMut() -> F)
+where
+	F: Future<Output = ()>,
+{
+	RT.enter(|| {
+		let _ = Lazy::force(&POOL);
+		b.bytes = fs::metadata(file).unwrap().len();
+		b.iter(|| RT.handle().block_on(task()))
+	})
+}
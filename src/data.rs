@@ -19,7 +19,7 @@ use std::any::Any as SerdeData;
 
 pub use amadeus_derive::Data;
 pub use amadeus_types::{
-	AmadeusOrd, Bson, Date, DateTime, DateTimeWithoutTimezone, DateWithoutTimezone, Decimal, Downcast, DowncastFrom, Enum, Group, IpAddr, Json, List, Time, TimeWithoutTimezone, Timezone, Url, Value, Webpage
+	Aggregate, Aggregations, AmadeusOrd, Bson, ColumnDescription, Date, DateTime, DateTimeWithoutTimezone, DateWithoutTimezone, Decimal, Describe, DescribeDistributedStream, DescribeParallelStream, Description, Downcast, DowncastFrom, Enum, Group, IpAddr, Json, List, Time, TimeWithoutTimezone, Timezone, Url, Value, Webpage
 };
 
 pub trait Data:
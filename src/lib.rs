@@ -60,12 +60,12 @@ pub mod dist {
 		#[cfg(feature = "aws")]
 		#[doc(no_inline)]
 		pub use crate::source::aws::{
-			AwsCredentials, AwsError, AwsRegion, CloudfrontRow, S3Directory, S3File
+			AwsCredentials, AwsError, AwsRegion, CloudfrontRow, S3Directory, S3File, S3Options
 		};
 		#[doc(no_inline)]
 		pub use crate::{
 			data::{
-				Date, DateTime, DateTimeWithoutTimezone, DateWithoutTimezone, Decimal, Downcast, DowncastFrom, Enum, Group, Time, TimeWithoutTimezone, Timezone
+				Date, DateTime, DateTimeWithoutTimezone, DateWithoutTimezone, Decimal, DescribeDistributedStream, Downcast, DowncastFrom, Enum, Group, Time, TimeWithoutTimezone, Timezone
 			}, par_pipe::DistributedPipe, par_stream::Identity, pool::ThreadPool, source::*, Data, DistributedStream, FromDistributedStream, IntoDistributedStream, IteratorExt, List, Value
 		};
 		#[doc(no_inline)]
@@ -79,12 +79,12 @@ pub mod prelude {
 	#[cfg(feature = "aws")]
 	#[doc(no_inline)]
 	pub use crate::source::aws::{
-		AwsCredentials, AwsError, AwsRegion, CloudfrontRow, S3Directory, S3File
+		AwsCredentials, AwsError, AwsRegion, CloudfrontRow, S3Directory, S3File, S3Options
 	};
 	#[doc(no_inline)]
 	pub use crate::{
 		data::{
-			Date, DateTime, DateTimeWithoutTimezone, DateWithoutTimezone, Decimal, Downcast, DowncastFrom, Enum, Group, Time, TimeWithoutTimezone, Timezone
+			Date, DateTime, DateTimeWithoutTimezone, DateWithoutTimezone, Decimal, DescribeParallelStream, Downcast, DowncastFrom, Enum, Group, Time, TimeWithoutTimezone, Timezone
 		}, par_pipe::ParallelPipe, par_stream::Identity, pool::ThreadPool, source::*, Data, FromParallelStream, IntoParallelStream, IteratorExt, List, ParallelStream, Value
 	};
 }
@@ -42,6 +42,14 @@ impl ProcessPoolTrait for ProcessPool {
 	{
 		Box::pin(ProcessPool::spawn_unchecked(self, work).map_err(|e| Box::new(e) as _))
 	}
+	fn spawn_all<F, Fut, T>(&self, work: F) -> BoxFuture<'static, Result<Vec<T>>>
+	where
+		F: traits::FnOnce(&Self::ThreadPool) -> Fut + ProcessSend + Clone + 'static,
+		Fut: Future<Output = T> + 'static,
+		T: ProcessSend + 'static,
+	{
+		Box::pin(ProcessPool::spawn_all(self, work).map_err(|e| Box::new(e) as _))
+	}
 }
 
 #[cfg_attr(not(nightly), serde_closure::desugar)]
@@ -72,6 +80,14 @@ impl ProcessPoolTrait for ThreadPool {
 		let spawn = move || work.call_once((&self_,));
 		Box::pin(ThreadPool::spawn_unchecked(self, spawn).map_err(|e| Box::new(e) as _))
 	}
+	fn spawn_all<F, Fut, T>(&self, work: F) -> BoxFuture<'static, Result<Vec<T>>>
+	where
+		F: traits::FnOnce(&Self::ThreadPool) -> Fut + ProcessSend + Clone + 'static,
+		Fut: Future<Output = T> + 'static,
+		T: ProcessSend + 'static,
+	{
+		Box::pin(ProcessPoolTrait::spawn(self, work).map_ok(|ret| vec![ret]))
+	}
 }
 
 impl ThreadPoolTrait for ThreadPool {
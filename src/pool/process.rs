@@ -1,9 +1,11 @@
 use constellation::{spawn, Receiver, Resources, Sender, SpawnError};
-use futures::{future::LocalBoxFuture, FutureExt};
+use futures::{
+	future::{self, Either, LocalBoxFuture}, stream::FuturesOrdered, FutureExt, StreamExt
+};
 use serde_closure::{traits, FnOnce};
 use serde_traitobject as st;
 use std::{
-	any, collections::VecDeque, fmt, future::Future, mem, panic::{self, RefUnwindSafe, UnwindSafe}, sync::{Arc, Mutex}
+	any, collections::VecDeque, fmt, future::Future, mem, panic::{self, RefUnwindSafe, UnwindSafe}, sync::{Arc, Mutex}, task::Poll
 };
 
 use amadeus_core::pool::ProcessSend;
@@ -132,16 +134,50 @@ impl ProcessPoolInner {
 
 							let thread_pool = ThreadPool::new(tasks_per_core).unwrap();
 
-							while let Some(work) = receiver.recv().await.unwrap() {
-								let ret = panic::catch_unwind(panic::AssertUnwindSafe(|| {
-									work.into_box().call_once_box((&thread_pool,))
-								}));
-								let ret = match ret {
-									Ok(t) => panic::AssertUnwindSafe(t).catch_unwind().await,
-									Err(e) => Err(e),
+							// Requests run concurrently, so that one can e.g. cancel another
+							// that's still running, but their responses are sent in the
+							// order they were received, which is how the parent matches
+							// them up
+							let mut running = FuturesOrdered::new();
+							let mut recv = Some(receiver.recv().boxed_local());
+							while recv.is_some() || !running.is_empty() {
+								let next = future::poll_fn(|cx| {
+									if let Some(recv_) = &mut recv {
+										if let Poll::Ready(work) = recv_.as_mut().poll(cx) {
+											return Poll::Ready(Either::Left(work.unwrap()));
+										}
+									}
+									match running.poll_next_unpin(cx) {
+										Poll::Ready(Some(ret)) => Poll::Ready(Either::Right(ret)),
+										_ => Poll::Pending,
+									}
+								})
+								.await;
+								match next {
+									Either::Left(Some(work)) => {
+										let ret =
+											panic::catch_unwind(panic::AssertUnwindSafe(|| {
+												work.into_box().call_once_box((&thread_pool,))
+											}));
+										running.push_back(
+											async move {
+												match ret {
+													Ok(t) => {
+														panic::AssertUnwindSafe(t)
+															.catch_unwind()
+															.await
+													}
+													Err(e) => Err(e),
+												}
+												.map_err(Panicked::from)
+											}
+											.boxed_local(),
+										);
+										recv = Some(receiver.recv().boxed_local());
+									}
+									Either::Left(None) => recv = None,
+									Either::Right(ret) => sender.send(ret).await,
 								}
-								.map_err(Panicked::from);
-								sender.send(ret).await;
 							}
 						})
 				}),
@@ -187,7 +223,14 @@ impl ProcessPoolInner {
 		Fut: Future<Output = T> + 'static,
 		T: ProcessSend + 'static,
 	{
-		let process_index = self.i.get();
+		self.spawn_to(self.i.get(), work).await
+	}
+	async fn spawn_to<F, Fut, T>(&self, process_index: usize, work: F) -> Result<T, Panicked>
+	where
+		F: for<'a> traits::FnOnce<(&'a ThreadPool,), Output = Fut> + ProcessSend + 'static,
+		Fut: Future<Output = T> + 'static,
+		T: ProcessSend + 'static,
+	{
 		let process = &self.processes[process_index];
 		let x = process
 			.sender
@@ -354,6 +397,23 @@ impl ProcessPool {
 		let inner = self.0.clone();
 		async move { inner.spawn(work).await }
 	}
+	pub fn spawn_all<F, Fut, T>(
+		&self, work: F,
+	) -> impl Future<Output = Result<Vec<T>, Panicked>> + Send
+	where
+		F: traits::FnOnce(&ThreadPool) -> Fut + ProcessSend + Clone + 'static,
+		Fut: Future<Output = T> + 'static,
+		T: ProcessSend + 'static,
+	{
+		let inner = self.0.clone();
+		async move {
+			future::try_join_all(
+				(0..inner.processes())
+					.map(|process_index| inner.spawn_to(process_index, work.clone())),
+			)
+			.await
+		}
+	}
 	#[allow(unsafe_code)]
 	pub unsafe fn spawn_unchecked<'a, F, Fut, T>(
 		&self, work: F,
@@ -12,6 +12,12 @@ use crate::{
 	par_sink::{DistributedSink, ParallelSink}, par_stream::{DistributedStream, ParallelStream, StreamTask}
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+#[doc(inline)]
+pub use amadeus_core::file::MemoryMapped;
+#[doc(inline)]
+pub use amadeus_core::file::{Compression, Glob, GlobError};
+
 #[cfg(feature = "aws")]
 #[doc(inline)]
 pub use amadeus_aws::Cloudfront;
@@ -19,11 +25,42 @@ pub use amadeus_aws::Cloudfront;
 pub mod aws {
 	pub use crate::data::CloudfrontRow;
 	#[doc(inline)]
-	pub use amadeus_aws::{AwsCredentials, AwsError, AwsRegion, S3Directory, S3File};
+	pub use amadeus_aws::{
+		AwsCredentials, AwsError, AwsRegion, RetryPolicy, S3Directory, S3File, S3Options, S3Page
+	};
+}
+#[cfg(feature = "azure")]
+#[doc(inline)]
+pub use amadeus_azure::{AzureDirectory, AzureFile};
+#[cfg(feature = "azure")]
+pub mod azure {
+	#[doc(inline)]
+	pub use amadeus_azure::{
+		AzureCredentials, AzureDirectory, AzureFile, AzurePage, AzurePartition
+	};
 }
 #[cfg(feature = "commoncrawl")]
 #[doc(inline)]
 pub use amadeus_commoncrawl::CommonCrawl;
+#[cfg(feature = "gcs")]
+#[doc(inline)]
+pub use amadeus_gcs::{GcsDirectory, GcsFile};
+#[cfg(feature = "gcs")]
+pub mod gcs {
+	#[doc(inline)]
+	pub use amadeus_gcs::{GcsCredentials, GcsDirectory, GcsFile, GcsPage, GcsPartition};
+}
+#[cfg(feature = "hdfs")]
+#[doc(inline)]
+pub use amadeus_hdfs::{HdfsDirectory, HdfsFile};
+#[cfg(feature = "hdfs")]
+pub mod hdfs {
+	#[doc(inline)]
+	pub use amadeus_hdfs::{BlockLocation, HdfsDirectory, HdfsFile, HdfsPage, HdfsPartition};
+}
+#[cfg(feature = "http")]
+#[doc(inline)]
+pub use amadeus_http::{HttpDirectory, HttpFile, HttpPage};
 #[cfg(feature = "parquet")]
 #[doc(inline)]
 pub use amadeus_parquet::{Parquet, ParquetDirectory};
@@ -0,0 +1,134 @@
+//! These tests run against [Azurite](https://github.com/Azure/Azurite), e.g.
+//! `docker run -p 10000:10000 mcr.microsoft.com/azure-storage/azurite azurite-blob --blobHost 0.0.0.0`,
+//! with a container named `amadeus`. Set `AMADEUS_AZURE_ENDPOINT` to use an
+//! endpoint other than `http://127.0.0.1:10000/devstoreaccount1`.
+//!
+//! They're `test = false`, so aren't run by a plain `cargo test`. Run them with
+//! `cargo test --features "azure csv json" --test azure`, as CI's emulators job does.
+
+use futures::future::try_join_all;
+use std::{env, io};
+use url::Url;
+
+use amadeus::{
+	amadeus_core::file::{Directory, File, Page, Partition}, prelude::*, source::azure::AzureCredentials
+};
+
+const ACCOUNT: &str = "devstoreaccount1";
+const CONTAINER: &str = "amadeus";
+
+fn endpoint() -> Url {
+	env::var("AMADEUS_AZURE_ENDPOINT")
+		.unwrap_or_else(|_| String::from("http://127.0.0.1:10000/devstoreaccount1"))
+		.parse()
+		.unwrap()
+}
+fn credentials() -> AzureCredentials {
+	// Azurite's well-known development key
+	AzureCredentials::SharedKey(String::from(
+		"Eby8vdM02xNOcqFlqUwJPLlmEtlCDXJ1OUzFT50uSRZ6IFsuFq2UVErCz4I6tq/K1SZFPTOtr/KBHBeksoGMGw==",
+	))
+}
+fn directory(prefix: &str) -> AzureDirectory {
+	AzureDirectory::new_with(ACCOUNT, CONTAINER, prefix, credentials()).endpoint(endpoint())
+}
+
+async fn put(file: AzureFile, data: &[u8]) {
+	let page = file.create();
+	page.write(0, data.to_vec().into_boxed_slice())
+		.await
+		.unwrap();
+	page.flush().await.unwrap();
+}
+async fn get(file: AzureFile) -> Vec<u8> {
+	let page = file.pages().await.unwrap().pop().unwrap();
+	let len = page.len().await.unwrap();
+	page.read(0, len as usize).await.unwrap().into_vec()
+}
+
+#[tokio::test]
+async fn azure_write() {
+	let directory = directory("azure_write/");
+
+	// Large enough to need several blocks, written out of order
+	let data = (0..40 * 1024 * 1024_u32)
+		.map(|i| (i % 251) as u8)
+		.collect::<Vec<_>>();
+	let page = directory.file("large").create();
+	let chunk = 1024 * 1024;
+	try_join_all(
+		data.chunks(chunk)
+			.enumerate()
+			.rev()
+			.map(|(i, buf)| page.write((i * chunk) as u64, buf.to_vec().into_boxed_slice())),
+	)
+	.await
+	.unwrap();
+	page.flush().await.unwrap();
+
+	put(directory.file("small"), b"hello world").await;
+	put(directory.file("empty"), b"").await;
+
+	assert!(get(directory.file("large")).await == data);
+	assert_eq!(get(directory.file("small")).await, b"hello world");
+	assert_eq!(get(directory.file("empty")).await, b"");
+
+	// Gaps are an error
+	let page = directory.file("gap").create();
+	page.write(1, vec![0].into_boxed_slice()).await.unwrap();
+	assert!(page.flush().await.is_err());
+}
+
+#[tokio::test]
+async fn azure_read() {
+	let file = directory("azure_read/").file("file");
+	put(file.clone(), b"0123456789").await;
+	let page = file.pages().await.unwrap().pop().unwrap();
+	assert_eq!(page.len().await.unwrap(), 10);
+	assert_eq!(&*page.read(3, 4).await.unwrap(), b"3456");
+	// Reads past the end are truncated
+	assert_eq!(&*page.read(8, 10).await.unwrap(), b"89");
+	assert_eq!(&*page.read(10, 10).await.unwrap(), b"");
+}
+
+#[tokio::test]
+async fn azure_directory() {
+	let directory = directory("azure_directory/");
+	for name in &["a/1", "a/2", "b/1", "c"] {
+		put(directory.file(name), name.as_bytes()).await;
+	}
+
+	let partitions = directory.clone().partitions().await.unwrap();
+	assert_eq!(partitions.len(), 4);
+	let mut contents = Vec::new();
+	for partition in partitions {
+		let page = partition.pages().await.unwrap().pop().unwrap();
+		let len = page.len().await.unwrap();
+		contents.push(page.read(0, len as usize).await.unwrap().into_vec());
+	}
+	contents.sort();
+	assert_eq!(contents, vec![&b"a/1"[..], b"a/2", b"b/1", b"c"]);
+
+	let partitions = directory
+		.partitions_filter(|path| path.iter().next().map_or(true, |first| first != "a"))
+		.await
+		.unwrap();
+	assert_eq!(partitions.len(), 2);
+}
+
+#[tokio::test]
+async fn azure_errors() {
+	let file = directory("azure_errors/").file("missing");
+	let err = file.pages().await.err().unwrap();
+	assert_eq!(io::Error::from(err).kind(), io::ErrorKind::NotFound);
+
+	let file = AzureFile::new_with(
+		ACCOUNT,
+		CONTAINER,
+		"azure_errors/denied",
+		// Well-formed, but not the account's
+		AzureCredentials::SharedKey(String::from("d3Jvbmcga2V5")),
+	)
+	.endpoint(endpoint());
+	assert!(file.pages().await.is_err());
+}
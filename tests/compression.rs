@@ -0,0 +1,178 @@
+use futures::AsyncWriteExt;
+use std::{env, fs, process};
+
+use amadeus::{
+	amadeus_core::file::{compress, LocalFile}, prelude::*
+};
+
+// `a,1\nb,2\n` compressed with the `gzip`, `zstd`, `bzip2`, `xz` and `lz4` command line tools
+const GZIP: &[u8] = &[
+	0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x4b, 0xd4, 0x31, 0xe4, 0x4a, 0xd2,
+	0x31, 0xe2, 0x02, 0x00, 0x6e, 0xdd, 0x35, 0x59, 0x08, 0x00, 0x00, 0x00,
+];
+const ZSTD: &[u8] = &[
+	0x28, 0xb5, 0x2f, 0xfd, 0x24, 0x08, 0x41, 0x00, 0x00, 0x61, 0x2c, 0x31, 0x0a, 0x62, 0x2c, 0x32,
+	0x0a, 0xe1, 0xa0, 0x19, 0xa1,
+];
+const BZIP2: &[u8] = &[
+	0x42, 0x5a, 0x68, 0x39, 0x31, 0x41, 0x59, 0x26, 0x53, 0x59, 0xb2, 0x4b, 0x81, 0xea, 0x00, 0x00,
+	0x03, 0x59, 0x00, 0x00, 0x10, 0x00, 0x04, 0x30, 0x00, 0x30, 0x00, 0x20, 0x00, 0x21, 0x93, 0x1a,
+	0x83, 0x00, 0xb7, 0x02, 0x17, 0x8b, 0xb9, 0x22, 0x9c, 0x28, 0x48, 0x59, 0x25, 0xc0, 0xf5, 0x00,
+];
+const XZ: &[u8] = &[
+	0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00, 0x00, 0x04, 0xe6, 0xd6, 0xb4, 0x46, 0x04, 0xc0, 0x0c, 0x08,
+	0x21, 0x01, 0x16, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xac, 0x77, 0xaa, 0xa4,
+	0x01, 0x00, 0x07, 0x61, 0x2c, 0x31, 0x0a, 0x62, 0x2c, 0x32, 0x0a, 0x00, 0x65, 0xc7, 0x4c, 0xea,
+	0x66, 0x9e, 0xf4, 0x19, 0x00, 0x01, 0x28, 0x08, 0xb3, 0x93, 0x00, 0x73, 0x1f, 0xb6, 0xf3, 0x7d,
+	0x01, 0x00, 0x00, 0x00, 0x00, 0x04, 0x59, 0x5a,
+];
+const LZ4: &[u8] = &[
+	0x04, 0x22, 0x4d, 0x18, 0x64, 0x40, 0xa7, 0x08, 0x00, 0x00, 0x80, 0x61, 0x2c, 0x31, 0x0a, 0x62,
+	0x2c, 0x32, 0x0a, 0x00, 0x00, 0x00, 0x00, 0x32, 0x69, 0xf5, 0x10,
+];
+// `{"a":"x","b":1}\n{"a":"y","b":2}\n` compressed with `zstd`
+const JSON_ZSTD: &[u8] = &[
+	0x28, 0xb5, 0x2f, 0xfd, 0x24, 0x20, 0xe5, 0x00, 0x00, 0xa0, 0x7b, 0x22, 0x61, 0x22, 0x3a, 0x22,
+	0x78, 0x22, 0x2c, 0x22, 0x62, 0x22, 0x3a, 0x31, 0x7d, 0x0a, 0x79, 0x32, 0x7d, 0x0a, 0x02, 0x00,
+	0x60, 0x08, 0x33, 0xcc, 0x25, 0xe7, 0x14, 0xa0, 0x55,
+];
+
+#[derive(Data, Clone, PartialEq, PartialOrd, Debug)]
+struct Row {
+	a: String,
+	b: u32,
+}
+
+#[tokio::test]
+async fn compression() {
+	let pool = &ThreadPool::new(None).unwrap();
+	let dir = env::temp_dir().join(format!("amadeus-compression-{}", process::id()));
+	fs::create_dir_all(&dir).unwrap();
+	let row = |a: &str, b| Row { a: a.to_owned(), b };
+
+	let files: &[(&str, &[u8])] = &[
+		("rows.csv", b"a,1\nb,2\n"),
+		("rows.csv.gz", GZIP),
+		("rows.csv.zst", ZSTD),
+		("rows.csv.bz2", BZIP2),
+		("rows.csv.xz", XZ),
+		("rows.csv.lz4", LZ4),
+		// Detected from magic bytes
+		("rows-gzip", GZIP),
+		("rows-zstd", ZSTD),
+	];
+	for (name, contents) in files {
+		fs::write(dir.join(name), contents).unwrap();
+		let rows = Csv::<_, Row>::new(dir.join(name)).await.unwrap();
+		let rows = rows
+			.par_stream()
+			.map(|row: Result<_, _>| row.unwrap())
+			.collect::<_, Vec<_>>(pool)
+			.await;
+		assert_eq!(rows, vec![row("a", 1), row("b", 2)], "{}", name);
+
+		// Safety: the file isn't modified while it's mapped
+		let file = unsafe { MemoryMapped::new(dir.join(name)) };
+		let rows = Csv::<_, Row>::new(file).await.unwrap();
+		let rows = rows
+			.par_stream()
+			.map(|row: Result<_, _>| row.unwrap())
+			.collect::<_, Vec<_>>(pool)
+			.await;
+		assert_eq!(
+			rows,
+			vec![row("a", 1), row("b", 2)],
+			"{} memory mapped",
+			name
+		);
+	}
+
+	// A recognised uncompressed extension isn't sniffed, even if it looks compressed
+	fs::write(dir.join("magic.csv"), b"BZh,1\nb,2\n").unwrap();
+	let rows = Csv::<_, Row>::new(dir.join("magic.csv"))
+		.await
+		.unwrap()
+		.par_stream()
+		.map(|row: Result<_, _>| row.unwrap())
+		.collect::<_, Vec<_>>(pool)
+		.await;
+	assert_eq!(rows, vec![row("BZh", 1), row("b", 2)]);
+
+	// Explicit override
+	let rows = Csv::<_, Row>::new(dir.join("rows-zstd"))
+		.await
+		.unwrap()
+		.compression(Compression::Zstd)
+		.par_stream()
+		.map(|row: Result<_, _>| row.unwrap())
+		.count(pool)
+		.await;
+	assert_eq!(rows, 2);
+	let errors = Csv::<_, Row>::new(dir.join("rows.csv.gz"))
+		.await
+		.unwrap()
+		.compression(Compression::Uncompressed)
+		.par_stream()
+		.filter(|row: &Result<_, _>| row.is_err())
+		.count(pool)
+		.await;
+	assert!(errors > 0);
+
+	fs::write(dir.join("rows.json.zst"), JSON_ZSTD).unwrap();
+	let rows = Json::<_, Row>::new(dir.join("rows.json.zst"))
+		.await
+		.unwrap()
+		.par_stream()
+		.map(|row: Result<_, _>| row.unwrap())
+		.collect::<_, Vec<_>>(pool)
+		.await;
+	assert_eq!(rows, vec![row("x", 1), row("y", 2)]);
+
+	fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn compressed_output() {
+	let pool = &ThreadPool::new(None).unwrap();
+	let dir = env::temp_dir().join(format!("amadeus-compressed-output-{}", process::id()));
+	fs::create_dir_all(&dir).unwrap();
+
+	// Larger than a page, so it's written in several chunks
+	let rows = (0..1_000_000_u32)
+		.map(|i| format!("{},{}\n", i % 7, i))
+		.collect::<String>();
+	for (name, level) in &[
+		("rows.csv", None),
+		("rows.csv.gz", Some(1)),
+		("rows.csv.gz", Some(9)),
+		("rows.csv.zst", Some(19)),
+		("rows.csv.bz2", None),
+		("rows.csv.xz", None),
+	] {
+		let path = dir.join(name);
+		let page = LocalFile::from(fs::File::create(&path).unwrap());
+		let mut writer = compress(page, Some(*name), None, *level);
+		for chunk in rows.as_bytes().chunks(100_000) {
+			writer.write_all(chunk).await.unwrap();
+		}
+		writer.close().await.unwrap();
+		if *name != "rows.csv" {
+			assert!(fs::metadata(&path).unwrap().len() < rows.len() as u64 / 2);
+		}
+
+		let count = Csv::<_, Row>::new(path)
+			.await
+			.unwrap()
+			.par_stream()
+			.map(|row: Result<_, _>| row.unwrap())
+			.count(pool)
+			.await;
+		assert_eq!(count, 1_000_000, "{}", name);
+	}
+
+	let page = LocalFile::from(fs::File::create(dir.join("rows.csv.lz4")).unwrap());
+	let mut writer = compress(page, Some("rows.csv.lz4"), None, None);
+	assert!(writer.write_all(b"a,1\n").await.is_err());
+
+	fs::remove_dir_all(dir).unwrap();
+}
@@ -57,5 +57,16 @@ async fn csv() {
 		100_000
 	);
 
+	// Safety: the test data isn't modified while it's being read
+	let file = unsafe { MemoryMapped::new(PathBuf::from("amadeus-testing/csv/game.csv")) };
+	let rows = Csv::<_, GameDerived>::new(file).await.unwrap();
+	assert_eq!(
+		rows.par_stream()
+			.map(|row: Result<_, _>| row.unwrap())
+			.count(pool)
+			.await,
+		100_000
+	);
+
 	println!("in {:?}", start.elapsed().unwrap());
 }
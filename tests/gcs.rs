@@ -0,0 +1,117 @@
+//! These tests run against [fake-gcs-server](https://github.com/fsouza/fake-gcs-server),
+//! e.g. `docker run -p 4443:4443 fsouza/fake-gcs-server -scheme http`, with a
+//! bucket named `amadeus`. Set `AMADEUS_GCS_ENDPOINT` to use an endpoint other
+//! than `http://localhost:4443`.
+//!
+//! They're `test = false`, so aren't run by a plain `cargo test`. Run them with
+//! `cargo test --features "gcs csv json" --test gcs`, as CI's emulators job does.
+
+use futures::future::try_join_all;
+use std::{env, io};
+use url::Url;
+
+use amadeus::{
+	amadeus_core::file::{Directory, File, Page, Partition}, prelude::*, source::gcs::GcsCredentials
+};
+
+const BUCKET: &str = "amadeus";
+
+fn endpoint() -> Url {
+	env::var("AMADEUS_GCS_ENDPOINT")
+		.unwrap_or_else(|_| String::from("http://localhost:4443"))
+		.parse()
+		.unwrap()
+}
+fn directory(prefix: &str) -> GcsDirectory {
+	GcsDirectory::new_with(BUCKET, prefix, GcsCredentials::Anonymous).endpoint(endpoint())
+}
+
+async fn put(file: GcsFile, data: &[u8]) {
+	let page = file.create();
+	page.write(0, data.to_vec().into_boxed_slice())
+		.await
+		.unwrap();
+	page.flush().await.unwrap();
+}
+async fn get(file: GcsFile) -> Vec<u8> {
+	let page = file.pages().await.unwrap().pop().unwrap();
+	let len = page.len().await.unwrap();
+	page.read(0, len as usize).await.unwrap().into_vec()
+}
+
+#[tokio::test]
+async fn gcs_write() {
+	let directory = directory("gcs_write/");
+
+	// Large enough to need a resumable upload, written out of order
+	let data = (0..40 * 1024 * 1024_u32)
+		.map(|i| (i % 251) as u8)
+		.collect::<Vec<_>>();
+	let page = directory.file("large").create();
+	let chunk = 1024 * 1024;
+	try_join_all(
+		data.chunks(chunk)
+			.enumerate()
+			.rev()
+			.map(|(i, buf)| page.write((i * chunk) as u64, buf.to_vec().into_boxed_slice())),
+	)
+	.await
+	.unwrap();
+	page.flush().await.unwrap();
+
+	put(directory.file("small"), b"hello world").await;
+	put(directory.file("empty"), b"").await;
+
+	assert!(get(directory.file("large")).await == data);
+	assert_eq!(get(directory.file("small")).await, b"hello world");
+	assert_eq!(get(directory.file("empty")).await, b"");
+
+	// Gaps are an error
+	let page = directory.file("gap").create();
+	page.write(1, vec![0].into_boxed_slice()).await.unwrap();
+	assert!(page.flush().await.is_err());
+}
+
+#[tokio::test]
+async fn gcs_read() {
+	let file = directory("gcs_read/").file("file");
+	put(file.clone(), b"0123456789").await;
+	let page = file.pages().await.unwrap().pop().unwrap();
+	assert_eq!(page.len().await.unwrap(), 10);
+	assert_eq!(&*page.read(3, 4).await.unwrap(), b"3456");
+	// Reads past the end are truncated
+	assert_eq!(&*page.read(8, 10).await.unwrap(), b"89");
+	assert_eq!(&*page.read(10, 10).await.unwrap(), b"");
+}
+
+#[tokio::test]
+async fn gcs_directory() {
+	let directory = directory("gcs_directory/");
+	for name in &["a/1", "a/2", "b/1", "c"] {
+		put(directory.file(name), name.as_bytes()).await;
+	}
+
+	let partitions = directory.clone().partitions().await.unwrap();
+	assert_eq!(partitions.len(), 4);
+	let mut contents = Vec::new();
+	for partition in partitions {
+		let page = partition.pages().await.unwrap().pop().unwrap();
+		let len = page.len().await.unwrap();
+		contents.push(page.read(0, len as usize).await.unwrap().into_vec());
+	}
+	contents.sort();
+	assert_eq!(contents, vec![&b"a/1"[..], b"a/2", b"b/1", b"c"]);
+
+	let partitions = directory
+		.partitions_filter(|path| path.iter().next().map_or(true, |first| first != "a"))
+		.await
+		.unwrap();
+	assert_eq!(partitions.len(), 2);
+}
+
+#[tokio::test]
+async fn gcs_errors() {
+	let file = directory("gcs_errors/").file("missing");
+	let err = file.pages().await.err().unwrap();
+	assert_eq!(io::Error::from(err).kind(), io::ErrorKind::NotFound);
+}
@@ -0,0 +1,78 @@
+use std::{env, fs, path::PathBuf};
+
+use amadeus::{
+	amadeus_core::file::{Directory, File}, prelude::*
+};
+
+#[tokio::test]
+async fn glob() {
+	let root = env::temp_dir().join(format!("amadeus-glob-{}", std::process::id()));
+	for path in &[
+		"2020-01-01/a.gz",
+		"2020-01-01/b.csv",
+		"2020-02-01/a.gz",
+		"2020-02-01/nested/c.gz",
+		"2020-05-01/a.gz",
+		"top.gz",
+	] {
+		let path = root.join(path);
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		fs::write(path, b"").unwrap();
+	}
+	let glob = |pattern: &str| Glob::new(&format!("{}/{}", root.display(), pattern)).unwrap();
+	let relative = |paths: Vec<PathBuf>| {
+		paths
+			.into_iter()
+			.map(|path| {
+				path.strip_prefix(&root)
+					.unwrap()
+					.to_str()
+					.unwrap()
+					.to_owned()
+			})
+			.collect::<Vec<_>>()
+	};
+
+	let pattern = glob("2020-0[1-3]-*/*.gz");
+	assert_eq!(pattern.root(), format!("{}/", root.display()));
+	assert_eq!(pattern.prefix(), format!("{}/2020-0", root.display()));
+	assert_eq!(
+		relative(pattern.partitions().await.unwrap()),
+		vec!["2020-01-01/a.gz", "2020-02-01/a.gz"]
+	);
+	assert_eq!(
+		relative(glob("**/*.gz").partitions().await.unwrap()),
+		vec![
+			"2020-01-01/a.gz",
+			"2020-02-01/a.gz",
+			"2020-02-01/nested/c.gz",
+			"2020-05-01/a.gz",
+			"top.gz"
+		]
+	);
+	assert_eq!(
+		relative(glob("*/[!a]*").partitions().await.unwrap()),
+		vec!["2020-01-01/b.csv"]
+	);
+
+	// Directories that can't match aren't descended into
+	let mut visited = Vec::new();
+	let _ = glob("2020-0[1-3]-*/*.gz")
+		.partitions_filter(|path| {
+			visited.push(path.display().to_string());
+			true
+		})
+		.await
+		.unwrap();
+	assert!(visited.iter().all(|path| !path.starts_with("2020-05-01")));
+	assert!(visited
+		.iter()
+		.all(|path| !path.starts_with("2020-02-01/nested")));
+
+	assert!(Glob::new("a[b").is_err());
+	let pattern = glob("**/*.gz");
+	let round_trip: Glob = serde_json::from_str(&serde_json::to_string(&pattern).unwrap()).unwrap();
+	assert_eq!(round_trip, pattern);
+
+	fs::remove_dir_all(root).unwrap();
+}
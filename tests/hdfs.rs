@@ -0,0 +1,371 @@
+use std::{
+	collections::BTreeMap, io::{self, BufRead, BufReader, Read, Write}, net::{SocketAddr, TcpListener, TcpStream}, sync::{Arc, Mutex}, thread
+};
+use url::Url;
+
+use amadeus::{
+	amadeus_core::file::{Directory, File, Page, Partition}, prelude::*
+};
+
+const BLOCK_SIZE: usize = 1000;
+
+#[derive(Default)]
+struct Fs {
+	files: BTreeMap<String, Vec<u8>>,
+	/// The targets of symlinks, which if relative are relative to the
+	/// symlink's directory.
+	links: BTreeMap<String, String>,
+	/// The `(offset, length)` of each `OPEN` request.
+	opens: Vec<(usize, usize)>,
+}
+
+/// Resolve the symlink, if any, that `path` is or is within.
+fn resolve(links: &BTreeMap<String, String>, path: &str) -> String {
+	for (link, target) in links {
+		if path == link || path.starts_with(&format!("{}/", link)) {
+			let target = if target.starts_with('/') {
+				target.clone()
+			} else {
+				format!("{}/{}", &link[..link.rfind('/').unwrap()], target)
+			};
+			return format!("{}{}", target, &path[link.len()..]);
+		}
+	}
+	path.to_owned()
+}
+
+/// A minimal in-memory WebHDFS namenode, that redirects reads and writes to a
+/// "datanode" on the same port. Appends to files named `flaky` take effect but
+/// respond with an error.
+fn serve() -> (SocketAddr, Arc<Mutex<Fs>>) {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let addr = listener.local_addr().unwrap();
+	let fs = Arc::new(Mutex::new(Fs::default()));
+	let fs_ = fs.clone();
+	let _ = thread::spawn(move || {
+		for stream in listener.incoming() {
+			let _ = respond(stream.unwrap(), addr, &fs_);
+		}
+	});
+	(addr, fs)
+}
+fn respond(stream: TcpStream, addr: SocketAddr, fs: &Mutex<Fs>) -> io::Result<()> {
+	let mut reader = BufReader::new(stream.try_clone()?);
+	let mut request = String::new();
+	let _ = reader.read_line(&mut request)?;
+	let mut content_length = 0;
+	loop {
+		let mut line = String::new();
+		let _ = reader.read_line(&mut line)?;
+		let line = line.trim_end().to_ascii_lowercase();
+		if line.is_empty() {
+			break;
+		}
+		if line.starts_with("content-length: ") {
+			content_length = line["content-length: ".len()..].parse().unwrap();
+		}
+	}
+	let mut body = vec![0; content_length];
+	reader.read_exact(&mut body)?;
+
+	let mut request = request.split(' ');
+	let (method, url) = (request.next().unwrap(), request.next().unwrap());
+	let url = Url::parse(&format!("http://{}{}", addr, url)).unwrap();
+	let query = url.query_pairs().into_owned().collect::<BTreeMap<_, _>>();
+	let op = query.get("op").map_or("", String::as_str);
+	let mut fs = fs.lock().unwrap();
+	let (status, headers, body) = if url.path().starts_with("/datanode/") {
+		let path = &query["path"];
+		match (method, op) {
+			("GET", "OPEN") => {
+				let file = &fs.files[path];
+				let offset: usize = query["offset"].parse().unwrap();
+				let length: usize = query["length"].parse().unwrap();
+				let end = file.len().min(offset + length);
+				("200 OK", String::new(), file[offset..end].to_vec())
+			}
+			("PUT", "CREATE") => {
+				let _ = fs.files.insert(path.clone(), body);
+				("201 Created", String::new(), Vec::new())
+			}
+			("POST", "APPEND") => {
+				fs.files.get_mut(path).unwrap().extend(body);
+				if path.ends_with("/flaky") {
+					("503 Service Unavailable", String::new(), Vec::new())
+				} else {
+					("200 OK", String::new(), Vec::new())
+				}
+			}
+			_ => unreachable!(),
+		}
+	} else {
+		let path = &resolve(&fs.links, &url.path()["/webhdfs/v1".len()..]);
+		let dir = format!("{}/", path.trim_end_matches('/'));
+		let is_dir = fs.files.keys().any(|file| file.starts_with(&dir));
+		let redirect = || {
+			format!(
+				"Location: http://{}/datanode/?op={}&path={}&{}\r\n",
+				addr,
+				op,
+				path,
+				url.query().unwrap()
+			)
+		};
+		let status = |path_suffix: &str, len: usize, type_: &str| {
+			format!(
+				r#"{{"pathSuffix":"{}","type":"{}","length":{},"blockSize":{},"owner":"amadeus","permission":"644"}}"#,
+				path_suffix, type_, len, BLOCK_SIZE
+			)
+		};
+		let symlink_status = |path_suffix: &str, target: &str| {
+			format!(
+				r#"{{"pathSuffix":"{}","type":"SYMLINK","symlink":"{}","length":0,"blockSize":0,"owner":"amadeus","permission":"777"}}"#,
+				path_suffix, target
+			)
+		};
+		let not_found = (
+			"404 Not Found",
+			String::new(),
+			format!(
+				r#"{{"RemoteException":{{"exception":"FileNotFoundException","javaClassName":"java.io.FileNotFoundException","message":"File {} does not exist."}}}}"#,
+				path
+			)
+			.into_bytes(),
+		);
+		match (method, op) {
+			("GET", "LISTSTATUS") if is_dir => {
+				let mut children = BTreeMap::new();
+				for (file, contents) in fs.files.range(dir.clone()..) {
+					if !file.starts_with(&dir) {
+						break;
+					}
+					let mut rest = file[dir.len()..].splitn(2, '/');
+					let (name, nested) = (rest.next().unwrap(), rest.next().is_some());
+					let _ = children.entry(name.to_owned()).or_insert(if nested {
+						None
+					} else {
+						Some(contents.len())
+					});
+				}
+				let mut children = children
+					.into_iter()
+					.map(|(name, len)| match len {
+						Some(len) => (name.clone(), status(&name, len, "FILE")),
+						None => (name.clone(), status(&name, 0, "DIRECTORY")),
+					})
+					.collect::<BTreeMap<_, _>>();
+				for (link, target) in &fs.links {
+					if link.starts_with(&dir) && !link[dir.len()..].contains('/') {
+						let name = &link[dir.len()..];
+						let _ = children.insert(name.to_owned(), symlink_status(name, target));
+					}
+				}
+				let statuses = children
+					.into_iter()
+					.map(|(_, status)| status)
+					.collect::<Vec<_>>()
+					.join(",");
+				let body = format!(r#"{{"FileStatuses":{{"FileStatus":[{}]}}}}"#, statuses);
+				("200 OK", String::new(), body.into_bytes())
+			}
+			("GET", "GETFILESTATUS") => match fs.files.get(path) {
+				Some(file) => {
+					let body = format!(r#"{{"FileStatus":{}}}"#, status("", file.len(), "FILE"));
+					("200 OK", String::new(), body.into_bytes())
+				}
+				None if is_dir => {
+					let body = format!(r#"{{"FileStatus":{}}}"#, status("", 0, "DIRECTORY"));
+					("200 OK", String::new(), body.into_bytes())
+				}
+				None => not_found,
+			},
+			("GET", "GETFILEBLOCKLOCATIONS") if fs.files.contains_key(path) => {
+				let len = fs.files[path].len();
+				let blocks = (0..len)
+					.step_by(BLOCK_SIZE)
+					.enumerate()
+					.map(|(i, offset)| {
+						format!(
+							r#"{{"offset":{},"length":{},"hosts":["datanode{}"],"names":[],"corrupt":false}}"#,
+							offset,
+							BLOCK_SIZE.min(len - offset),
+							i % 3
+						)
+					})
+					.collect::<Vec<_>>()
+					.join(",");
+				let body = format!(r#"{{"BlockLocations":{{"BlockLocation":[{}]}}}}"#, blocks);
+				("200 OK", String::new(), body.into_bytes())
+			}
+			("GET", "OPEN") if fs.files.contains_key(path) => {
+				let offset = query["offset"].parse().unwrap();
+				let length = query["length"].parse().unwrap();
+				fs.opens.push((offset, length));
+				("307 Temporary Redirect", redirect(), Vec::new())
+			}
+			("PUT", "CREATE") => ("307 Temporary Redirect", redirect(), Vec::new()),
+			("POST", "APPEND") if fs.files.contains_key(path) => {
+				("307 Temporary Redirect", redirect(), Vec::new())
+			}
+			_ => not_found,
+		}
+	};
+	write!(
+		&stream,
+		"HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+		status,
+		headers,
+		body.len()
+	)?;
+	(&stream).write_all(&body)
+}
+
+#[derive(Data, Clone, PartialEq, PartialOrd, Debug)]
+struct Row {
+	a: String,
+	b: u32,
+}
+
+async fn put(file: HdfsFile, data: &[u8]) {
+	let page = file.create();
+	page.write(0, data.to_vec().into_boxed_slice())
+		.await
+		.unwrap();
+	page.flush().await.unwrap();
+}
+
+#[tokio::test]
+async fn hdfs() {
+	let (addr, fs) = serve();
+	let namenode = Url::parse(&format!("http://{}", addr)).unwrap();
+	let directory = HdfsDirectory::new(namenode.clone(), "/data").user("amadeus");
+
+	// Written out of order, and appended once contiguous
+	let page = directory.file("a/1.csv").create();
+	page.write(4, b"b,2\n".to_vec().into_boxed_slice())
+		.await
+		.unwrap();
+	page.write(0, b"a,1\n".to_vec().into_boxed_slice())
+		.await
+		.unwrap();
+	page.flush().await.unwrap();
+	put(directory.file("a/2.csv"), b"c,3\n").await;
+	put(directory.file("b/1.csv"), b"d,4\n").await;
+	put(directory.file("empty.csv"), b"").await;
+	assert_eq!(fs.lock().unwrap().files["/data/a/1.csv"], b"a,1\nb,2\n");
+	assert_eq!(fs.lock().unwrap().files["/data/empty.csv"], b"");
+
+	// Symlinks are followed, but not back into the directory being listed, and
+	// dangling ones are skipped
+	for (link, target) in &[
+		("/data/c", "/data/b"),
+		("/data/d.csv", "a/2.csv"),
+		("/data/loop", "/data"),
+		("/data/dangling", "/missing"),
+	] {
+		let _ = fs
+			.lock()
+			.unwrap()
+			.links
+			.insert((*link).to_owned(), (*target).to_owned());
+	}
+
+	let pool = &ThreadPool::new(None).unwrap();
+	let mut rows = Csv::<_, Row>::new(directory.clone())
+		.await
+		.unwrap()
+		.par_stream()
+		.map(|row: Result<_, _>| row.unwrap().b)
+		.collect::<_, Vec<_>>(pool)
+		.await;
+	rows.sort();
+	assert_eq!(rows, vec![1, 2, 3, 3, 4, 4]);
+
+	let partitions = directory
+		.clone()
+		.partitions_filter(|path| path.iter().next().map_or(true, |first| first != "a"))
+		.await
+		.unwrap();
+	let names = partitions
+		.iter()
+		.map(|partition| partition.name().unwrap())
+		.collect::<Vec<_>>();
+	assert_eq!(
+		names,
+		vec![
+			"/data/b/1.csv",
+			"/data/c/1.csv",
+			"/data/d.csv",
+			"/data/empty.csv"
+		]
+	);
+
+	// Reads are split at block boundaries
+	let data = (0..5500_u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+	put(directory.file("large"), &data).await;
+	let partition = directory
+		.file("large")
+		.partitions()
+		.await
+		.unwrap()
+		.pop()
+		.unwrap();
+	let locations = partition.block_locations().await.unwrap();
+	assert_eq!(locations.len(), 6);
+	assert_eq!(locations[5].offset, 5000);
+	assert_eq!(locations[5].length, 500);
+	assert_eq!(locations[1].hosts, vec!["datanode1"]);
+	assert_eq!(partition.hosts(), ["datanode0", "datanode1", "datanode2"]);
+	let page = partition.pages().await.unwrap().pop().unwrap();
+	assert_eq!(page.len().await.unwrap(), 5500);
+	fs.lock().unwrap().opens.clear();
+	assert_eq!(&*page.read(500, 3000).await.unwrap(), &data[500..3500]);
+	assert_eq!(
+		fs.lock().unwrap().opens,
+		vec![(500, 500), (1000, 1000), (2000, 1000), (3000, 500)]
+	);
+	assert_eq!(&*page.read(5400, 1000).await.unwrap(), &data[5400..]);
+
+	// Or partitioned by block
+	let partitions = directory
+		.clone()
+		.split_blocks(true)
+		.file("large")
+		.partitions()
+		.await
+		.unwrap();
+	assert_eq!(partitions.len(), 6);
+	assert_eq!(partitions[1].hosts(), ["datanode1"]);
+	assert_eq!(partitions[5].hosts(), ["datanode2"]);
+	let page = partitions[1].clone().pages().await.unwrap().pop().unwrap();
+	assert_eq!(page.len().await.unwrap(), 1000);
+	fs.lock().unwrap().opens.clear();
+	assert_eq!(&*page.read(100, 2000).await.unwrap(), &data[1100..2000]);
+	assert_eq!(fs.lock().unwrap().opens, vec![(1100, 900)]);
+	let page = partitions[5].clone().pages().await.unwrap().pop().unwrap();
+	assert_eq!(&*page.read(0, 1000).await.unwrap(), &data[5000..]);
+
+	// Appends aren't retried, but if one fails having taken effect that's
+	// recognised
+	let flaky = HdfsFile::new(namenode.clone(), "/flaky");
+	let page = flaky.clone().create();
+	page.write(0, b"a,1\n".to_vec().into_boxed_slice())
+		.await
+		.unwrap();
+	page.write(4, b"b,2\n".to_vec().into_boxed_slice())
+		.await
+		.unwrap();
+	page.flush().await.unwrap();
+	assert_eq!(fs.lock().unwrap().files["/flaky"], b"a,1\nb,2\n");
+
+	let err = HdfsFile::new(namenode, "/missing")
+		.partitions()
+		.await
+		.unwrap_err();
+	assert_eq!(io::Error::from(err).kind(), io::ErrorKind::NotFound);
+
+	// Gaps are an error
+	let page = directory.file("gap").create();
+	page.write(1, vec![0].into_boxed_slice()).await.unwrap();
+	assert!(page.flush().await.is_err());
+}
@@ -0,0 +1,173 @@
+use std::{
+	io::{self, BufRead, BufReader, Write}, net::{SocketAddr, TcpListener}, sync::atomic::{AtomicUsize, Ordering}, thread
+};
+use url::Url;
+
+use amadeus::{
+	amadeus_core::file::{Directory, Page, Partition}, prelude::*
+};
+
+#[derive(Data, Clone, PartialEq, PartialOrd, Debug)]
+struct Row {
+	a: String,
+	b: u32,
+}
+
+fn data() -> Vec<u8> {
+	(0..100_000_u32).map(|i| (i % 251) as u8).collect()
+}
+
+/// A minimal HTTP server. Paths under `/flaky/` have their responses cut off
+/// after 1000 bytes, paths under `/norange/` ignore `Range` headers and don't
+/// send `Accept-Ranges`, paths under `/ignorerange/` send it but ignore
+/// `Range` headers anyway, paths under `/nohead/` reject `HEAD` requests, and
+/// paths under `/chunked/` send chunked responses without a `Content-Length`.
+fn serve() -> SocketAddr {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let addr = listener.local_addr().unwrap();
+	let _ = thread::spawn(move || {
+		for stream in listener.incoming() {
+			let _ = respond(stream.unwrap());
+		}
+	});
+	addr
+}
+/// How many `GET` requests have been made for paths under `/norange/`.
+static NORANGE_GETS: AtomicUsize = AtomicUsize::new(0);
+
+fn respond(stream: std::net::TcpStream) -> io::Result<()> {
+	let mut reader = BufReader::new(stream.try_clone()?);
+	let mut request = String::new();
+	let _ = reader.read_line(&mut request)?;
+	let mut range = None;
+	loop {
+		let mut line = String::new();
+		let _ = reader.read_line(&mut line)?;
+		let line = line.trim_end();
+		if line.is_empty() {
+			break;
+		}
+		let lowercase = line.to_ascii_lowercase();
+		if lowercase.starts_with("range: bytes=") {
+			let mut bounds = line["range: bytes=".len()..].split('-');
+			let start: usize = bounds.next().unwrap().parse().unwrap();
+			let end: usize = bounds.next().unwrap().parse().unwrap();
+			range = Some((start, end));
+		}
+	}
+	let mut request = request.split(' ');
+	let (method, path) = (request.next().unwrap(), request.next().unwrap());
+	if method == "HEAD" && path.starts_with("/nohead/") {
+		return write!(
+			&stream,
+			"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+		);
+	}
+	if method == "GET" && path.starts_with("/norange/") {
+		let _ = NORANGE_GETS.fetch_add(1, Ordering::Relaxed);
+	}
+	let body = match path.rsplit('/').next().unwrap() {
+		"file" => data(),
+		"rows.csv" => b"a,1\nb,2\n".to_vec(),
+		_ => {
+			return write!(
+				&stream,
+				"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+			)
+		}
+	};
+	let (status, body) = match range {
+		Some((start, end))
+			if !path.starts_with("/norange/") && !path.starts_with("/ignorerange/") =>
+		{
+			("206 Partial Content", &body[start..=end])
+		}
+		_ => ("200 OK", &body[..]),
+	};
+	let accept_ranges = if path.starts_with("/norange/") {
+		""
+	} else {
+		"Accept-Ranges: bytes\r\n"
+	};
+	if path.starts_with("/chunked/") {
+		write!(
+			&stream,
+			"HTTP/1.1 {}\r\n{}Transfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+			status, accept_ranges
+		)?;
+		if method == "GET" {
+			for chunk in body.chunks(4096) {
+				write!(&stream, "{:x}\r\n", chunk.len())?;
+				(&stream).write_all(chunk)?;
+				write!(&stream, "\r\n")?;
+			}
+			write!(&stream, "0\r\n\r\n")?;
+		}
+		return Ok(());
+	}
+	write!(
+		&stream,
+		"HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+		status,
+		accept_ranges,
+		body.len()
+	)?;
+	if method == "GET" {
+		let body = if path.starts_with("/flaky/") {
+			&body[..body.len().min(1000)]
+		} else {
+			body
+		};
+		(&stream).write_all(body)?;
+	}
+	Ok(())
+}
+
+#[tokio::test]
+async fn http() {
+	let addr = serve();
+	let url = |path: &str| Url::parse(&format!("http://{}/{}", addr, path)).unwrap();
+	let data = data();
+
+	for path in &[
+		"file",
+		"flaky/file",
+		"norange/file",
+		"ignorerange/file",
+		"nohead/file",
+		"chunked/file",
+	] {
+		let page = HttpFile::new(url(path))
+			.pages()
+			.await
+			.unwrap()
+			.pop()
+			.unwrap();
+		assert_eq!(page.len().await.unwrap(), data.len() as u64);
+		assert_eq!(&*page.read(1000, 5000).await.unwrap(), &data[1000..6000]);
+		assert_eq!(&*page.read(99_990, 100).await.unwrap(), &data[99_990..]);
+		assert!(page.write(0, vec![0].into_boxed_slice()).await.is_err());
+	}
+	// Without range support the file is downloaded once, rather than once per read
+	assert_eq!(NORANGE_GETS.load(Ordering::Relaxed), 1);
+
+	let err = HttpFile::new(url("missing")).pages().await.err().unwrap();
+	assert_eq!(io::Error::from(err).kind(), io::ErrorKind::NotFound);
+
+	let pool = &ThreadPool::new(None).unwrap();
+	let directory = HttpDirectory::new(vec![url("a/rows.csv"), url("b/rows.csv")]);
+	let rows = Csv::<_, Row>::new(directory.clone())
+		.await
+		.unwrap()
+		.par_stream()
+		.map(|row: Result<_, _>| row.unwrap())
+		.count(pool)
+		.await;
+	assert_eq!(rows, 4);
+
+	let partitions = directory
+		.partitions_filter(|path| path.iter().next().map_or(true, |first| first != "a"))
+		.await
+		.unwrap();
+	assert_eq!(partitions, vec![HttpFile::new(url("b/rows.csv"))]);
+}
@@ -1,6 +1,8 @@
 use either::Either;
+use futures::stream;
+use std::time::Duration;
 
-use amadeus::prelude::*;
+use amadeus::{par_stream::Position, prelude::*};
 
 #[tokio::test]
 async fn into_par_stream() {
@@ -33,3 +35,461 @@ async fn into_par_stream() {
 	let sum: usize = slice.iter().cloned().par().sum(&pool).await;
 	assert_eq!(sum, slice.iter().sum::<usize>());
 }
+
+#[tokio::test]
+async fn enumerate_zip() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	let mut res = (0..1000_usize)
+		.par()
+		.positions()
+		.map(|(position, i): (Position, usize)| (position.partition, position.offset, i))
+		.collect::<_, Vec<_>>(pool)
+		.await;
+	res.sort();
+	assert_eq!(res, (0..1000_usize).map(|i| (i, 0, i)).collect::<Vec<_>>());
+
+	let mut res = (0..1000_usize)
+		.flat_map(|i| (0..i % 5).map(move |j| (i, j)))
+		.par()
+		.enumerate(pool)
+		.await
+		.collect::<_, Vec<_>>(pool)
+		.await;
+	res.sort();
+	assert_eq!(
+		res,
+		(0..1000_usize)
+			.flat_map(|i| (0..i % 5).map(move |j| (i, j)))
+			.enumerate()
+			.collect::<Vec<_>>()
+	);
+
+	let mut res = (0..1000_usize)
+		.par()
+		.zip((0..1000_usize).map(|i| i * 2).par())
+		.collect::<_, Vec<_>>(pool)
+		.await;
+	res.sort();
+	assert_eq!(res, (0..1000_usize).map(|i| (i, i * 2)).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn collect_ordered() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	let res = (0..1000_usize)
+		.par()
+		.flat_map(|i: usize| stream::iter((0..i % 5).map(move |j| (i, j))))
+		.collect_ordered::<_, Vec<_>>(pool)
+		.await;
+	assert_eq!(
+		res,
+		(0..1000_usize)
+			.flat_map(|i| (0..i % 5).map(move |j| (i, j)))
+			.collect::<Vec<_>>()
+	);
+}
+
+#[tokio::test]
+async fn take() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	let res = (0..100_000_usize)
+		.par()
+		.take(pool, 100)
+		.await
+		.collect_ordered::<_, Vec<_>>(pool)
+		.await;
+	assert_eq!(res, (0..100_usize).collect::<Vec<_>>());
+
+	let res = (0..1000_usize)
+		.filter(|i| i % 3 == 0)
+		.par()
+		.limit(pool, 10, 5)
+		.await
+		.collect_ordered::<_, Vec<_>>(pool)
+		.await;
+	assert_eq!(res, vec![30, 33, 36, 39, 42]);
+
+	let res = (0..10_usize)
+		.par()
+		.take(pool, 100)
+		.await
+		.collect_ordered::<_, Vec<_>>(pool)
+		.await;
+	assert_eq!(res, (0..10_usize).collect::<Vec<_>>());
+
+	let res = (0..1000_usize)
+		.flat_map(|i| (0..i % 5).map(move |j| (i, j)))
+		.par()
+		.skip(pool, 1500)
+		.await
+		.collect_ordered::<_, Vec<_>>(pool)
+		.await;
+	assert_eq!(
+		res,
+		(0..1000_usize)
+			.flat_map(|i| (0..i % 5).map(move |j| (i, j)))
+			.skip(1500)
+			.collect::<Vec<_>>()
+	);
+
+	let res = (0..10_usize)
+		.par()
+		.skip(pool, 100)
+		.await
+		.collect_ordered::<_, Vec<_>>(pool)
+		.await;
+	assert_eq!(res, Vec::<usize>::new());
+
+	assert!((0..100_000_usize).par().any(pool, |i: usize| i == 10).await);
+}
+
+#[tokio::test]
+async fn map_async() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	let res = (0..1000_usize)
+		.par()
+		.flat_map(|i: usize| stream::iter(i * 10..i * 10 + 10))
+		.map_async(4, |i: usize| async move { i * 2 })
+		.filter_async(4, |i: &usize| {
+			let i = *i;
+			async move { i % 3 == 0 }
+		})
+		.collect_ordered::<_, Vec<_>>(pool)
+		.await;
+	assert_eq!(
+		res,
+		(0..10_000_usize)
+			.map(|i| i * 2)
+			.filter(|i| i % 3 == 0)
+			.collect::<Vec<_>>()
+	);
+}
+
+#[tokio::test]
+async fn chunks() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	let res = (0..100_usize)
+		.par()
+		.flat_map(|i: usize| stream::iter(i * 10..i * 10 + 10))
+		.chunks(4)
+		.collect_ordered::<_, Vec<_>>(pool)
+		.await;
+	// Chunks don't span tasks, so each task's last chunk is short
+	assert_eq!(
+		res,
+		(0..100_usize)
+			.flat_map(|i| vec![
+				(i * 10..i * 10 + 4).collect::<Vec<_>>(),
+				(i * 10 + 4..i * 10 + 8).collect(),
+				(i * 10 + 8..i * 10 + 10).collect(),
+			])
+			.collect::<Vec<_>>()
+	);
+
+	let res = (0..100_usize)
+		.par()
+		.flat_map(|i: usize| stream::iter(i * 10..i * 10 + 10))
+		.chunks_timeout(4, Duration::from_secs(60))
+		.flatten()
+		.collect_ordered::<_, Vec<_>>(pool)
+		.await;
+	assert_eq!(res, (0..1000_usize).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn quantiles() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	let res = (0..100_000_u32)
+		.par()
+		.map(|i: u32| f64::from(i))
+		.quantiles(pool, &[0.0, 0.5, 0.9, 0.99, 1.0])
+		.await;
+	assert_eq!(res[0], 0.0);
+	assert_eq!(res[4], 99_999.0);
+	for (&estimate, &q) in res[1..4].iter().zip(&[0.5, 0.9, 0.99]) {
+		assert!(
+			(estimate / 100_000.0 - q).abs() < 0.01,
+			"{}: {}",
+			q,
+			estimate
+		);
+	}
+
+	let res = Vec::<f64>::new()
+		.into_par_stream()
+		.quantiles(pool, &[0.5])
+		.await;
+	assert!(res[0].is_nan());
+}
+
+#[tokio::test]
+async fn covariance() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	let pairs = (0..1_000_u32)
+		.map(|i| (f64::from(i), 2.0 * f64::from(i) + 1.0))
+		.collect::<Vec<_>>();
+	let variance = (1_000.0_f64.powi(2) - 1.0) / 12.0;
+	let res = pairs.clone().into_par_stream().covariance(pool).await;
+	assert!((res - 2.0 * variance).abs() < 1e-6, "{}", res);
+	let res = pairs.into_par_stream().pearson_correlation(pool).await;
+	assert!((res - 1.0).abs() < 1e-9, "{}", res);
+
+	let res = (0..1_000_u32)
+		.par()
+		.map(|i: u32| {
+			let (x1, x2) = (f64::from(i), f64::from(i * 7 % 13));
+			(vec![x1, x2], 3.0 + 2.0 * x1 - 0.5 * x2)
+		})
+		.linear_regression(pool)
+		.await;
+	assert_eq!(res.len(), 3, "{:?}", res);
+	for (coefficient, expected) in res.into_iter().zip(&[3.0, 2.0, -0.5]) {
+		assert!((coefficient - expected).abs() < 1e-6, "{}", coefficient);
+	}
+
+	let res = Vec::<(Vec<f64>, f64)>::new()
+		.into_par_stream()
+		.linear_regression(pool)
+		.await;
+	assert!(res.is_empty());
+}
+
+#[tokio::test]
+async fn moments() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	let values = (0..10_000_u32)
+		.map(|i| 1_000_000_000 + u64::from(i * i % 1_013))
+		.collect::<Vec<_>>();
+	let count = values.len() as f64;
+	let mean = values.iter().map(|&x| x as f64).sum::<f64>() / count;
+	let moment = |p: i32| {
+		values
+			.iter()
+			.map(|&x| (x as f64 - mean).powi(p))
+			.sum::<f64>()
+			/ count
+	};
+	let (variance, m3, m4) = (moment(2), moment(3), moment(4));
+
+	let res = values.clone().into_par_stream().moments(pool).await;
+	assert_eq!(res.count, 10_000);
+	assert!((res.mean - mean).abs() < 1e-3, "{:?}", res);
+	assert!((res.variance / variance - 1.0).abs() < 1e-9, "{:?}", res);
+	assert!(
+		(res.skewness - m3 / variance.powf(1.5)).abs() < 1e-6,
+		"{:?}",
+		res
+	);
+	assert!(
+		(res.kurtosis - (m4 / (variance * variance) - 3.0)).abs() < 1e-6,
+		"{:?}",
+		res
+	);
+
+	let res = values
+		.into_par_stream()
+		.map(|x: u64| x as f64)
+		.stddev(pool)
+		.await;
+	assert!((res / variance.sqrt() - 1.0).abs() < 1e-6, "{}", res);
+
+	let res = Vec::<f32>::new().into_par_stream().moments(pool).await;
+	assert_eq!(res.count, 0);
+	assert!(res.mean.is_nan());
+}
+
+#[tokio::test]
+async fn histogram_buckets() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	let res = (0..100_u32)
+		.par()
+		.histogram_buckets(pool, &[0.0, 10.0, 50.0, 99.0])
+		.await;
+	assert_eq!(
+		res.iter().map(|bucket| bucket.count).collect::<Vec<_>>(),
+		[10, 40, 50]
+	);
+
+	let res = (0..1_000_u64).par().histogram_log(pool, 1.0, 10.0).await;
+	assert_eq!(
+		res.iter()
+			.map(|bucket| (bucket.lower, bucket.count))
+			.collect::<Vec<_>>(),
+		[(0.0, 1), (1.0, 9), (10.0, 90), (100.0, 900)]
+	);
+
+	// NaN and negative values aren't counted, and huge ones don't overflow
+	let res = vec![-1.0, f64::NAN, 0.5, 2.0, 1e300]
+		.into_par_stream()
+		.histogram_log(pool, 1.0, 1.0001)
+		.await;
+	assert_eq!(
+		res.iter().map(|bucket| bucket.count).collect::<Vec<_>>(),
+		[1, 1, 1]
+	);
+	assert!(
+		res[2].lower <= 1e300 && 1e300 < res[2].upper,
+		"{:?}",
+		res[2]
+	);
+
+	let res = (0..100_000_u32).par().histogram_auto(pool, 4).await;
+	assert_eq!(res.len(), 4);
+	assert_eq!(res[0].lower, 0.0);
+	assert_eq!(res[3].upper, 99_999.0);
+	assert_eq!(res.iter().map(|bucket| bucket.count).sum::<u64>(), 100_000);
+	for bucket in &res {
+		assert!(
+			(bucket.count as f64 / 25_000.0 - 1.0).abs() < 0.02,
+			"{:?}",
+			bucket
+		);
+	}
+}
+
+#[tokio::test]
+#[should_panic(expected = "base is too close to 1")]
+async fn histogram_log_base() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	let _ = (0..1_000_u64)
+		.par()
+		.histogram_log(pool, 1e-300, 1.0 + 1e-9)
+		.await;
+}
+
+#[tokio::test]
+async fn sample() {
+	let pool = &ThreadPool::new(None).unwrap();
+	let pool_coarse = &ThreadPool::new(Some(1)).unwrap();
+
+	let res = (0..10_000_u32)
+		.par()
+		.sample(pool, 100, 42)
+		.await
+		.into_iter()
+		.collect::<Vec<_>>();
+	assert_eq!(res.len(), 100);
+	let res_coarse = (0..10_000_u32)
+		.par()
+		.sample(pool_coarse, 100, 42)
+		.await
+		.into_iter()
+		.collect::<Vec<_>>();
+	assert_eq!(res, res_coarse);
+	let res_seed = (0..10_000_u32)
+		.par()
+		.sample(pool, 100, 43)
+		.await
+		.into_iter()
+		.collect::<Vec<_>>();
+	assert_ne!(res, res_seed);
+
+	let res = (0..10_000_u32)
+		.par()
+		.sample_weighted(pool, 10, 42, |&i: &u32| if i < 10 { 1e9 } else { 1.0 })
+		.await
+		.into_iter()
+		.collect::<Vec<_>>();
+	assert!(res.iter().all(|&i| i < 10), "{:?}", res);
+
+	let res = (0..10_000_u32)
+		.par()
+		.map(|i: u32| (i % 3, i))
+		.sample_stratified(pool, 5, 42)
+		.await;
+	assert_eq!(res.len(), 3);
+	for (stratum, sample) in res {
+		let sample = sample.into_iter().collect::<Vec<_>>();
+		assert_eq!(sample.len(), 5);
+		assert!(sample.iter().all(|i| i % 3 == stratum));
+	}
+}
+
+#[tokio::test]
+async fn sample_fraction() {
+	let pool = &ThreadPool::new(None).unwrap();
+	let pool_coarse = &ThreadPool::new(Some(1)).unwrap();
+
+	let mut res = (0..100_000_u32)
+		.par()
+		.sample_fraction(0.01, 42)
+		.collect::<_, Vec<_>>(pool)
+		.await;
+	assert!((900..1_100).contains(&res.len()), "{}", res.len());
+	let mut res_coarse = (0..100_000_u32)
+		.par()
+		.sample_fraction(0.01, 42)
+		.collect::<_, Vec<_>>(pool_coarse)
+		.await;
+	res.sort_unstable();
+	res_coarse.sort_unstable();
+	assert_eq!(res, res_coarse);
+
+	let res = (0..1_000_u32)
+		.par()
+		.map(|i: u32| i * 2)
+		.sample_fraction(1.0, 42)
+		.count(pool)
+		.await;
+	assert_eq!(res, 1_000);
+	let res = (0..1_000_u32)
+		.par()
+		.sample_fraction(0.0, 42)
+		.count(pool)
+		.await;
+	assert_eq!(res, 0);
+}
+
+#[tokio::test]
+async fn top_k_by() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	let res = (0..10_000_u64)
+		.par()
+		.top_k_by(
+			pool,
+			|&i: &u64| i % 10,
+			|&i: &u64| i % 10 * 1_000 + 1,
+			3,
+			0.99,
+			0.002,
+		)
+		.await;
+	assert_eq!(
+		res.iter().map(|(&key, _)| key).collect::<Vec<_>>(),
+		[9, 8, 7]
+	);
+	// Count-min sketches never underestimate
+	let (_, &weight) = res.iter().next().unwrap();
+	assert!(weight >= 9_001_000, "{}", weight);
+}
+
+#[tokio::test]
+async fn bloom_filter() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	// Semi-join: keep the rows of one dataset whose key is in another
+	let keys = (0..10_000_u64)
+		.par()
+		.map(|i: u64| i * 3)
+		.bloom_filter(pool, 10_000, 0.01)
+		.await;
+	let res = (0..30_000_u64)
+		.par()
+		.filter(move |i: &u64| keys.contains(i))
+		.collect::<_, Vec<_>>(pool)
+		.await;
+	assert!(res.len() >= 10_000);
+	assert!(res.len() < 10_400, "{}", res.len());
+	assert!((0..30_000).step_by(3).all(|i| res.contains(&i)));
+}
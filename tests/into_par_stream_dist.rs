@@ -1,17 +1,42 @@
+#[cfg(feature = "constellation")]
+use constellation::*;
 use either::Either;
+use std::{
+	sync::atomic::{AtomicUsize, Ordering}, thread, time::Duration
+};
 
 use amadeus::dist::prelude::*;
 
-#[tokio::main]
-async fn main() {
-	let pool = ThreadPool::new(None).unwrap();
+fn main() {
+	#[cfg(feature = "constellation")]
+	init(Resources::default());
 
+	tokio::runtime::Builder::new()
+		.threaded_scheduler()
+		.enable_all()
+		.build()
+		.unwrap()
+		.block_on(async {
+			let thread_pool = ThreadPool::new(None).unwrap();
+			run(&thread_pool).await;
+			cancellation(&thread_pool).await;
+
+			#[cfg(feature = "constellation")]
+			{
+				let process_pool = ProcessPool::new(None, None, Resources::default()).unwrap();
+				run(&process_pool).await;
+				cancellation(&process_pool).await;
+			}
+		})
+}
+
+async fn run<P: amadeus_core::pool::ProcessPool>(pool: &P) {
 	<&[usize] as IntoDistributedStream>::into_dist_stream(&[1, 2, 3])
 		.map(FnMut!(|a: usize| a))
-		.for_each(&pool, FnMut!(|a: usize| println!("{:?}", a)))
+		.for_each(pool, FnMut!(|a: usize| println!("{:?}", a)))
 		.await;
 
-	let res: usize = [1, 2, 3].into_dist_stream().sum(&pool).await;
+	let res: usize = [1, 2, 3].into_dist_stream().sum(pool).await;
 	assert_eq!(res, 6);
 
 	let slice = [
@@ -23,13 +48,59 @@ async fn main() {
 			.dist_stream()
 			.into_dist_stream()
 			.fold(
-				&pool,
+				pool,
 				FnMut!(|| 0_usize),
 				FnMut!(|a: usize, b: Either<usize, usize>| a + b.into_inner()),
 			)
 			.await;
 		assert_eq!(res, slice[..i].iter().sum::<usize>());
 	}
-	let sum: usize = slice.iter().cloned().dist().sum(&pool).await;
+	let sum: usize = slice.iter().cloned().dist().sum(pool).await;
 	assert_eq!(sum, slice.iter().sum::<usize>());
 }
+
+/// Items inspected so far. Only those processed in this process are counted,
+/// so this stays at 0 for a `ProcessPool`.
+static PROCESSED: AtomicUsize = AtomicUsize::new(0);
+
+/// Reductions answered early stop the remaining work, which at 100µs an item
+/// would otherwise take 10s of CPU time.
+async fn cancellation<P: amadeus_core::pool::ProcessPool>(pool: &P) {
+	let slow = FnMut!(|_: &usize| {
+		PROCESSED.fetch_add(1, Ordering::Relaxed);
+		thread::sleep(Duration::from_micros(100));
+	});
+
+	PROCESSED.store(0, Ordering::Relaxed);
+	assert!(
+		(0..100_000_usize)
+			.dist()
+			.inspect(slow.clone())
+			.any(pool, FnMut!(|i: usize| i == 10))
+			.await
+	);
+	assert!(PROCESSED.load(Ordering::Relaxed) < 50_000);
+
+	PROCESSED.store(0, Ordering::Relaxed);
+	let res = (0..100_000_usize)
+		.dist()
+		.take(pool, 100)
+		.await
+		.inspect(slow.clone())
+		.collect_ordered::<_, Vec<_>>(pool)
+		.await;
+	assert_eq!(res, (0..100_usize).collect::<Vec<_>>());
+	assert!(PROCESSED.load(Ordering::Relaxed) <= 100);
+
+	PROCESSED.store(0, Ordering::Relaxed);
+	let res = (0..100_000_usize)
+		.filter(|i| i % 3 == 0)
+		.dist()
+		.limit(pool, 10, 5)
+		.await
+		.inspect(slow)
+		.collect_ordered::<_, Vec<_>>(pool)
+		.await;
+	assert_eq!(res, vec![30, 33, 36, 39, 42]);
+	assert!(PROCESSED.load(Ordering::Relaxed) <= 5);
+}
@@ -1,8 +1,10 @@
 #![allow(clippy::suspicious_map)]
 
-use std::{path::PathBuf, time::SystemTime};
+use std::{env, fs, path::PathBuf, process, time::SystemTime};
 
-use amadeus::prelude::*;
+use amadeus::{
+	data::{Aggregate, Aggregations}, prelude::*
+};
 
 #[tokio::test]
 async fn json() {
@@ -82,6 +84,180 @@ async fn json() {
 		3_605 * tasks
 	);
 	println!("b: {:?}", b.elapsed().unwrap());
+	let c = SystemTime::now();
+
+	let rows = Json::<_, Value>::new(vec![
+		PathBuf::from("amadeus-testing/json/bitcoin2.json");
+		tasks
+	])
+	.await
+	.unwrap();
+	let stats = rows
+		.par_stream()
+		.map(|row: Result<Value, _>| row.unwrap())
+		.pipe(
+			pool,
+			Aggregations::new()
+				.aggregate("txCount", Aggregate::Count)
+				.aggregate("txCount", Aggregate::Mean)
+				.aggregate("fees", Aggregate::Min)
+				.aggregate("date", Aggregate::Distinct)
+				.aggregate("missing", Aggregate::Max),
+		)
+		.await;
+	assert_eq!(
+		stats.get("count(txCount)"),
+		Some(&Value::U64(3_605 * tasks as u64))
+	);
+	let bitcoin =
+		Json::<_, BitcoinDerived>::new(PathBuf::from("amadeus-testing/json/bitcoin2.json"))
+			.await
+			.unwrap()
+			.par_stream()
+			.map(|row: Result<_, _>| row.unwrap())
+			.collect::<_, Vec<_>>(pool)
+			.await;
+	let number = |value: &Value| match value.clone().into_option().unwrap().unwrap() {
+		Value::U64(n) => n as f64,
+		Value::I64(n) => n as f64,
+		Value::F64(n) => n,
+		value => panic!("not a number: {:?}", value),
+	};
+	let mean = bitcoin
+		.iter()
+		.map(|row| f64::from(row.tx_count))
+		.sum::<f64>()
+		/ bitcoin.len() as f64;
+	let mean_ = number(stats.get("mean(txCount)").unwrap());
+	assert!((mean_ / mean - 1.0).abs() < 1e-9, "{} {}", mean_, mean);
+	let min = bitcoin
+		.iter()
+		.map(|row| row.fees)
+		.fold(f64::INFINITY, f64::min);
+	assert_eq!(number(stats.get("min(fees)").unwrap()), min);
+	let distinct = stats.get("distinct(date)").unwrap().as_f64().unwrap();
+	assert!((distinct / 3_605.0 - 1.0).abs() < 0.05, "{}", distinct);
+	assert_eq!(stats.get("max(missing)"), Some(&Value::Option(None)));
+	println!("c: {:?}", c.elapsed().unwrap());
+	let d = SystemTime::now();
+
+	let rows = Json::<_, Value>::new(vec![
+		PathBuf::from("amadeus-testing/json/bitcoin2.json");
+		tasks
+	])
+	.await
+	.unwrap();
+	let description = rows
+		.par_stream()
+		.map(|row: Result<Value, _>| row.unwrap())
+		.describe(pool, 5)
+		.await;
+	assert_eq!(description.rows, 3_605 * tasks as u64);
+	let date = description
+		.columns
+		.iter()
+		.find(|column| column.name == "date")
+		.unwrap();
+	assert_eq!(date.nulls, 0);
+	assert_eq!(date.types.len(), 1);
+	assert!(
+		(date.distinct / 3_605.0 - 1.0).abs() < 0.05,
+		"{}",
+		date.distinct
+	);
+	assert_eq!(date.top.len(), 5);
+	println!("d: {:?}", d.elapsed().unwrap());
 
 	println!("in {:?}", start.elapsed().unwrap());
 }
+
+#[tokio::test]
+async fn aggregations() {
+	let pool = &ThreadPool::new(None).unwrap();
+
+	let path = env::temp_dir().join(format!("amadeus-aggregations-{}.json", process::id()));
+	fs::write(
+		&path,
+		r#"{"a": 1, "b": "x", "c": 1}
+		{"a": 2, "b": "y", "c": -2}
+		{"a": 4, "b": null, "c": 3.5}
+		{"a": null, "b": "x", "c": "x"}
+		{"a": 5, "b": "z", "c": 2}"#,
+	)
+	.unwrap();
+	let rows = Json::<_, Value>::new(vec![path.clone(); 3]).await.unwrap();
+	let stats = rows
+		.par_stream()
+		.map(|row: Result<Value, _>| row.unwrap())
+		.pipe(
+			pool,
+			Aggregations::new()
+				.aggregate("a", Aggregate::Count)
+				.aggregate("a", Aggregate::Sum)
+				.aggregate("a", Aggregate::Mean)
+				.aggregate("a", Aggregate::StdDev)
+				.aggregate("a", Aggregate::Min)
+				.aggregate("a", Aggregate::Max)
+				.aggregate("b", Aggregate::Min)
+				.aggregate("b", Aggregate::Distinct)
+				.aggregate("b", Aggregate::Mean)
+				.aggregate("c", Aggregate::Min)
+				.aggregate("c", Aggregate::Max)
+				.aggregate("c", Aggregate::Mean),
+		)
+		.await;
+	assert_eq!(stats.get("count(a)"), Some(&Value::U64(12)));
+	assert_eq!(stats.get("sum(a)"), Some(&Value::F64(36.0)));
+	assert_eq!(stats.get("mean(a)"), Some(&Some(3.0_f64).into()));
+	let stddev = stats.get("stddev(a)").unwrap().clone();
+	let stddev = Option::<f64>::downcast_from(stddev).unwrap().unwrap();
+	assert!((stddev - 2.5_f64.sqrt()).abs() < 1e-9, "{}", stddev);
+	assert_eq!(stats.get("min(a)"), Some(&Some(Value::U64(1)).into()));
+	assert_eq!(stats.get("max(a)"), Some(&Some(Value::U64(5)).into()));
+	assert_eq!(
+		stats.get("min(b)"),
+		Some(&Some(Value::String(String::from("x"))).into())
+	);
+	let distinct = stats.get("distinct(b)").unwrap().as_f64().unwrap();
+	assert!((distinct - 3.0).abs() < 0.1, "{}", distinct);
+	assert_eq!(stats.get("mean(b)"), Some(&Value::Option(None)));
+	// Numbers of different types compare by value, and before other types
+	assert_eq!(stats.get("min(c)"), Some(&Some(Value::I64(-2)).into()));
+	assert_eq!(
+		stats.get("max(c)"),
+		Some(&Some(Value::String(String::from("x"))).into())
+	);
+	assert_eq!(stats.get("mean(c)"), Some(&Some(1.125_f64).into()));
+
+	let rows = Json::<_, Value>::new(vec![path.clone(); 3]).await.unwrap();
+	let description = rows
+		.par_stream()
+		.map(|row: Result<Value, _>| row.unwrap())
+		.describe(pool, 2)
+		.await;
+	assert_eq!(description.rows, 15);
+	let names = description
+		.columns
+		.iter()
+		.map(|column| &*column.name)
+		.collect::<Vec<_>>();
+	assert_eq!(names, ["a", "b", "c"]);
+	let a = &description.columns[0];
+	assert_eq!(a.nulls, 3);
+	assert_eq!(a.mean, Some(3.0));
+	assert_eq!(a.min, Some(Value::U64(1)));
+	assert_eq!(a.max, Some(Value::U64(5)));
+	let b = &description.columns[1];
+	assert_eq!(b.types, [(String::from("string"), 12)]);
+	assert_eq!(b.top[0], (Value::String(String::from("x")), 6));
+
+	fs::remove_file(path).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "mean(a) has already been added")]
+fn aggregations_duplicate() {
+	let _ = Aggregations::new()
+		.aggregate("a", Aggregate::Mean)
+		.aggregate("a", Aggregate::Mean);
+}
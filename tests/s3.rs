@@ -0,0 +1,178 @@
+//! These tests run against a local S3-compatible store such as [MinIO](https://min.io/), e.g.
+//! `docker run -p 9000:9000 minio/minio server /data`, with a bucket named `amadeus`. Set
+//! `AMADEUS_S3_ENDPOINT` to use a store other than `http://localhost:9000`.
+//!
+//! They're `test = false`, so aren't run by a plain `cargo test`. Run them with
+//! `cargo test --features "aws csv json" --test s3`, as CI's emulators job does.
+
+use futures::future::try_join_all;
+use std::{env, time::Duration};
+
+use amadeus::{
+	amadeus_core::file::{Directory, File, Page, Partition}, prelude::*, source::aws::RetryPolicy
+};
+
+const BUCKET: &str = "amadeus";
+
+fn options() -> S3Options {
+	let endpoint =
+		env::var("AMADEUS_S3_ENDPOINT").unwrap_or_else(|_| String::from("http://localhost:9000"));
+	S3Options::new().endpoint(&endpoint).verify_tls(false)
+}
+fn credentials() -> AwsCredentials {
+	AwsCredentials::AccessKey {
+		id: String::from("minioadmin"),
+		secret: String::from("minioadmin"),
+	}
+}
+fn directory(prefix: &str) -> S3Directory {
+	S3Directory::new_with(AwsRegion::UsEast1, BUCKET, prefix, credentials()).with_options(options())
+}
+
+async fn put(file: S3File, data: &[u8]) {
+	let page = file.create();
+	page.write(0, data.to_vec().into_boxed_slice())
+		.await
+		.unwrap();
+	page.flush().await.unwrap();
+}
+async fn get(file: S3File) -> Vec<u8> {
+	let page = file.pages().await.unwrap().pop().unwrap();
+	let len = page.len().await.unwrap();
+	page.read(0, len as usize).await.unwrap().into_vec()
+}
+
+#[tokio::test]
+async fn s3_write() {
+	let directory = directory("s3_write/");
+
+	// Large enough to need a multipart upload, written out of order
+	let data = (0..40 * 1024 * 1024_u32)
+		.map(|i| (i % 251) as u8)
+		.collect::<Vec<_>>();
+	let page = directory.file("large").create();
+	let chunk = 1024 * 1024;
+	try_join_all(
+		data.chunks(chunk)
+			.enumerate()
+			.rev()
+			.map(|(i, buf)| page.write((i * chunk) as u64, buf.to_vec().into_boxed_slice())),
+	)
+	.await
+	.unwrap();
+	page.flush().await.unwrap();
+
+	put(directory.file("small"), b"hello world").await;
+
+	assert!(get(directory.file("large")).await == data);
+	assert_eq!(get(directory.file("small")).await, b"hello world");
+
+	// Gaps are an error
+	let page = directory.file("gap").create();
+	page.write(1, vec![0].into_boxed_slice()).await.unwrap();
+	assert!(page.flush().await.is_err());
+}
+
+#[tokio::test]
+async fn s3_directory() {
+	let directory = directory("s3_directory/");
+	for key in &["a/1", "a/2", "b/1", "c"] {
+		put(directory.file(key), key.as_bytes()).await;
+	}
+
+	let partitions = directory.clone().partitions().await.unwrap();
+	assert_eq!(partitions.len(), 4);
+	let mut contents = Vec::new();
+	for partition in partitions {
+		let page = partition.pages().await.unwrap().pop().unwrap();
+		let len = page.len().await.unwrap();
+		contents.push(page.read(0, len as usize).await.unwrap().into_vec());
+	}
+	contents.sort();
+	assert_eq!(contents, vec![&b"a/1"[..], b"a/2", b"b/1", b"c"]);
+
+	let partitions = directory
+		.partitions_filter(|path| path.iter().next().map_or(true, |first| first != "a"))
+		.await
+		.unwrap();
+	assert_eq!(partitions.len(), 2);
+}
+
+#[tokio::test]
+async fn s3_options_serde() {
+	// Options travel with files to remote workers
+	let file = directory("s3_options_serde/").file("file");
+	put(file.clone(), b"remote").await;
+	let file: S3File = serde_json::from_str(&serde_json::to_string(&file).unwrap()).unwrap();
+	assert_eq!(get(file).await, b"remote");
+}
+
+#[tokio::test]
+async fn s3_errors() {
+	let options = options().retry_policy(
+		RetryPolicy::new()
+			.max_attempts(3)
+			.initial_backoff(Duration::from_millis(1)),
+	);
+	let file = S3File::new_with(
+		AwsRegion::UsEast1,
+		BUCKET,
+		"s3_errors/missing",
+		credentials(),
+	)
+	.with_options(options.clone());
+	assert!(file.pages().await.is_err());
+
+	let wrong_credentials = AwsCredentials::AccessKey {
+		id: String::from("minioadmin"),
+		secret: String::from("wrong"),
+	};
+	let file = S3File::new_with(
+		AwsRegion::UsEast1,
+		BUCKET,
+		"s3_errors/denied",
+		wrong_credentials,
+	)
+	.with_options(options.clone());
+	assert!(file.pages().await.is_err());
+
+	// Nothing listening, so every attempt fails to dispatch
+	let file = S3File::new_with(
+		AwsRegion::UsEast1,
+		BUCKET,
+		"s3_errors/unreachable",
+		credentials(),
+	)
+	.with_options(options.endpoint("http://localhost:1"));
+	assert!(file.pages().await.is_err());
+}
+
+#[tokio::test]
+async fn s3_glob() {
+	let directory = directory("s3_glob/");
+	for key in &[
+		"2020-01-01/a.gz",
+		"2020-01-01/b.csv",
+		"2020-02-01/a.gz",
+		"2020-05-01/a.gz",
+	] {
+		put(directory.file(key), key.as_bytes()).await;
+	}
+
+	let glob = S3Directory::glob_with(
+		AwsRegion::UsEast1,
+		&format!("s3://{}/s3_glob/2020-0[1-3]-*/*.gz", BUCKET),
+		credentials(),
+	)
+	.unwrap()
+	.with_options(options());
+	let mut contents = Vec::new();
+	for partition in glob.partitions().await.unwrap() {
+		let page = partition.pages().await.unwrap().pop().unwrap();
+		let len = page.len().await.unwrap();
+		contents.push(page.read(0, len as usize).await.unwrap().into_vec());
+	}
+	assert_eq!(contents, vec![&b"2020-01-01/a.gz"[..], b"2020-02-01/a.gz"]);
+
+	assert!(S3Directory::glob(AwsRegion::UsEast1, "bucket/key").is_err());
+}